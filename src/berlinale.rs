@@ -3,8 +3,10 @@
 //! Film page: https://www.berlinale.de/en/2026/programme/202608333.html
 //! Film pages embed JSON in a script (initial_result) with title, synopsis, cast, events, etc.
 
-use crate::{CinemaScraper, Film};
-use reqwest::{Client, header};
+use crate::{CinemaScraper, Film, HttpClient, ScrapeError, Showtime, showtime};
+use chrono::NaiveDate;
+use futures::future::join_all;
+use reqwest::header;
 use scraper::{Html, Selector};
 use std::collections::HashSet;
 
@@ -135,6 +137,20 @@ fn extract_film_urls_from_raw(html: &str, _base: &str) -> Vec<String> {
     v
 }
 
+/// Mid-festival date used to resolve day/month-only screening dates; the festival
+/// year comes from the film URL (/en/2026/programme/...), falling back to today.
+fn festival_reference_date(url: &str) -> NaiveDate {
+    url.split('/')
+        .find_map(|seg| {
+            let year = seg
+                .parse::<i32>()
+                .ok()
+                .filter(|y| (2000..=2100).contains(y))?;
+            NaiveDate::from_ymd_opt(year, 2, 15)
+        })
+        .unwrap_or_else(showtime::today)
+}
+
 /// Scraper for Berlinale programme (films on sale / in programme).
 pub struct BerlinaleScraper {
     listing_url: String,
@@ -148,7 +164,7 @@ impl BerlinaleScraper {
 
 #[async_trait::async_trait]
 impl CinemaScraper for BerlinaleScraper {
-    async fn fetch_films(&self, client: &Client) -> Result<Vec<Film>, Box<dyn std::error::Error>> {
+    async fn fetch_films(&self, client: &HttpClient) -> Result<Vec<Film>, ScrapeError> {
         let resp = client
             .get(self.listing_url.as_str())
             .header(header::USER_AGENT, USER_AGENT)
@@ -162,8 +178,7 @@ impl CinemaScraper for BerlinaleScraper {
             return Ok(Vec::new());
         }
 
-        let mut films = Vec::new();
-        for url in film_urls {
+        let details = film_urls.into_iter().map(|url| async move {
             let resp = match client
                 .get(&url)
                 .header(header::USER_AGENT, USER_AGENT)
@@ -171,372 +186,390 @@ impl CinemaScraper for BerlinaleScraper {
                 .await
             {
                 Ok(r) => r,
-                Err(_) => continue,
+                Err(_) => return None,
             };
             let resp = match resp.error_for_status() {
                 Ok(r) => r,
-                Err(_) => continue,
+                Err(_) => return None,
             };
             let body = match resp.text().await {
                 Ok(b) => b,
-                Err(_) => continue,
+                Err(_) => return None,
             };
-            let doc = Html::parse_document(&body);
-            let json = extract_initial_result_json(&body);
+            parse_film_page(&url, &body)
+        });
+        let films: Vec<Film> = join_all(details).await.into_iter().flatten().collect();
+
+        Ok(films)
+    }
+
+    fn rss_filename(&self) -> String {
+        "docs/feeds/berlinale.xml".to_string()
+    }
+}
 
-            let title = json
-                .as_ref()
-                .and_then(|j| j.get("title"))
-                .and_then(|t| t.as_str())
-                .map(String::from)
+/// A film page: title, poster, crew/cast, synopsis and screenings from the embedded
+/// `initial_result` JSON, with the visible page text as fallback.
+fn parse_film_page(url: &str, body: &str) -> Option<Film> {
+    let doc = Html::parse_document(body);
+    let json = extract_initial_result_json(body);
+    let reference = festival_reference_date(url);
+
+    let title = json
+        .as_ref()
+        .and_then(|j| j.get("title"))
+        .and_then(|t| t.as_str())
+        .map(String::from)
+        .or_else(|| {
+            Selector::parse("meta[property=\"og:title\"]")
+                .ok()
+                .and_then(|sel| {
+                    doc.select(&sel)
+                        .next()
+                        .and_then(|m| m.value().attr("content").map(String::from))
+                })
                 .or_else(|| {
-                    Selector::parse("meta[property=\"og:title\"]")
-                        .ok()
-                        .and_then(|sel| {
-                            doc.select(&sel)
-                                .next()
-                                .and_then(|m| m.value().attr("content").map(String::from))
+                    Selector::parse("h1").ok().and_then(|sel| {
+                        doc.select(&sel).next().map(|h| {
+                            h.text()
+                                .map(|t| t.trim())
+                                .filter(|t| !t.is_empty())
+                                .collect::<Vec<_>>()
+                                .join(" ")
                         })
-                        .or_else(|| {
-                            Selector::parse("h1").ok().and_then(|sel| {
-                                doc.select(&sel).next().map(|h| {
-                                    h.text()
-                                        .map(|t| t.trim())
-                                        .filter(|t| !t.is_empty())
-                                        .collect::<Vec<_>>()
-                                        .join(" ")
-                                })
-                            })
-                        })
-                })
-                .map(|t| {
-                    t.trim_end_matches(" | Berlinale")
-                        .trim_end_matches(" – Berlinale")
-                        .to_string()
+                    })
                 })
-                .and_then(|t| if t.is_empty() { None } else { Some(t) })
-                .unwrap_or_default();
-            if title.is_empty() || title.starts_with("https://") {
-                continue;
-            }
+        })
+        .map(|t| {
+            t.trim_end_matches(" | Berlinale")
+                .trim_end_matches(" – Berlinale")
+                .to_string()
+        })
+        .and_then(|t| if t.is_empty() { None } else { Some(t) })
+        .unwrap_or_default();
+    if title.is_empty() || title.starts_with("https://") {
+        return None;
+    }
 
-            let poster_url = json
-                .as_ref()
-                .and_then(|j| j.get("filmstills"))
-                .and_then(|a| a.as_array())
-                .and_then(|arr| {
-                    arr.iter().find_map(|s| {
-                        let uri = s.get("media")?.get("defaultImage")?.get("uri")?.as_str()?;
-                        if uri.contains("plakate") || uri.contains("poster") {
-                            Some(if uri.starts_with("http") {
-                                uri.to_string()
-                            } else {
-                                format!("{}{}", BASE, uri)
-                            })
-                        } else {
-                            None
-                        }
+    let poster_url = json
+        .as_ref()
+        .and_then(|j| j.get("filmstills"))
+        .and_then(|a| a.as_array())
+        .and_then(|arr| {
+            arr.iter().find_map(|s| {
+                let uri = s.get("media")?.get("defaultImage")?.get("uri")?.as_str()?;
+                if uri.contains("plakate") || uri.contains("poster") {
+                    Some(if uri.starts_with("http") {
+                        uri.to_string()
+                    } else {
+                        format!("{}{}", BASE, uri)
                     })
+                } else {
+                    None
+                }
+            })
+        })
+        .or_else(|| {
+            json.as_ref()
+                .and_then(|j| j.get("image"))
+                .and_then(|i| i.get("default"))
+                .and_then(|d| d.get("uri"))
+                .and_then(|u| u.as_str())
+                .map(|s| {
+                    if s.starts_with("http") {
+                        s.to_string()
+                    } else {
+                        format!("{}{}", BASE, s)
+                    }
                 })
-                .or_else(|| {
-                    json.as_ref()
-                        .and_then(|j| j.get("image"))
-                        .and_then(|i| i.get("default"))
-                        .and_then(|d| d.get("uri"))
-                        .and_then(|u| u.as_str())
-                        .map(|s| {
+        })
+        .or_else(|| {
+            Selector::parse("meta[property=\"og:image\"]")
+                .ok()
+                .and_then(|sel| {
+                    doc.select(&sel).next().and_then(|m| {
+                        m.value().attr("content").map(|s| {
+                            let s = s.trim();
                             if s.starts_with("http") {
                                 s.to_string()
+                            } else if s.starts_with('/') {
+                                format!("{}{}", BASE, s)
                             } else {
+                                format!("{}/{}", BASE, s)
+                            }
+                        })
+                    })
+                })
+        })
+        .or_else(|| {
+            Selector::parse("img[src*=\"berlinale\"], img[src*=\"programme\"]")
+                .ok()
+                .and_then(|sel| {
+                    doc.select(&sel).find_map(|img| {
+                        img.value().attr("src").map(|s| {
+                            let s = s.trim();
+                            if s.starts_with("http") {
+                                s.to_string()
+                            } else if s.starts_with('/') {
                                 format!("{}{}", BASE, s)
+                            } else {
+                                format!("{}/{}", BASE, s)
                             }
                         })
+                    })
                 })
+        });
+
+    let (mut running_time, mut cast, mut synopsis_parts, mut showtimes, mut director_for_title) =
+        if let Some(ref j) = json {
+            let rt = j
+                .get("meta")
+                .and_then(|m| m.as_array())
+                .and_then(|a| a.first())
+                .and_then(|s| s.as_str())
+                .and_then(|s| s.trim_end_matches('\'').trim().parse::<u32>().ok())
                 .or_else(|| {
-                    Selector::parse("meta[property=\"og:image\"]")
-                        .ok()
-                        .and_then(|sel| {
-                            doc.select(&sel).next().and_then(|m| {
-                                m.value().attr("content").map(|s| {
-                                    let s = s.trim();
-                                    if s.starts_with("http") {
-                                        s.to_string()
-                                    } else if s.starts_with('/') {
-                                        format!("{}{}", BASE, s)
-                                    } else {
-                                        format!("{}/{}", BASE, s)
-                                    }
-                                })
-                            })
+                    j.get("events")
+                        .and_then(|e| e.as_array())
+                        .and_then(|a| a.first())
+                        .and_then(|e| e.get("time"))
+                        .and_then(|t| t.get("durationInMinutes"))
+                        .and_then(|d| d.as_u64())
+                        .map(|n| n as u32)
+                });
+            let by_crew = j
+                .get("crewMembers")
+                .and_then(|c| c.as_array())
+                .and_then(|arr| {
+                    let parts: Vec<String> = arr
+                        .iter()
+                        .filter_map(|m| {
+                            let func = m.get("function")?.as_str()?;
+                            if func != "Director"
+                                && func != "Screenplay"
+                                && !func.eq_ignore_ascii_case("Screenplay based on")
+                            {
+                                return None;
+                            }
+                            let name =
+                                m.get("names")?.as_array()?.first()?.get("name")?.as_str()?;
+                            Some(format!("{} ({})", name, func))
                         })
+                        .collect();
+                    if parts.is_empty() {
+                        None
+                    } else {
+                        Some("by ".to_string() + &parts.join(", "))
+                    }
+                });
+            let cast_names = j.get("castMembers").and_then(|c| c.as_array()).map(|arr| {
+                arr.iter()
+                    .filter_map(|m| m.get("name").and_then(|n| n.as_str()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            });
+            let director_for_title = j
+                .get("crewMembers")
+                .and_then(|c| c.as_array())
+                .and_then(|arr| {
+                    arr.iter()
+                        .find(|m| m.get("function").and_then(|f| f.as_str()) == Some("Director"))
+                })
+                .and_then(|m| {
+                    m.get("names")?
+                        .as_array()?
+                        .first()?
+                        .get("name")?
+                        .as_str()
+                        .map(String::from)
                 })
                 .or_else(|| {
-                    Selector::parse("img[src*=\"berlinale\"], img[src*=\"programme\"]")
-                        .ok()
-                        .and_then(|sel| {
-                            doc.select(&sel).find_map(|img| {
-                                img.value().attr("src").map(|s| {
-                                    let s = s.trim();
-                                    if s.starts_with("http") {
-                                        s.to_string()
-                                    } else if s.starts_with('/') {
-                                        format!("{}{}", BASE, s)
-                                    } else {
-                                        format!("{}/{}", BASE, s)
-                                    }
-                                })
+                    j.get("reducedCrewMembers")
+                        .and_then(|r| r.as_array())
+                        .and_then(|arr| {
+                            arr.iter().find_map(|m| {
+                                m.get("name")
+                                    .and_then(|n| n.as_str())
+                                    .and_then(|s| s.strip_suffix(" (Director)").map(String::from))
                             })
                         })
                 });
-
-            let (
-                mut running_time,
-                mut cast,
-                mut synopsis_parts,
-                mut showtimes,
-                mut director_for_title,
-            ) = if let Some(ref j) = json {
-                let rt = j
-                    .get("meta")
-                    .and_then(|m| m.as_array())
-                    .and_then(|a| a.first())
-                    .and_then(|s| s.as_str())
-                    .and_then(|s| s.trim_end_matches('\'').trim().parse::<u32>().ok())
-                    .or_else(|| {
-                        j.get("events")
-                            .and_then(|e| e.as_array())
-                            .and_then(|a| a.first())
-                            .and_then(|e| e.get("time"))
-                            .and_then(|t| t.get("durationInMinutes"))
-                            .and_then(|d| d.as_u64())
-                            .map(|n| n as u32)
-                    });
-                let by_crew = j
-                    .get("crewMembers")
-                    .and_then(|c| c.as_array())
-                    .and_then(|arr| {
-                        let parts: Vec<String> = arr
-                            .iter()
-                            .filter_map(|m| {
-                                let func = m.get("function")?.as_str()?;
-                                if func != "Director"
-                                    && func != "Screenplay"
-                                    && !func.eq_ignore_ascii_case("Screenplay based on")
-                                {
-                                    return None;
-                                }
-                                let name =
-                                    m.get("names")?.as_array()?.first()?.get("name")?.as_str()?;
-                                Some(format!("{} ({})", name, func))
-                            })
-                            .collect();
-                        if parts.is_empty() {
-                            None
-                        } else {
-                            Some("by ".to_string() + &parts.join(", "))
-                        }
-                    });
-                let cast_names = j.get("castMembers").and_then(|c| c.as_array()).map(|arr| {
-                    arr.iter()
-                        .filter_map(|m| m.get("name").and_then(|n| n.as_str()))
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                });
-                let director_for_title = j
-                    .get("crewMembers")
-                    .and_then(|c| c.as_array())
-                    .and_then(|arr| {
-                        arr.iter().find(|m| {
-                            m.get("function").and_then(|f| f.as_str()) == Some("Director")
+            let cast_str = by_crew
+                .or_else(|| {
+                    j.get("reducedCrewMembers")
+                        .and_then(|r| r.as_array())
+                        .map(|arr| {
+                            "by ".to_string()
+                                + &arr
+                                    .iter()
+                                    .filter_map(|m| m.get("name").and_then(|n| n.as_str()))
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
                         })
-                    })
-                    .and_then(|m| {
-                        m.get("names")?
-                            .as_array()?
-                            .first()?
-                            .get("name")?
-                            .as_str()
-                            .map(String::from)
-                    })
-                    .or_else(|| {
-                        j.get("reducedCrewMembers")
-                            .and_then(|r| r.as_array())
-                            .and_then(|arr| {
-                                arr.iter().find_map(|m| {
-                                    m.get("name").and_then(|n| n.as_str()).and_then(|s| {
-                                        s.strip_suffix(" (Director)").map(String::from)
-                                    })
-                                })
-                            })
-                    });
-                let cast_str = by_crew
-                    .or_else(|| {
-                        j.get("reducedCrewMembers")
-                            .and_then(|r| r.as_array())
-                            .map(|arr| {
-                                "by ".to_string()
-                                    + &arr
-                                        .iter()
-                                        .filter_map(|m| m.get("name").and_then(|n| n.as_str()))
-                                        .collect::<Vec<_>>()
-                                        .join(", ")
-                            })
-                    })
-                    .map(|by_line| {
-                        if let Some(ref cn) = cast_names {
-                            if cn.is_empty() {
-                                by_line
-                            } else {
-                                format!("{} Cast: {}", by_line, cn)
-                            }
-                        } else {
+                })
+                .map(|by_line| {
+                    if let Some(ref cn) = cast_names {
+                        if cn.is_empty() {
                             by_line
+                        } else {
+                            format!("{} Cast: {}", by_line, cn)
                         }
-                    });
-                let syn = j
-                    .get("synopsis")
-                    .and_then(|s| s.as_str())
-                    .map(|s| {
-                        s.replace("<br />", "\n")
-                            .replace("<br/>", "\n")
-                            .trim()
-                            .to_string()
-                    })
-                    .unwrap_or_default();
-                let syn_vec = if syn.is_empty() {
-                    Vec::new()
-                } else {
-                    vec![syn]
-                };
-                let events: Vec<String> = j
-                    .get("events")
-                    .and_then(|e| e.as_array())
-                    .map(|arr| {
-                        arr.iter()
-                            .filter_map(|e| {
-                                let date = e
-                                    .get("displayDate")
-                                    .and_then(|d| d.get("dayAndMonth"))
-                                    .and_then(|s| s.as_str())
-                                    .unwrap_or("");
-                                let weekday = e
-                                    .get("displayDate")
-                                    .and_then(|d| d.get("weekday"))
-                                    .and_then(|s| s.as_str())
-                                    .unwrap_or("");
-                                let time = e
-                                    .get("time")
-                                    .and_then(|t| t.get("text"))
-                                    .and_then(|s| s.as_str())
-                                    .unwrap_or("");
-                                let venue =
-                                    e.get("venueHall").and_then(|s| s.as_str()).unwrap_or("");
-                                if date.is_empty() && time.is_empty() {
-                                    None
-                                } else {
-                                    Some(format!("{} {} {} - {}", weekday, date, time, venue))
-                                }
-                            })
-                            .collect()
-                    })
-                    .unwrap_or_default();
-                (rt, cast_str, syn_vec, events, director_for_title)
+                    } else {
+                        by_line
+                    }
+                });
+            let syn = j
+                .get("synopsis")
+                .and_then(|s| s.as_str())
+                .map(|s| {
+                    s.replace("<br />", "\n")
+                        .replace("<br/>", "\n")
+                        .trim()
+                        .to_string()
+                })
+                .unwrap_or_default();
+            let syn_vec = if syn.is_empty() {
+                Vec::new()
             } else {
-                (None, None, Vec::new(), Vec::new(), None)
+                vec![syn]
             };
+            let events: Vec<Showtime> = j
+                .get("events")
+                .and_then(|e| e.as_array())
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|e| {
+                            let date = e
+                                .get("displayDate")
+                                .and_then(|d| d.get("dayAndMonth"))
+                                .and_then(|s| s.as_str())
+                                .unwrap_or("");
+                            let time = e
+                                .get("time")
+                                .and_then(|t| t.get("text"))
+                                .and_then(|s| s.as_str())
+                                .unwrap_or("");
+                            let venue = e
+                                .get("venueHall")
+                                .and_then(|s| s.as_str())
+                                .map(String::from);
+                            let date = showtime::parse_date(date, reference)?;
+                            let time = showtime::parse_time(time)?;
+                            Showtime::at(date, time, chrono_tz::Europe::Berlin)
+                                .map(|st| st.with_hall(venue))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            (rt, cast_str, syn_vec, events, director_for_title)
+        } else {
+            (None, None, Vec::new(), Vec::new(), None)
+        };
 
-            if synopsis_parts.is_empty() || cast.is_none() || showtimes.is_empty() {
-                let all_text: Vec<String> = doc
-                    .root_element()
-                    .text()
-                    .map(|t| t.trim())
-                    .filter(|t| !t.is_empty())
-                    .map(String::from)
-                    .collect();
-                for (i, line) in all_text.iter().enumerate() {
-                    if running_time.is_none() && (line.contains(" min") || line == "min") {
-                        let num: String = line.chars().take_while(|c| c.is_ascii_digit()).collect();
-                        if !num.is_empty() {
-                            running_time = num.parse::<u32>().ok();
-                        }
-                    }
-                    if cast.is_none()
-                        && (line.eq_ignore_ascii_case("Director:")
-                            || line.eq_ignore_ascii_case("Regie:"))
-                        && let Some(next) = all_text.get(i + 1)
-                    {
-                        cast = Some(next.clone());
-                        director_for_title = Some(next.clone());
-                    }
-                    if cast.is_some()
-                        && line.eq_ignore_ascii_case("Cast:")
-                        && let Some(next) = all_text.get(i + 1)
-                    {
-                        let existing = cast.take().unwrap_or_default();
-                        cast = Some(if existing.is_empty() {
-                            next.clone()
-                        } else {
-                            format!("{}. {}", existing, next)
-                        });
-                    }
-                    if synopsis_parts.is_empty()
-                        && (line.eq_ignore_ascii_case("Synopsis")
-                            || line.eq_ignore_ascii_case("Plot"))
-                    {
-                        for s in all_text.iter().skip(i + 1).take(14) {
-                            if s.len() > 50
-                                && !s.starts_with("http")
-                                && !s.eq_ignore_ascii_case("Director:")
-                                && !s.eq_ignore_ascii_case("Cast:")
-                            {
-                                synopsis_parts.push(s.clone());
-                            } else if s.len() < 10 {
-                                break;
-                            }
-                        }
-                    }
-                    if showtimes.is_empty()
-                        && (line.contains("Screenings")
-                            || line.contains("Februar")
-                            || line.contains("February"))
-                        && (line.contains(':') || line.chars().any(|c| c.is_ascii_digit()))
+    if synopsis_parts.is_empty() || cast.is_none() || showtimes.is_empty() {
+        let all_text: Vec<String> = doc
+            .root_element()
+            .text()
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+            .map(String::from)
+            .collect();
+        for (i, line) in all_text.iter().enumerate() {
+            if running_time.is_none() && (line.contains(" min") || line == "min") {
+                let num: String = line.chars().take_while(|c| c.is_ascii_digit()).collect();
+                if !num.is_empty() {
+                    running_time = num.parse::<u32>().ok();
+                }
+            }
+            if cast.is_none()
+                && (line.eq_ignore_ascii_case("Director:") || line.eq_ignore_ascii_case("Regie:"))
+                && let Some(next) = all_text.get(i + 1)
+            {
+                cast = Some(next.clone());
+                director_for_title = Some(next.clone());
+            }
+            if cast.is_some()
+                && line.eq_ignore_ascii_case("Cast:")
+                && let Some(next) = all_text.get(i + 1)
+            {
+                let existing = cast.take().unwrap_or_default();
+                cast = Some(if existing.is_empty() {
+                    next.clone()
+                } else {
+                    format!("{}. {}", existing, next)
+                });
+            }
+            if synopsis_parts.is_empty()
+                && (line.eq_ignore_ascii_case("Synopsis") || line.eq_ignore_ascii_case("Plot"))
+            {
+                for s in all_text.iter().skip(i + 1).take(14) {
+                    if s.len() > 50
+                        && !s.starts_with("http")
+                        && !s.eq_ignore_ascii_case("Director:")
+                        && !s.eq_ignore_ascii_case("Cast:")
                     {
-                        showtimes.push(line.clone());
+                        synopsis_parts.push(s.clone());
+                    } else if s.len() < 10 {
+                        break;
                     }
                 }
             }
+            if showtimes.is_empty()
+                && (line.contains("Screenings")
+                    || line.contains("Februar")
+                    || line.contains("February"))
+                && let Some(date) = showtime::parse_date(line, reference)
+                && let Some(time) = showtime::parse_time(line)
+                && let Some(st) = Showtime::at(date, time, chrono_tz::Europe::Berlin)
+            {
+                showtimes.push(st);
+            }
+        }
+    }
 
-            let synopsis = if synopsis_parts.is_empty() {
-                None
-            } else {
-                Some(synopsis_parts.join("\n\n"))
-            };
-            let showtimes = if showtimes.is_empty() {
-                None
-            } else {
-                Some(showtimes)
-            };
+    let synopsis = if synopsis_parts.is_empty() {
+        None
+    } else {
+        Some(synopsis_parts.join("\n\n"))
+    };
+    let showtimes = if showtimes.is_empty() {
+        None
+    } else {
+        Some(showtimes)
+    };
 
-            let display_title = director_for_title
-                .as_ref()
-                .map(|d| format!("{} by {}", title.trim(), d))
-                .unwrap_or_else(|| title.clone());
-            films.push(Film {
-                title: display_title,
-                url: url.clone(),
-                poster_url,
-                cast,
-                release_date: None,
-                running_time,
-                synopsis,
-                showtimes,
-            });
-        }
+    let display_title = director_for_title
+        .as_ref()
+        .map(|d| format!("{} by {}", title.trim(), d))
+        .unwrap_or_else(|| title.clone());
+    Some(Film {
+        title: display_title,
+        url: url.to_string(),
+        poster_url,
+        cast,
+        director: director_for_title,
+        running_time,
+        synopsis,
+        showtimes,
+        ..Default::default()
+    })
+}
 
-        Ok(films)
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
 
-    fn rss_filename(&self) -> String {
-        "docs/feeds/berlinale.xml".to_string()
+    #[test]
+    fn parses_fixture() {
+        let listing = fixtures::load("berlinale", "on-sale-from-today.html");
+        let films: Vec<Film> = extract_film_urls(&listing, "")
+            .iter()
+            .filter_map(|url| {
+                let page = url.rsplit('/').next().unwrap();
+                parse_film_page(url, &fixtures::load("berlinale", page))
+            })
+            .collect();
+        fixtures::assert_films("berlinale", &films);
     }
 }
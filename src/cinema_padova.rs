@@ -1,30 +1,8 @@
-use crate::{CinemaScraper, Film};
-use chrono::{DateTime, Datelike};
-use reqwest::{Client, header};
+use crate::{CinemaScraper, Film, HttpClient, ScrapeError, Showtime};
+use chrono::DateTime;
+use reqwest::header;
 use serde::Deserialize;
 
-static IT_WEEKDAY: [&str; 7] = [
-    "lunedì",
-    "martedì",
-    "mercoledì",
-    "giovedì",
-    "venerdì",
-    "sabato",
-    "domenica",
-];
-
-fn format_showtime(dt: &DateTime<chrono::Utc>) -> String {
-    let wd = dt.weekday().num_days_from_monday() as usize;
-    let day_name = IT_WEEKDAY.get(wd).copied().unwrap_or("");
-    format!(
-        "{} {:02}/{:02} ore {}",
-        day_name,
-        dt.day(),
-        dt.month(),
-        dt.format("%H:%M")
-    )
-}
-
 const JSON_URL: &str = "https://www.cinemarex.it/pages/rexJsonCompact.php";
 const TICKET_BASE: &str = "https://ticket.cinebot.it/rex/titolo";
 const PROGRAMMAZIONE_FALLBACK: &str = "https://www.cinemarex.it/programmazione";
@@ -68,11 +46,31 @@ struct RexEvento {
     inizio: i64, // milliseconds since epoch
     #[serde(default)]
     id_cinebot: String, // e.g. "1028" -> https://ticket.cinebot.it/rex/titolo/1028
+    #[serde(default)]
+    sala: String, // e.g. "1"
+}
+
+impl RexEvento {
+    /// "Sala 1", when the event names its hall.
+    fn hall(&self) -> Option<String> {
+        let sala = self.sala.trim();
+        (!sala.is_empty()).then(|| format!("Sala {sala}"))
+    }
+
+    /// Cinebot booking page of this screening, if it is on sale online.
+    fn ticket_url(&self) -> Option<String> {
+        let id = self.id_cinebot.trim();
+        if id.is_empty() {
+            None
+        } else {
+            Some(format!("{}/{}", TICKET_BASE, id))
+        }
+    }
 }
 
 #[async_trait::async_trait]
 impl CinemaScraper for FeedPadovaScraper {
-    async fn fetch_films(&self, client: &Client) -> Result<Vec<Film>, Box<dyn std::error::Error>> {
+    async fn fetch_films(&self, client: &HttpClient) -> Result<Vec<Film>, ScrapeError> {
         let resp = client
             .get(JSON_URL)
             .header(
@@ -86,91 +84,90 @@ impl CinemaScraper for FeedPadovaScraper {
             .error_for_status()?;
 
         let body = resp.text().await?;
-        let data: RexResponse = serde_json::from_str(&body)?;
-
-        let mut films = Vec::new();
-        for t in data.titoli {
-            // Only include items categorized as "Film"
-            if t.categoria_film != "y" {
-                continue;
-            }
-
-            let title = t.titolo.trim().to_string();
-            if title.is_empty() {
-                continue;
-            }
-
-            // Prefer ticket page (working link); fallback to programmazione if no id_cinebot
-            let url = t
-                .eventi
-                .first()
-                .and_then(|e| {
-                    let id = e.id_cinebot.trim();
-                    if id.is_empty() {
-                        None
-                    } else {
-                        Some(format!("{}/{}", TICKET_BASE, id))
-                    }
-                })
-                .unwrap_or_else(|| PROGRAMMAZIONE_FALLBACK.to_string());
-
-            let running_time = t.durata.trim().parse::<u32>().ok();
-
-            let synopsis = t.descrizione.trim();
-            let synopsis = if synopsis.is_empty() {
-                None
-            } else {
-                Some(synopsis.to_string())
-            };
-
-            let cast = if t.autore.trim().is_empty() {
-                None
-            } else {
-                Some(format!("Regia: {}", t.autore.trim()))
-            };
-
-            let showtimes: Vec<String> = t
-                .eventi
-                .iter()
-                .filter_map(|e| {
-                    DateTime::from_timestamp_millis(e.inizio).map(|dt| format_showtime(&dt))
-                })
-                .collect();
-
-            // Avoid duplicate date+time (same film can have multiple eventi with same slot)
-            let showtimes: Vec<String> = {
-                let mut seen = std::collections::HashSet::new();
-                let mut out = Vec::new();
-                for s in showtimes {
-                    if seen.insert(s.clone()) {
-                        out.push(s);
-                    }
-                }
-                out
-            };
-
-            let showtimes = if showtimes.is_empty() {
-                None
-            } else {
-                Some(showtimes)
-            };
-
-            films.push(Film {
-                title,
-                url,
-                poster_url: None,
-                cast,
-                release_date: None,
-                running_time,
-                synopsis,
-                showtimes,
-            });
-        }
-
-        Ok(films)
+        parse_films(&body)
     }
 
     fn rss_filename(&self) -> String {
         "docs/feeds/padova.xml".to_string()
     }
 }
+
+/// Films (not theatre or concerts) from the `rexJsonCompact.php` response.
+fn parse_films(body: &str) -> Result<Vec<Film>, ScrapeError> {
+    let data: RexResponse = serde_json::from_str(body)?;
+
+    let mut films = Vec::new();
+    for t in data.titoli {
+        // Only include items categorized as "Film"
+        if t.categoria_film != "y" {
+            continue;
+        }
+
+        let title = t.titolo.trim().to_string();
+        if title.is_empty() {
+            continue;
+        }
+
+        // Prefer ticket page (working link); fallback to programmazione if no id_cinebot
+        let url = t
+            .eventi
+            .first()
+            .and_then(RexEvento::ticket_url)
+            .unwrap_or_else(|| PROGRAMMAZIONE_FALLBACK.to_string());
+
+        let running_time = t.durata.trim().parse::<u32>().ok();
+
+        let synopsis = t.descrizione.trim();
+        let synopsis = if synopsis.is_empty() {
+            None
+        } else {
+            Some(synopsis.to_string())
+        };
+
+        let director = Some(t.autore.trim().to_string()).filter(|a| !a.is_empty());
+
+        // Avoid duplicate slots (same film can have multiple eventi with same start and hall)
+        let mut seen = std::collections::HashSet::new();
+        let showtimes: Vec<Showtime> = t
+            .eventi
+            .iter()
+            .filter_map(|e| Some((DateTime::from_timestamp_millis(e.inizio)?, e)))
+            .map(|(dt, e)| (dt.with_timezone(&chrono_tz::Europe::Rome).fixed_offset(), e))
+            .filter(|(start, e)| seen.insert((*start, e.sala.trim())))
+            .map(|(start, e)| Showtime {
+                ticket_url: e.ticket_url(),
+                ..Showtime::new(start).with_hall(e.hall())
+            })
+            .collect();
+
+        let showtimes = if showtimes.is_empty() {
+            None
+        } else {
+            Some(showtimes)
+        };
+
+        films.push(Film {
+            title,
+            url,
+            director,
+            running_time,
+            synopsis,
+            showtimes,
+            ..Default::default()
+        });
+    }
+
+    Ok(films)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn parses_fixture() {
+        let films = parse_films(&fixtures::load("cinema_padova", "rexJsonCompact.json")).unwrap();
+        fixtures::assert_films("cinema_padova", &films);
+    }
+}
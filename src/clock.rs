@@ -0,0 +1,18 @@
+//! The run's idea of "now". Normally the wall clock; `--record` and `--replay` pin it
+//! to the moment the recording started, so dates the scrapers infer and timestamps in
+//! the feeds come out the same when a recorded run is replayed.
+
+use chrono::{DateTime, Utc};
+use std::sync::OnceLock;
+
+static PINNED: OnceLock<DateTime<Utc>> = OnceLock::new();
+
+/// Freeze [`now`] at `at` for the rest of the process. Only the first call counts.
+pub fn pin(at: DateTime<Utc>) {
+    let _ = PINNED.set(at);
+}
+
+/// The pinned time if there is one, else the wall clock.
+pub fn now() -> DateTime<Utc> {
+    PINNED.get().copied().unwrap_or_else(Utc::now)
+}
@@ -0,0 +1,463 @@
+//! Feed/cinema registry loaded from a TOML file (default: `cinemas.toml`).
+//! Each `[[feed]]` becomes `docs/feeds/<name>.xml`; each `[[feed.cinema]]` names a
+//! scraper `kind` plus its constructor parameters, so new cinemas that reuse an
+//! existing scraper only need a config entry.
+
+use crate::berlinale::BerlinaleScraper;
+use crate::cinema_edera::CinemaEderaScraper;
+use crate::cinema_padova::FeedPadovaScraper;
+use crate::cinema_trieste_scraper::CinemaTriesteScraper;
+use crate::cinemazero::CinemazeroScraper;
+use crate::eighteen_tickets::EighteenTicketsScraper;
+use crate::email::EmailConfig;
+use crate::enrico_pizzuti::EnricoPizzutiScraper;
+use crate::multi_astra::MultiAstraScraper;
+use crate::new_bev::NewBevScraper;
+use crate::porto_astra::PortoAstraScraper;
+use crate::rassegne_cristallo::RassegneScraperCristallo;
+use crate::rassegne_edera::RassegneScraperEdera;
+use crate::space_cinema::{SpaceCinemaScraper, Venue};
+use crate::vista_theatre::VistaTheatreScraper;
+use cinema_scrape::notify::{Event, Mastodon, Matrix, Telegram, Webhook};
+use cinema_scrape::{
+    CinemaScraper, FilterRules, Grouping, Locale, Notifier, WatchRule, showtime, slug,
+};
+use serde::Deserialize;
+use std::path::Path;
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    #[serde(rename = "feed")]
+    pub feeds: Vec<FeedConfig>,
+    /// Rules for the art-house variant of every feed; without them no variant is written.
+    #[serde(default)]
+    pub filter: Option<FilterRules>,
+    /// Films to look out for at every cinema, published in the watchlist feed.
+    #[serde(default, rename = "watch")]
+    pub watchlist: Vec<WatchRule>,
+    /// SMTP server and recipients of the `--digest` email.
+    #[serde(default)]
+    pub email: Option<EmailConfig>,
+    /// Chats told about new films after each run.
+    #[serde(default, rename = "notify")]
+    pub notifiers: Vec<NotifyConfig>,
+}
+
+/// One output feed and the cinemas merged into it.
+#[derive(Debug, Deserialize)]
+pub struct FeedConfig {
+    /// File stem: the feed is written to `docs/feeds/<name>.xml`.
+    pub name: String,
+    pub title: String,
+    pub link: String,
+    pub description: String,
+    #[serde(default = "default_language")]
+    pub language: Locale,
+    /// `"cinema"` (default): one item per cinema and film; `"film"`: one item per film
+    /// listing every cinema showing it.
+    #[serde(default)]
+    pub group_by: Grouping,
+    /// Also publish the feed grouped by film as `docs/feeds/<by_film>.xml`, leaving
+    /// this one as it is.
+    #[serde(default)]
+    pub by_film: Option<String>,
+    #[serde(rename = "cinema")]
+    pub cinemas: Vec<CinemaConfig>,
+}
+
+fn default_language() -> Locale {
+    Locale::Italian
+}
+
+fn default_days() -> u32 {
+    1
+}
+
+fn default_space_days() -> u32 {
+    7
+}
+
+/// A chat to tell about new films, and which ones.
+#[derive(Debug, Deserialize)]
+pub struct NotifyConfig {
+    /// `["new"]` (default): every new film; `["watchlist"]`: only watchlist hits.
+    #[serde(default = "default_events")]
+    pub events: Vec<Event>,
+    #[serde(default = "default_language")]
+    pub language: Locale,
+    #[serde(flatten)]
+    pub notifier: NotifierConfig,
+}
+
+fn default_events() -> Vec<Event> {
+    vec![Event::New]
+}
+
+/// Notifier kind and its parameters. Access tokens are read from the environment
+/// variable named by `token_env`, never from the config.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NotifierConfig {
+    Webhook {
+        url: String,
+    },
+    Telegram {
+        #[serde(default = "default_telegram_api")]
+        base_url: String,
+        chat_id: String,
+        #[serde(default = "default_telegram_token_env")]
+        token_env: String,
+    },
+    Matrix {
+        base_url: String,
+        room_id: String,
+        #[serde(default = "default_matrix_token_env")]
+        token_env: String,
+    },
+    Mastodon {
+        base_url: String,
+        #[serde(default = "default_visibility")]
+        visibility: String,
+        #[serde(default = "default_mastodon_token_env")]
+        token_env: String,
+    },
+}
+
+fn default_telegram_api() -> String {
+    "https://api.telegram.org".to_string()
+}
+
+fn default_telegram_token_env() -> String {
+    "TELEGRAM_BOT_TOKEN".to_string()
+}
+
+fn default_matrix_token_env() -> String {
+    "MATRIX_ACCESS_TOKEN".to_string()
+}
+
+fn default_mastodon_token_env() -> String {
+    "MASTODON_ACCESS_TOKEN".to_string()
+}
+
+fn default_visibility() -> String {
+    "unlisted".to_string()
+}
+
+impl NotifyConfig {
+    /// Fails when the access token's environment variable is not set.
+    pub fn build(&self) -> Result<Box<dyn Notifier>, String> {
+        let token = |var: &str| {
+            std::env::var(var).map_err(|_| format!("notifier needs an access token in ${var}"))
+        };
+        let locale = self.language;
+        Ok(match &self.notifier {
+            NotifierConfig::Webhook { url } => Box::new(Webhook {
+                url: url.clone(),
+                locale,
+            }),
+            NotifierConfig::Telegram {
+                base_url,
+                chat_id,
+                token_env,
+            } => Box::new(Telegram {
+                base_url: base_url.clone(),
+                token: token(token_env)?,
+                chat_id: chat_id.clone(),
+                locale,
+            }),
+            NotifierConfig::Matrix {
+                base_url,
+                room_id,
+                token_env,
+            } => Box::new(Matrix {
+                base_url: base_url.clone(),
+                room_id: room_id.clone(),
+                access_token: token(token_env)?,
+                locale,
+            }),
+            NotifierConfig::Mastodon {
+                base_url,
+                visibility,
+                token_env,
+            } => Box::new(Mastodon {
+                base_url: base_url.clone(),
+                access_token: token(token_env)?,
+                visibility: visibility.clone(),
+                locale,
+            }),
+        })
+    }
+}
+
+/// A cinema inside a feed: display name (used as item category/prefix) plus scraper.
+#[derive(Debug, Deserialize)]
+pub struct CinemaConfig {
+    pub name: String,
+    /// A failure of this cinema makes the whole run exit non-zero.
+    #[serde(default)]
+    pub required: bool,
+    /// Zero films is a normal outcome (festivals off-season, rassegne between cycles).
+    #[serde(default)]
+    pub allow_empty: bool,
+    #[serde(flatten)]
+    pub scraper: ScraperConfig,
+}
+
+/// Scraper kind and its constructor parameters.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ScraperConfig {
+    SpaceCinema {
+        cinema_id: u32,
+        /// Days of programme to fetch, starting today (or `SHOWING_DATE`).
+        #[serde(default = "default_space_days")]
+        days: u32,
+    },
+    CinemaEdera {
+        url: String,
+    },
+    CinemaRex {
+        url: String,
+    },
+    CinemaTrieste,
+    Cinemazero {
+        url: String,
+    },
+    /// Any cinema on 18tickets: `subdomain` of `<subdomain>.18tickets.it`, and how
+    /// many days of programme to fetch starting today.
+    EighteenTickets {
+        subdomain: String,
+        #[serde(default = "default_days")]
+        days: u32,
+    },
+    EnricoPizzuti {
+        url: String,
+    },
+    MultiAstra {
+        url: String,
+    },
+    NewBev,
+    PortoAstra {
+        url: String,
+    },
+    RassegneCristallo {
+        url: String,
+    },
+    RassegneEdera {
+        url: String,
+    },
+    Berlinale {
+        url: String,
+    },
+    VistaTheatre {
+        url: String,
+    },
+}
+
+impl ScraperConfig {
+    pub fn build(&self) -> Box<dyn CinemaScraper + Send + Sync> {
+        match self {
+            ScraperConfig::SpaceCinema { cinema_id, days } => {
+                // SHOWING_DATE may be "2026-02-09" or the API's "2026-02-09T00:00:00".
+                let first_date = std::env::var("SHOWING_DATE")
+                    .ok()
+                    .and_then(|d| chrono::NaiveDate::parse_from_str(d.get(..10)?, "%Y-%m-%d").ok())
+                    .unwrap_or_else(showtime::today);
+                Box::new(SpaceCinemaScraper::new(*cinema_id, first_date, *days))
+            }
+            ScraperConfig::CinemaEdera { url } => Box::new(CinemaEderaScraper::new(url.clone())),
+            ScraperConfig::CinemaRex { url } => Box::new(FeedPadovaScraper::new(url.clone())),
+            ScraperConfig::CinemaTrieste => Box::new(CinemaTriesteScraper::new()),
+            ScraperConfig::Cinemazero { url } => Box::new(CinemazeroScraper::new(url.clone())),
+            ScraperConfig::EighteenTickets { subdomain, days } => {
+                Box::new(EighteenTicketsScraper::new(subdomain, *days))
+            }
+            ScraperConfig::EnricoPizzuti { url } => {
+                Box::new(EnricoPizzutiScraper::new(url.clone()))
+            }
+            ScraperConfig::MultiAstra { url } => Box::new(MultiAstraScraper::new(url.clone())),
+            ScraperConfig::NewBev => Box::new(NewBevScraper::new()),
+            ScraperConfig::PortoAstra { url } => Box::new(PortoAstraScraper::new(url.clone())),
+            ScraperConfig::RassegneCristallo { url } => {
+                Box::new(RassegneScraperCristallo::new(url.clone()))
+            }
+            ScraperConfig::RassegneEdera { url } => {
+                Box::new(RassegneScraperEdera::new(url.clone()))
+            }
+            ScraperConfig::Berlinale { url } => Box::new(BerlinaleScraper::new(url.clone())),
+            ScraperConfig::VistaTheatre { url } => Box::new(VistaTheatreScraper::new(url.clone())),
+        }
+    }
+}
+
+/// Feeds for the Space Cinema venues picked on the command line with `--space`:
+/// every venue whose name or city matches one of `queries`. Each venue gets a feed
+/// `space-<venue>`, or with `merged` they all go into one feed of that name,
+/// grouped by film.
+pub fn space_feeds(
+    venues: &[Venue],
+    queries: &[String],
+    merged: Option<&str>,
+) -> Result<Vec<FeedConfig>, String> {
+    let mut picked: Vec<&Venue> = Vec::new();
+    for query in queries {
+        let matching: Vec<&Venue> = venues.iter().filter(|v| v.matches(query)).collect();
+        if matching.is_empty() {
+            return Err(format!(
+                "no Space Cinema venue matches '{query}' (see --space-venues)"
+            ));
+        }
+        for venue in matching {
+            if !picked.contains(&venue) {
+                picked.push(venue);
+            }
+        }
+    }
+
+    let feed = |name: String, title: String, group_by, venues: &[&Venue]| FeedConfig {
+        name,
+        title,
+        link: "https://www.thespacecinema.it/".to_string(),
+        description: format!(
+            "Programmazione The Space Cinema: {}.",
+            venues
+                .iter()
+                .map(|v| format!("{} ({})", v.name, v.city))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        language: default_language(),
+        group_by,
+        by_film: None,
+        cinemas: venues
+            .iter()
+            .map(|v| CinemaConfig {
+                name: format!("The Space Cinema - {}", v.name),
+                required: false,
+                allow_empty: false,
+                scraper: ScraperConfig::SpaceCinema {
+                    cinema_id: v.id,
+                    days: default_space_days(),
+                },
+            })
+            .collect(),
+    };
+
+    Ok(match merged {
+        Some(name) => vec![feed(
+            name.to_string(),
+            "The Space Cinema".to_string(),
+            Grouping::Film,
+            &picked,
+        )],
+        None => picked
+            .iter()
+            .map(|v| {
+                feed(
+                    format!("space-{}", slug(&v.name)),
+                    format!("The Space Cinema {}", v.name),
+                    Grouping::Cinema,
+                    &[v],
+                )
+            })
+            .collect(),
+    })
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read config {}: {e}", path.display()))?;
+        let config: Config =
+            toml::from_str(&text).map_err(|e| format!("invalid config {}: {e}", path.display()))?;
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cinema_scrape::filter::Reason;
+    use cinema_scrape::{Film, FilmFilter};
+
+    #[test]
+    fn shipped_config_parses() {
+        let config = Config::load(Path::new("cinemas.toml")).unwrap();
+        let names: Vec<&str> = config.feeds.iter().map(|f| f.name.as_str()).collect();
+        assert!(names.contains(&"multisala"));
+        // Published per cinema; the by-film layout is a feed of its own.
+        let multisala = &config.feeds[0];
+        assert_eq!(multisala.group_by, Grouping::Cinema);
+        assert_eq!(multisala.by_film.as_deref(), Some("multisala-film"));
+        assert!(config.feeds.iter().all(|f| !f.cinemas.is_empty()));
+    }
+
+    #[test]
+    fn shipped_filter_keeps_films_named_like_franchises() {
+        let config = Config::load(Path::new("cinemas.toml")).unwrap();
+        let filter = FilmFilter::new(config.filter.as_ref().unwrap()).unwrap();
+        let check = |title: &str| {
+            filter.check(&Film {
+                title: title.to_string(),
+                ..Default::default()
+            })
+        };
+        assert_eq!(check("I Saw the TV Glow"), None);
+        assert_eq!(check("Frozen River"), None);
+        assert_eq!(check("Frozen 2"), Some(Reason::Sequel));
+        assert!(check("Toy Story 5").is_some());
+    }
+
+    #[test]
+    fn space_feeds_per_venue_or_merged() {
+        let venue = |id, name: &str, city: &str| Venue {
+            id,
+            name: name.to_string(),
+            city: city.to_string(),
+        };
+        let venues = [
+            venue(1009, "Silea", "Treviso"),
+            venue(1014, "Roma Parco de' Medici", "Roma"),
+            venue(1022, "Roma Moderno", "Roma"),
+        ];
+        let queries = ["roma".to_string(), "Moderno".to_string()];
+
+        let feeds = space_feeds(&venues, &queries, None).unwrap();
+        let names: Vec<&str> = feeds.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["space-roma-parco-de-medici", "space-roma-moderno"]);
+
+        let feeds = space_feeds(&venues, &queries, Some("space-roma")).unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].cinemas.len(), 2);
+        assert_eq!(feeds[0].group_by, Grouping::Film);
+
+        assert!(space_feeds(&venues, &["Bari".to_string()], None).is_err());
+    }
+
+    #[test]
+    fn notifiers_parse_and_need_their_token() {
+        let config: Config = toml::from_str(
+            r#"
+            feed = []
+
+            [[notify]]
+            kind = "webhook"
+            url = "http://localhost:9000/hook"
+
+            [[notify]]
+            kind = "telegram"
+            chat_id = "-1001234567890"
+            token_env = "CINEMA_SCRAPE_TEST_UNSET_TOKEN"
+            events = ["watchlist"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.notifiers[0].events, [Event::New]);
+        assert_eq!(config.notifiers[0].build().unwrap().name(), "webhook");
+        assert!(matches!(
+            config.notifiers[1].notifier,
+            NotifierConfig::Telegram { ref base_url, .. } if base_url == "https://api.telegram.org"
+        ));
+        assert!(config.notifiers[1].build().is_err());
+    }
+}
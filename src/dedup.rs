@@ -0,0 +1,268 @@
+//! Cross-cinema film matching: recognises "ODISSEA (V.O.)" at one cinema and
+//! "Odissea" at another as the same film, so a feed can publish one item per film.
+
+use crate::Film;
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+/// Running times within this many minutes of each other are considered equal
+/// (cinemas round differently, some include trailers).
+pub const RUNTIME_TOLERANCE: u32 = 10;
+
+/// Years within one of each other are considered equal: some cinemas give the
+/// production year, others the Italian release, often the year after.
+const YEAR_TOLERANCE: u32 = 1;
+
+/// Words that mark a version or format rather than the film itself.
+const VERSION_WORDS: &[&str] = &[
+    "3d",
+    "2d",
+    "4k",
+    "imax",
+    "atmos",
+    "vo",
+    "vos",
+    "ov",
+    "omu",
+    "sub",
+    "ita",
+    "eng",
+    "versione",
+    "originale",
+    "lingua",
+    "sottotitolato",
+    "sottotitoli",
+];
+
+/// Matching key for a title: accents folded, lowercase, punctuation dropped,
+/// bracketed notes ("(2025)", "[V.O.]") and version words removed.
+/// "L'Odissea (V.O. sub ITA)" -> "l odissea".
+pub fn normalize_title(title: &str) -> String {
+    let folded: String = title
+        .nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .collect::<String>()
+        .to_lowercase();
+    let mut plain = String::with_capacity(folded.len());
+    let mut depth = 0usize;
+    for c in folded.chars() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            // "v.o." -> "vo"
+            '.' => {}
+            _ if depth > 0 => {}
+            c if c.is_alphanumeric() => plain.push(c),
+            _ => plain.push(' '),
+        }
+    }
+    plain
+        .split_whitespace()
+        .filter(|w| !VERSION_WORDS.contains(w))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Year of the film: [`Film::year`] or a "(2025)" in the title. Never `release_date`,
+/// where some cinemas (Pizzuti, Cristallo) write the date of the screening.
+pub fn year(film: &Film) -> Option<u32> {
+    if film.year.is_some() {
+        return film.year;
+    }
+    film.title
+        .split(['(', ')', '[', ']'])
+        .skip(1)
+        .step_by(2)
+        .find_map(|inner| parse_year(inner.trim()))
+}
+
+fn parse_year(s: &str) -> Option<u32> {
+    let year: u32 = s.parse().ok().filter(|_| s.len() == 4)?;
+    (1890..=2100).contains(&year).then_some(year)
+}
+
+/// Normalised director: [`Film::director`], or else a "Regia: X" / "Director: X"
+/// part a scraper put into `cast`.
+pub fn director(film: &Film) -> Option<String> {
+    if let Some(ref director) = film.director {
+        return Some(normalize_title(director)).filter(|d| !d.is_empty());
+    }
+    let cast = film.cast.as_deref()?;
+    ["Regia:", "Regia di", "Director:", "Directed by"]
+        .iter()
+        .find_map(|label| cast.find(label).map(|i| &cast[i + label.len()..]))
+        .map(|rest| rest.split(['|', ',', ';', '\n']).next().unwrap_or(""))
+        .map(normalize_title)
+        .filter(|d| !d.is_empty())
+}
+
+/// Same normalised title, and no contradiction in year, director or running time
+/// (a field missing on either side never prevents a match).
+pub fn same_film(a: &Film, b: &Film, runtime_tolerance: u32) -> bool {
+    fn compatible<T>(a: Option<T>, b: Option<T>, eq: impl Fn(T, T) -> bool) -> bool {
+        match (a, b) {
+            (Some(a), Some(b)) => eq(a, b),
+            _ => true,
+        }
+    }
+    let title = normalize_title(&a.title);
+    !title.is_empty()
+        && title == normalize_title(&b.title)
+        && compatible(year(a), year(b), |x, y| x.abs_diff(y) <= YEAR_TOLERANCE)
+        && compatible(director(a), director(b), |x, y| x == y)
+        && compatible(a.running_time, b.running_time, |x, y| {
+            x.abs_diff(y) <= runtime_tolerance
+        })
+}
+
+/// One film and every cinema showing it, in source order.
+pub struct FilmGroup<'a> {
+    pub showings: Vec<(&'a str, &'a Film)>,
+}
+
+impl<'a> FilmGroup<'a> {
+    /// The entry the group was started from; its title and URL name the group.
+    pub fn film(&self) -> &'a Film {
+        self.showings[0].1
+    }
+
+    pub fn cinemas(&self) -> Vec<&'a str> {
+        let mut cinemas: Vec<&str> = Vec::new();
+        for (cinema, _) in &self.showings {
+            if !cinemas.contains(cinema) {
+                cinemas.push(cinema);
+            }
+        }
+        cinemas
+    }
+
+    /// The group's metadata: each field from the first cinema that has it.
+    /// Showtimes stay per cinema and are left out.
+    pub fn merged(&self) -> Film {
+        let first = |f: fn(&Film) -> &Option<String>| {
+            self.showings.iter().find_map(|(_, film)| f(film).clone())
+        };
+        Film {
+            title: self.film().title.clone(),
+            url: self.film().url.clone(),
+            poster_url: first(|f| &f.poster_url),
+            cast: first(|f| &f.cast),
+            director: first(|f| &f.director),
+            release_date: first(|f| &f.release_date),
+            year: self.showings.iter().find_map(|(_, f)| f.year),
+            country: first(|f| &f.country),
+            genres: self
+                .showings
+                .iter()
+                .map(|(_, f)| &f.genres)
+                .find(|g| !g.is_empty())
+                .cloned()
+                .unwrap_or_default(),
+            original_title: first(|f| &f.original_title),
+            language: first(|f| &f.language),
+            running_time: self.showings.iter().find_map(|(_, f)| f.running_time),
+            synopsis: first(|f| &f.synopsis),
+            // The film dates from its first appearance at any of the cinemas.
+            first_seen: self.showings.iter().filter_map(|(_, f)| f.first_seen).min(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Group the films of every cinema so that each film appears once.
+pub fn group_films<'a>(
+    sources: &[(&'a str, &'a [Film])],
+    runtime_tolerance: u32,
+) -> Vec<FilmGroup<'a>> {
+    let mut groups: Vec<FilmGroup> = Vec::new();
+    for (cinema, films) in sources {
+        for film in *films {
+            match groups
+                .iter_mut()
+                .find(|g| same_film(g.film(), film, runtime_tolerance))
+            {
+                Some(group) => group.showings.push((cinema, film)),
+                None => groups.push(FilmGroup {
+                    showings: vec![(cinema, film)],
+                }),
+            }
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn film(title: &str, running_time: Option<u32>, cast: Option<&str>) -> Film {
+        Film {
+            title: title.to_string(),
+            url: format!("https://example.com/{}", crate::slug(title)),
+            cast: cast.map(String::from),
+            running_time,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn matches_across_cinemas() {
+        assert_eq!(normalize_title("L'Odissea (V.O. sub ITA)"), "l odissea");
+        assert_eq!(normalize_title("ODISSEA - 3D"), "odissea");
+        assert_eq!(normalize_title("Amélie [2001]"), "amelie");
+
+        let space = [
+            film("ODISSEA", Some(150), None),
+            film("Stalker", None, None),
+        ];
+        let edera = [film(
+            "Odissea (v.o.)",
+            Some(143),
+            Some("Regia: Christopher Nolan"),
+        )];
+        let manzoni = [film("Odissea", Some(95), None)];
+        let sources: [(&str, &[Film]); 3] =
+            [("Space", &space), ("Edera", &edera), ("Manzoni", &manzoni)];
+        let groups = group_films(&sources, RUNTIME_TOLERANCE);
+
+        // 150 vs 143 minutes is the same film; 95 minutes is another one.
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].cinemas(), ["Space", "Edera"]);
+        assert_eq!(groups[0].merged().running_time, Some(150));
+        assert!(groups[0].merged().cast.unwrap().contains("Nolan"));
+        assert_eq!(groups[2].cinemas(), ["Manzoni"]);
+
+        assert!(!same_film(
+            &film("Odissea (1997)", None, None),
+            &film("Odissea (2026)", None, None),
+            RUNTIME_TOLERANCE
+        ));
+        let nolan = film("Odissea", None, Some("Regia: Christopher Nolan"));
+        let other = film(
+            "Odissea",
+            None,
+            Some("Genere: Drammatico | Regia: Andrej Končalovskij"),
+        );
+        assert!(!same_film(&nolan, &other, RUNTIME_TOLERANCE));
+    }
+
+    #[test]
+    fn production_and_release_years_match() {
+        let produced = Film {
+            year: Some(2024),
+            ..film("Anora", None, None)
+        };
+        let released = Film {
+            year: Some(2025),
+            ..film("Anora", None, None)
+        };
+        assert!(same_film(&produced, &released, RUNTIME_TOLERANCE));
+
+        // A screening date says nothing about the film's year.
+        let screened = Film {
+            release_date: Some("Martedì 10 Febbraio 2026 ore 21:00".to_string()),
+            ..film("Anora", None, None)
+        };
+        assert!(same_film(&produced, &screened, RUNTIME_TOLERANCE));
+    }
+}
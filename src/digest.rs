@@ -0,0 +1,223 @@
+//! Email digest: the programme of the coming day or week, grouped by cinema and then
+//! by day, as an HTML and a plain-text body built from the same films as the feeds.
+
+use crate::site::{booking_link, esc, short_time};
+use crate::{Film, Locale, Showtime, showtime};
+use chrono::{Duration, NaiveDate};
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+/// How far ahead a digest looks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    /// Today's screenings.
+    Daily,
+    /// Screenings of the next seven days, today included.
+    Weekly,
+}
+
+impl Period {
+    pub fn days(self) -> i64 {
+        match self {
+            Period::Daily => 1,
+            Period::Weekly => 7,
+        }
+    }
+}
+
+impl FromStr for Period {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "daily" => Ok(Period::Daily),
+            "weekly" => Ok(Period::Weekly),
+            other => Err(format!("unknown digest period '{other}' (daily, weekly)")),
+        }
+    }
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Period::Daily => "daily",
+            Period::Weekly => "weekly",
+        })
+    }
+}
+
+/// A rendered digest, ready to be sent.
+#[derive(Debug, Clone)]
+pub struct Digest {
+    pub subject: String,
+    pub text: String,
+    pub html: String,
+}
+
+/// One cinema's films per day: day -> (film, that day's screenings), by first screening.
+type CinemaDays<'a> = BTreeMap<NaiveDate, Vec<(&'a Film, Vec<&'a Showtime>)>>;
+
+/// Digest of the screenings from `from` over `period`, per cinema of `sources`.
+/// Films without published times are listed after each cinema's days. Returns
+/// `None` when no cinema has anything to show.
+pub fn render_digest(
+    sources: &[(&str, &[Film])],
+    period: Period,
+    from: NaiveDate,
+    locale: Locale,
+) -> Option<Digest> {
+    let until = from + Duration::days(period.days());
+    let first_day = showtime::format_day(from, locale);
+    let (subject, no_showtimes) = match (locale, period) {
+        (Locale::Italian, Period::Daily) => (format!("Programma di {first_day}"), NO_TIMES_IT),
+        (Locale::Italian, Period::Weekly) => (
+            format!("Programma della settimana da {first_day}"),
+            NO_TIMES_IT,
+        ),
+        (Locale::English, Period::Daily) => (format!("Programme for {first_day}"), NO_TIMES_EN),
+        (Locale::English, Period::Weekly) => (
+            format!("Programme for the week from {first_day}"),
+            NO_TIMES_EN,
+        ),
+    };
+
+    let mut text = format!("{subject}\n");
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{}</title>\n</head>\n<body style=\"font-family: sans-serif\">\n<h1>{}</h1>\n",
+        locale.code(),
+        esc(&subject),
+        esc(&subject)
+    );
+    let mut anything = false;
+    for (cinema, films) in sources {
+        let mut days: CinemaDays = BTreeMap::new();
+        let mut undated: Vec<&Film> = Vec::new();
+        for film in *films {
+            let showtimes = film.showtimes.as_deref().unwrap_or_default();
+            if showtimes.is_empty() {
+                undated.push(film);
+                continue;
+            }
+            let mut per_day: BTreeMap<NaiveDate, Vec<&Showtime>> = BTreeMap::new();
+            for st in showtimes {
+                let day = st.start.date_naive();
+                if (from..until).contains(&day) {
+                    per_day.entry(day).or_default().push(st);
+                }
+            }
+            for (day, sts) in per_day {
+                days.entry(day).or_default().push((film, sts));
+            }
+        }
+        if days.is_empty() && undated.is_empty() {
+            continue;
+        }
+        anything = true;
+
+        let _ = write!(text, "\n== {cinema} ==\n");
+        let _ = writeln!(html, "<h2>{}</h2>", esc(cinema));
+        for (day, mut films) in days {
+            films.sort_by_key(|(_, sts)| sts.first().map(|s| s.start));
+            let day = showtime::format_day(day, locale);
+            let _ = write!(text, "\n{day}\n");
+            let _ = writeln!(html, "<h3>{}</h3>\n<ul>", esc(&day));
+            for (film, sts) in films {
+                let times: Vec<String> = sts.iter().map(|s| short_time(s, locale)).collect();
+                let _ = writeln!(
+                    text,
+                    "  {} - {}\n    {}",
+                    film.title,
+                    times.join(", "),
+                    film.url
+                );
+                let times: Vec<String> = sts
+                    .iter()
+                    .map(|s| booking_link(s, esc(&short_time(s, locale))))
+                    .collect();
+                let _ = writeln!(
+                    html,
+                    "<li><a href=\"{}\">{}</a> &middot; {}</li>",
+                    esc(&film.url),
+                    esc(&film.title),
+                    times.join(", ")
+                );
+            }
+            html.push_str("</ul>\n");
+        }
+        if !undated.is_empty() {
+            let _ = write!(text, "\n{no_showtimes}\n");
+            let _ = writeln!(html, "<h3>{no_showtimes}</h3>\n<ul>");
+            for film in undated {
+                let _ = writeln!(text, "  {}\n    {}", film.title, film.url);
+                let _ = writeln!(
+                    html,
+                    "<li><a href=\"{}\">{}</a></li>",
+                    esc(&film.url),
+                    esc(&film.title)
+                );
+            }
+            html.push_str("</ul>\n");
+        }
+    }
+    html.push_str("</body>\n</html>\n");
+    anything.then_some(Digest {
+        subject,
+        text,
+        html,
+    })
+}
+
+const NO_TIMES_IT: &str = "In programmazione (orari non disponibili)";
+const NO_TIMES_EN: &str = "Now showing (no times published)";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveTime;
+
+    fn film(title: &str, days: &[u32]) -> Film {
+        let at = |d| {
+            let date = NaiveDate::from_ymd_opt(2026, 2, d).unwrap();
+            let time = NaiveTime::from_hms_opt(20, 30, 0).unwrap();
+            Showtime::at(date, time, chrono_tz::Europe::Rome).unwrap()
+        };
+        Film {
+            title: title.to_string(),
+            url: format!("https://example.com/{title}"),
+            showtimes: Some(days.iter().map(|&d| at(d)).collect()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn groups_by_cinema_and_day() {
+        let edera = [film("Stalker", &[9, 10]), film("Solaris", &[20])];
+        let rex = [film("Lo specchio", &[11])];
+        let sources: [(&str, &[Film]); 2] = [("Cinema Edera", &edera), ("Cinema Rex", &rex)];
+        let monday = NaiveDate::from_ymd_opt(2026, 2, 9).unwrap();
+
+        let daily = render_digest(&sources, Period::Daily, monday, Locale::Italian).unwrap();
+        assert_eq!(daily.subject, "Programma di Lunedì 9 Febbraio");
+        assert!(
+            daily
+                .text
+                .contains("== Cinema Edera ==\n\nLunedì 9 Febbraio\n  Stalker - 20:30")
+        );
+        assert!(!daily.text.contains("Cinema Rex"));
+        assert!(!daily.text.contains("Solaris"));
+
+        let weekly = render_digest(&sources, Period::Weekly, monday, Locale::Italian).unwrap();
+        assert!(weekly.text.contains("Martedì 10 Febbraio"));
+        assert!(weekly.html.contains("<h2>Cinema Rex</h2>"));
+        assert!(
+            weekly
+                .html
+                .contains("<a href=\"https://example.com/Lo specchio\">Lo specchio</a>")
+        );
+
+        let sunday = NaiveDate::from_ymd_opt(2026, 2, 15).unwrap();
+        assert!(render_digest(&sources, Period::Daily, sunday, Locale::Italian).is_none());
+    }
+}
@@ -0,0 +1,444 @@
+//! Scraper for cinemas that sell tickets through the 18tickets platform
+//! (e.g. Cinergia Conegliano, Cineplex Moderno Due Carrare).
+//! Listing: https://<subdomain>.18tickets.it/?ref_date=YYYY-MM-DD  (#movie-list has film links)
+//! Film page: https://<subdomain>.18tickets.it/film/41324?ref_date=YYYY-MM-DD
+
+use crate::{CinemaScraper, Film, HttpClient, ScrapeError, Showtime, merge_days, showtime};
+use chrono::{Duration, NaiveDate};
+use futures::future::join_all;
+use reqwest::header;
+use scraper::{ElementRef, Html, Selector};
+use std::collections::HashSet;
+
+const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) \
+     AppleWebKit/537.36 (KHTML, like Gecko) Chrome/143.0.0.0 Safari/537.36";
+
+const MONTHS: [&str; 24] = [
+    "gennaio",
+    "febbraio",
+    "marzo",
+    "aprile",
+    "maggio",
+    "giugno",
+    "luglio",
+    "agosto",
+    "settembre",
+    "ottobre",
+    "novembre",
+    "dicembre",
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+const WEEKDAYS: [&str; 14] = [
+    "lunedì",
+    "martedì",
+    "mercoledì",
+    "giovedì",
+    "venerdì",
+    "sabato",
+    "domenica",
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+/// Scraper for one cinema on 18tickets, identified by its subdomain
+/// (`coneglianocinergia` for https://coneglianocinergia.18tickets.it).
+pub struct EighteenTicketsScraper {
+    subdomain: String,
+    base_url: String,
+    days: u32,
+}
+
+impl EighteenTicketsScraper {
+    /// `days` consecutive programme days are fetched, starting today.
+    pub fn new(subdomain: &str, days: u32) -> Self {
+        let subdomain = subdomain.trim_matches('.').to_string();
+        Self {
+            base_url: format!("https://{}.18tickets.it", subdomain),
+            subdomain,
+            days: days.max(1),
+        }
+    }
+
+    async fn get(&self, client: &HttpClient, url: &str) -> Result<String, ScrapeError> {
+        let resp = client
+            .get(url)
+            .header(header::USER_AGENT, USER_AGENT)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(resp.text().await?)
+    }
+}
+
+#[async_trait::async_trait]
+impl CinemaScraper for EighteenTicketsScraper {
+    async fn fetch_films(&self, client: &HttpClient) -> Result<Vec<Film>, ScrapeError> {
+        let today = showtime::today();
+        let ref_dates: Vec<NaiveDate> = (0..self.days)
+            .map(|d| today + Duration::days(i64::from(d)))
+            .collect();
+
+        // The listing of every day decides which film pages to open for that day.
+        // Only the first day is required; later days may not be published yet.
+        let listings = join_all(ref_dates.iter().map(|date| {
+            let url = format!("{}/?ref_date={}", self.base_url, date.format("%Y-%m-%d"));
+            async move { self.get(client, &url).await }
+        }))
+        .await;
+        let mut pages = Vec::new();
+        for (i, (date, listing)) in ref_dates.iter().zip(listings).enumerate() {
+            match listing {
+                Ok(body) => pages.extend(parse_listing(&body).into_iter().map(|id| (id, *date))),
+                Err(e) if i == 0 => return Err(e),
+                Err(e) => eprintln!("18tickets {}: skipping {date}: {e}", self.subdomain),
+            }
+        }
+
+        let details = pages.into_iter().map(|(id, date)| async move {
+            let film_url = format!(
+                "{}/film/{}?ref_date={}",
+                self.base_url,
+                id,
+                date.format("%Y-%m-%d")
+            );
+            let body = match self.get(client, &film_url).await {
+                Ok(body) => body,
+                Err(e) => {
+                    eprintln!("18tickets {}: skipping {film_url}: {e}", self.subdomain);
+                    return None;
+                }
+            };
+            let url = format!("{}/film/{}", self.base_url, id);
+            Some(parse_film_page(&id, &url, &body, today))
+        });
+        let films: Vec<Film> = join_all(details).await.into_iter().flatten().collect();
+
+        Ok(merge_days(films))
+    }
+
+    fn rss_filename(&self) -> String {
+        format!("docs/feeds/18tickets_{}.xml", self.subdomain)
+    }
+}
+
+fn collect_film_ids_from_links<'a, I>(ids: &mut HashSet<String>, elements: I)
+where
+    I: Iterator<Item = scraper::ElementRef<'a>>,
+{
+    for a in elements {
+        let href = match a.value().attr("href") {
+            Some(h) => h.trim(),
+            None => continue,
+        };
+        let path = href
+            .split('?')
+            .next()
+            .unwrap_or(href)
+            .split('#')
+            .next()
+            .unwrap_or(href);
+        let rest = if path.starts_with("/film/") {
+            path.trim_start_matches("/film/").trim_end_matches('/')
+        } else if path.contains("/film/") {
+            path.split("/film/")
+                .nth(1)
+                .unwrap_or("")
+                .trim_end_matches('/')
+        } else {
+            continue;
+        };
+        let id_part = rest.split('/').next().unwrap_or(rest);
+        if !id_part.is_empty() && id_part.chars().all(|c| c.is_ascii_digit()) {
+            ids.insert(id_part.to_string());
+        }
+    }
+}
+
+/// Extract unique film IDs from listing. Prefer links inside #movie-list if present.
+fn extract_film_ids(document: &Html) -> Vec<String> {
+    let link_sel = Selector::parse("a[href*=\"/film/\"]").unwrap_or_else(|_| unreachable!());
+    let mut ids = HashSet::new();
+
+    if let Ok(movie_list_sel) = Selector::parse("#movie-list")
+        && let Some(container) = document.select(&movie_list_sel).next()
+    {
+        collect_film_ids_from_links(&mut ids, container.select(&link_sel));
+    }
+    if ids.is_empty() {
+        collect_film_ids_from_links(&mut ids, document.select(&link_sel));
+    }
+
+    let mut v: Vec<String> = ids.into_iter().collect();
+    v.sort();
+    v
+}
+
+/// Fallback: find film IDs in raw HTML when #movie-list or links are JS-rendered.
+fn extract_film_ids_from_raw(html: &str) -> Vec<String> {
+    let mut ids = HashSet::new();
+    for (i, _) in html.match_indices("/film/") {
+        let after = &html[i + 6..];
+        let end = after
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(after.len());
+        let id = &after[..end];
+        if !id.is_empty() {
+            ids.insert(id.to_string());
+        }
+    }
+    let mut v: Vec<String> = ids.into_iter().collect();
+    v.sort();
+    v
+}
+
+/// Film IDs linked from the listing page, falling back to a raw scan of the HTML.
+fn parse_listing(body: &str) -> Vec<String> {
+    let document = Html::parse_document(body);
+    let from_doc = extract_film_ids(&document);
+    if from_doc.is_empty() {
+        extract_film_ids_from_raw(body)
+    } else {
+        from_doc
+    }
+}
+
+fn looks_like_time(s: &str) -> bool {
+    let s = s.trim().trim_start_matches('-').trim();
+    if s.len() >= 4 && s.contains(':') {
+        let parts: Vec<&str> = s.split(':').collect();
+        parts.len() == 2
+            && parts[0].chars().all(|c| c.is_ascii_digit())
+            && parts[1].chars().all(|c| c.is_ascii_digit())
+    } else {
+        false
+    }
+}
+
+/// "Lunedì 9 Febbraio", "Monday, February 9" or "09/02/2026".
+fn looks_like_date_line(s: &str) -> bool {
+    let numeric = s.split_whitespace().any(|w| {
+        let parts: Vec<&str> = w.split('/').collect();
+        parts.len() == 3
+            && parts[2].len() == 4
+            && parts.iter().all(|p| p.chars().all(|c| c.is_ascii_digit()))
+    });
+    let words: Vec<String> = s
+        .split(|c: char| !c.is_alphabetic())
+        .map(str::to_lowercase)
+        .collect();
+    let has = |list: &[&str]| words.iter().any(|w| list.contains(&w.as_str()));
+    numeric || (has(&MONTHS) && has(&WEEKDAYS))
+}
+
+/// A film page: title, poster, Durata/Director/With lines, the Plot section and the
+/// showtimes listed under each date line.
+fn parse_film_page(id: &str, url: &str, body: &str, today: NaiveDate) -> Film {
+    let doc = Html::parse_document(body);
+
+    let title = {
+        let h_sel = Selector::parse("h1, h2, h3, h4, h5, h6").ok();
+        let mut t = None;
+        if let Some(ref sel) = h_sel {
+            for h in doc.select(sel) {
+                let text = h
+                    .text()
+                    .map(|x| x.trim())
+                    .filter(|x| !x.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                if !text.is_empty()
+                    && !text.eq_ignore_ascii_case("Plot")
+                    && !text.eq_ignore_ascii_case("Info")
+                    && !text.eq_ignore_ascii_case("Trama")
+                {
+                    t = Some(text);
+                    break;
+                }
+            }
+        }
+        t.unwrap_or_else(|| format!("Film {}", id))
+    };
+
+    let poster_url = Selector::parse("meta[property=\"og:image\"]")
+        .ok()
+        .and_then(|sel| {
+            doc.select(&sel)
+                .next()
+                .and_then(|m| m.value().attr("content").map(String::from))
+        })
+        .or_else(|| {
+            Selector::parse("img[src]").ok().and_then(|sel| {
+                doc.select(&sel).find_map(|img| {
+                    let src = img.value().attr("src")?;
+                    let s = src.trim();
+                    if s.starts_with("http") && !s.contains("cookie") && !s.contains("logo") {
+                        Some(s.to_string())
+                    } else {
+                        None
+                    }
+                })
+            })
+        });
+
+    // Page text, each with the link it sits in: time buttons link to their booking page.
+    let site = url.split("/film/").next().unwrap_or(url);
+    let (all_text, links): (Vec<String>, Vec<Option<String>>) = doc
+        .root_element()
+        .descendants()
+        .filter_map(|node| {
+            let text = node.value().as_text()?.trim();
+            if text.is_empty() {
+                return None;
+            }
+            let href = node
+                .ancestors()
+                .filter_map(ElementRef::wrap)
+                .find(|e| e.value().name() == "a")
+                .and_then(|a| a.value().attr("href"))
+                .map(str::trim)
+                .filter(|h| !h.is_empty() && !h.starts_with('#'))
+                .map(|h| {
+                    if h.starts_with("http") {
+                        h.to_string()
+                    } else {
+                        format!("{}/{}", site, h.trim_start_matches('/'))
+                    }
+                });
+            Some((text.to_string(), href))
+        })
+        .unzip();
+
+    let mut running_time = None;
+    let mut director = None;
+    let mut with_cast = None;
+    let mut year = None;
+    let mut synopsis_parts = Vec::new();
+    let mut showtimes = Vec::new();
+    let mut in_plot = false;
+    let mut current_date_line: Option<String> = None;
+
+    for (i, line) in all_text.iter().enumerate() {
+        if line.starts_with("Durata:") {
+            let rest = line.trim_start_matches("Durata:").trim();
+            if let Some(num_str) = rest.split_whitespace().next() {
+                running_time = num_str.parse::<u32>().ok();
+            }
+        } else if line.eq_ignore_ascii_case("Director:") || line.eq_ignore_ascii_case("Regia:") {
+            if let Some(next) = all_text.get(i + 1) {
+                director = Some(next.clone());
+            }
+        } else if line.eq_ignore_ascii_case("With:") || line.eq_ignore_ascii_case("Con:") {
+            if let Some(next) = all_text.get(i + 1) {
+                with_cast = Some(next.clone());
+            }
+        } else if line.eq_ignore_ascii_case("Year:") || line.eq_ignore_ascii_case("Anno:") {
+            if let Some(next) = all_text.get(i + 1) {
+                year = next.trim().parse::<u32>().ok();
+            }
+        } else if line.eq_ignore_ascii_case("Plot") || line.eq_ignore_ascii_case("Trama") {
+            in_plot = true;
+        } else if in_plot {
+            if line.eq_ignore_ascii_case("Info") || looks_like_date_line(line) {
+                in_plot = false;
+            } else if line.len() > 20
+                && !line.contains("Watch the trailer")
+                && !line.contains("Seleziona")
+                && !line.contains("Select ")
+                && !line.contains("Back to ")
+            {
+                synopsis_parts.push(line.clone());
+            }
+        }
+
+        if looks_like_date_line(line) {
+            current_date_line = Some(line.clone());
+        } else if looks_like_time(line)
+            && let Some(date) = current_date_line
+                .as_deref()
+                .and_then(|d| showtime::parse_date(d, today))
+            && let Some(time) = showtime::parse_time(line)
+            && let Some(st) = Showtime::at(date, time, chrono_tz::Europe::Rome)
+        {
+            showtimes.push(Showtime {
+                ticket_url: links[i].clone(),
+                ..st
+            });
+        }
+    }
+
+    let synopsis = if synopsis_parts.is_empty() {
+        None
+    } else {
+        Some(synopsis_parts.join("\n\n"))
+    };
+    let showtimes = if showtimes.is_empty() {
+        None
+    } else {
+        Some(showtimes)
+    };
+
+    Film {
+        title,
+        url: url.to_string(),
+        poster_url,
+        cast: with_cast,
+        director,
+        year,
+        running_time,
+        synopsis,
+        showtimes,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    /// Films of one saved cinema, parsed as if `days` listings had returned the same films.
+    fn parse_fixture_cinema(cinema: &str, days: usize) -> Vec<Film> {
+        let dir = format!("eighteen_tickets/{cinema}");
+        let base = "https://example.18tickets.it";
+        let ids = parse_listing(&fixtures::load(&dir, "listing.html"));
+        let films = (0..days)
+            .flat_map(|_| &ids)
+            .map(|id| {
+                let body = fixtures::load(&dir, &format!("film-{id}.html"));
+                parse_film_page(id, &format!("{base}/film/{id}"), &body, fixtures::today())
+            })
+            .collect();
+        merge_days(films)
+    }
+
+    #[test]
+    fn parses_fixture() {
+        for cinema in ["cinergia_conegliano", "cineplex_moderno"] {
+            let films = parse_fixture_cinema(cinema, 1);
+            fixtures::assert_films(&format!("eighteen_tickets/{cinema}"), &films);
+            // The same film seen on several days is still one item with each showtime once.
+            let merged = parse_fixture_cinema(cinema, 3);
+            assert_eq!(
+                serde_json::to_value(&merged).unwrap(),
+                serde_json::to_value(&films).unwrap()
+            );
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! Sending the programme digest by email, through the SMTP server of the `[email]`
+//! table of the config. The password is read from an environment variable, so it
+//! never sits in `cinemas.toml`.
+
+use cinema_scrape::{Digest, Locale};
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct EmailConfig {
+    pub smtp_host: String,
+    /// Default: 465 with `tls`, 587 with `starttls`, 25 with `none`.
+    pub smtp_port: Option<u16>,
+    #[serde(default)]
+    pub security: Security,
+    /// Log in as this user, with the password from `password_env`.
+    pub username: Option<String>,
+    #[serde(default = "default_password_env")]
+    pub password_env: String,
+    /// Sender, e.g. "Cinema <cinema@example.com>".
+    pub from: String,
+    pub to: Vec<String>,
+    /// Feeds whose cinemas are in the digest (default: every feed of the run).
+    #[serde(default)]
+    pub feeds: Vec<String>,
+    #[serde(default = "default_language")]
+    pub language: Locale,
+}
+
+fn default_password_env() -> String {
+    "SMTP_PASSWORD".to_string()
+}
+
+fn default_language() -> Locale {
+    Locale::Italian
+}
+
+/// How the connection to the SMTP server is secured.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Security {
+    /// TLS from the start (SMTPS).
+    Tls,
+    /// Plain connection upgraded with STARTTLS, which the server must offer.
+    #[default]
+    Starttls,
+    /// No encryption: only for a local relay or a test sink.
+    None,
+}
+
+/// Send `digest` as a multipart (plain text + HTML) message to every recipient.
+pub async fn send(config: &EmailConfig, digest: &Digest) -> Result<(), Box<dyn std::error::Error>> {
+    let mut message = Message::builder()
+        .from(config.from.parse::<Mailbox>()?)
+        .subject(&digest.subject);
+    for to in &config.to {
+        message = message.to(to.parse::<Mailbox>()?);
+    }
+    let message = message.multipart(MultiPart::alternative_plain_html(
+        digest.text.clone(),
+        digest.html.clone(),
+    ))?;
+
+    let host = config.smtp_host.as_str();
+    let mut transport = match config.security {
+        Security::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host)?,
+        Security::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?,
+        Security::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+    };
+    if let Some(port) = config.smtp_port {
+        transport = transport.port(port);
+    }
+    if let Some(ref username) = config.username {
+        let password = std::env::var(&config.password_env).map_err(|_| {
+            format!(
+                "SMTP user {username} needs a password in ${}",
+                config.password_env
+            )
+        })?;
+        transport = transport.credentials(Credentials::new(username.clone(), password));
+    }
+    transport.build().send(message).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// A one-message SMTP sink: accepts a single delivery and returns the envelope
+    /// recipients and the message data.
+    fn smtp_sink() -> (u16, thread::JoinHandle<(Vec<String>, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut out = stream;
+            let mut reply = |line: &str| out.write_all(format!("{line}\r\n").as_bytes()).unwrap();
+            reply("220 sink ESMTP");
+            let (mut recipients, mut data) = (Vec::new(), String::new());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 0 {
+                let command = line.trim_end().to_string();
+                line.clear();
+                let verb = command.to_ascii_uppercase();
+                if verb.starts_with("EHLO") || verb.starts_with("HELO") {
+                    reply("250 sink");
+                } else if verb.starts_with("RCPT TO:") {
+                    recipients.push(command[8..].to_string());
+                    reply("250 OK");
+                } else if verb == "DATA" {
+                    reply("354 go ahead");
+                    while reader.read_line(&mut line).unwrap() > 0 && line != ".\r\n" {
+                        data.push_str(&line);
+                        line.clear();
+                    }
+                    line.clear();
+                    reply("250 queued");
+                } else if verb == "QUIT" {
+                    reply("221 bye");
+                    break;
+                } else {
+                    reply("250 OK");
+                }
+            }
+            (recipients, data)
+        });
+        (port, handle)
+    }
+
+    #[tokio::test]
+    async fn sends_a_multipart_digest() {
+        let (port, sink) = smtp_sink();
+        let config = EmailConfig {
+            smtp_host: "127.0.0.1".to_string(),
+            smtp_port: Some(port),
+            security: Security::None,
+            username: None,
+            password_env: default_password_env(),
+            from: "Cinema <cinema@example.com>".to_string(),
+            to: vec!["a@example.com".to_string(), "b@example.com".to_string()],
+            feeds: Vec::new(),
+            language: Locale::Italian,
+        };
+        let digest = Digest {
+            subject: "Programma di Lunedì 9 Febbraio".to_string(),
+            text: "Stalker - 20:30".to_string(),
+            html: "<p>Stalker &middot; 20:30</p>".to_string(),
+        };
+        send(&config, &digest).await.unwrap();
+
+        let (recipients, data) = sink.join().unwrap();
+        assert_eq!(recipients, ["<a@example.com>", "<b@example.com>"]);
+        assert!(data.contains("multipart/alternative"));
+        assert!(data.contains("text/plain"));
+        assert!(data.contains("text/html"));
+        assert!(data.contains("From: Cinema <cinema@example.com>"));
+    }
+}
@@ -1,5 +1,7 @@
-use crate::{CinemaScraper, Film};
-use reqwest::{Client, header};
+use crate::{CinemaScraper, Film, HttpClient, ScrapeError, Showtime, showtime};
+use chrono::NaiveDate;
+use futures::future::join_all;
+use reqwest::header;
 use scraper::{ElementRef, Html, Selector};
 use std::collections::HashSet;
 
@@ -17,7 +19,7 @@ impl EnricoPizzutiScraper {
 
 #[async_trait::async_trait]
 impl CinemaScraper for EnricoPizzutiScraper {
-    async fn fetch_films(&self, client: &Client) -> Result<Vec<Film>, Box<dyn std::error::Error>> {
+    async fn fetch_films(&self, client: &HttpClient) -> Result<Vec<Film>, ScrapeError> {
         let resp = client
             .get(&self.url)
             .header(
@@ -32,98 +34,16 @@ impl CinemaScraper for EnricoPizzutiScraper {
 
         let body = resp.text().await?;
 
-        // Scope HTML parsing and Cineforum extraction so that non-Send types
-        // (`Html`, `ElementRef`, etc.) are dropped before we perform any further awaits.
-        let film_urls: Vec<String> = {
-            let document = Html::parse_document(&body);
-
-            // Find the Cineforum section (e.g. "<h5>Cineforum 2026</h5>") and, from there,
-            // extract the list of film links that belong to that section only.
-            let cineforum_h5_selector = Selector::parse("h5")?;
-            let mut film_urls: Vec<String> = Vec::new();
-            let mut seen_urls: HashSet<String> = HashSet::new();
-
-            // Helper selector used when trying candidate containers.
-            let link_selector = Selector::parse("a[href]")?;
-
-            for h5 in document.select(&cineforum_h5_selector) {
-                let text = h5
-                    .text()
-                    .map(|t| t.trim().to_lowercase())
-                    .collect::<Vec<_>>()
-                    .join(" ");
-
-                if !text.contains("cineforum") {
-                    continue;
-                }
-
-                // Walk up a few levels to find a container whose subtree holds film links.
-                let mut current = Some(h5);
-                for _ in 0..6 {
-                    if let Some(cur) = current {
-                        let parent = match cur.parent().and_then(ElementRef::wrap) {
-                            Some(p) => p,
-                            None => break,
-                        };
-
-                        let mut urls_in_container = Vec::new();
-                        for link in parent.select(&link_selector) {
-                            if let Some(href) = link.value().attr("href")
-                                && href.contains("/film/")
-                            {
-                                let full_url = if href.starts_with("http") {
-                                    href.to_string()
-                                } else {
-                                    format!("https://www.enricopizzuti.it{}", href)
-                                };
-                                if seen_urls.insert(full_url.clone()) {
-                                    urls_in_container.push(full_url);
-                                }
-                            }
-                        }
-
-                        if !urls_in_container.is_empty() {
-                            film_urls.extend(urls_in_container);
-                            break;
-                        }
-
-                        current = parent.parent().and_then(ElementRef::wrap);
-                    } else {
-                        break;
-                    }
-                }
-
-                // If we already found a suitable container, no need to check further h5s.
-                if !film_urls.is_empty() {
-                    break;
-                }
-            }
-
-            film_urls
-        };
+        let film_urls = parse_cineforum(&body)?;
 
         // If no Cineforum section was found, return an empty list gracefully.
         if film_urls.is_empty() {
             return Ok(Vec::new());
         }
 
-        // For each film URL in the Cineforum section, open the detail page and extract data
-        // from ".container.film-description" and ".film-content".
-        let film_container_selector = Selector::parse("div.container.film-description")?;
-        let film_date_selector = Selector::parse("div.film-date")?;
-        let film_cast_block_selector = Selector::parse("div.film-cast")?;
-        let director_selector = Selector::parse("div.director")?;
-        let nation_selector = Selector::parse("div.nazione")?;
-        let cast_selector = Selector::parse("div.cast")?;
-        let h1_selector = Selector::parse("h1")?;
-        // Synopsis and poster inside the film-content block
-        let film_content_selector = Selector::parse("div.film-content")?;
-        let film_text_selector = Selector::parse("div.film-text p")?;
-        let film_screens_img_selector = Selector::parse("div.film-screens img")?;
-
-        let mut films = Vec::new();
-
-        for url in film_urls {
+        // For each film URL in the Cineforum section, open the detail page.
+        let today = showtime::today();
+        let details = film_urls.into_iter().map(|url| async move {
             let resp = client
                 .get(&url)
                 .header(
@@ -137,134 +57,272 @@ impl CinemaScraper for EnricoPizzutiScraper {
                 .error_for_status()?;
 
             let body = resp.text().await?;
-            let doc = Html::parse_document(&body);
-
-            // Find the main film description container.
-            let container = match doc.select(&film_container_selector).next() {
-                Some(c) => c,
-                None => {
-                    // If the structure is not as expected, skip this film.
-                    continue;
-                }
-            };
+            parse_film_page(&url, &body, today)
+        });
+        let mut films = Vec::new();
+        for film in join_all(details).await {
+            films.extend(film?);
+        }
 
-            // Title
-            let title = container
-                .select(&h1_selector)
-                .next()
-                .map(|h1| {
-                    h1.text()
-                        .map(|t| t.trim())
-                        .filter(|t| !t.is_empty())
-                        .collect::<Vec<_>>()
-                        .join(" ")
-                })
-                .filter(|s| !s.is_empty())
-                .unwrap_or_else(|| "Senza titolo".to_string());
-
-            // Date / showtime
-            let date_text = container
-                .select(&film_date_selector)
-                .next()
-                .map(|d| {
-                    d.text()
-                        .map(|t| t.trim())
-                        .filter(|t| !t.is_empty())
-                        .collect::<Vec<_>>()
-                        .join(" ")
-                })
-                .filter(|s| !s.is_empty());
-
-            // Cast-related info: director, nation/year, full cast
-            let mut cast_parts: Vec<String> = Vec::new();
-
-            if let Some(cast_block) = container.select(&film_cast_block_selector).next() {
-                if let Some(dir_el) = cast_block.select(&director_selector).next() {
-                    let dir_text = dir_el
-                        .text()
-                        .map(|t| t.trim())
-                        .filter(|t| !t.is_empty())
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    if !dir_text.is_empty() {
-                        cast_parts.push(dir_text);
-                    }
-                }
+        Ok(films)
+    }
+
+    fn rss_filename(&self) -> String {
+        "docs/feeds/enrico_pizzuti.xml".to_string()
+    }
+}
+
+/// Film page URLs of the home page's Cineforum section (e.g. "<h5>Cineforum 2026</h5>").
+fn parse_cineforum(body: &str) -> Result<Vec<String>, ScrapeError> {
+    let document = Html::parse_document(body);
+
+    // Find the Cineforum section (e.g. "<h5>Cineforum 2026</h5>") and, from there,
+    // extract the list of film links that belong to that section only.
+    let cineforum_h5_selector = Selector::parse("h5")?;
+    let mut film_urls: Vec<String> = Vec::new();
+    let mut seen_urls: HashSet<String> = HashSet::new();
+
+    // Helper selector used when trying candidate containers.
+    let link_selector = Selector::parse("a[href]")?;
+
+    for h5 in document.select(&cineforum_h5_selector) {
+        let text = h5
+            .text()
+            .map(|t| t.trim().to_lowercase())
+            .collect::<Vec<_>>()
+            .join(" ");
 
-                if let Some(nation_el) = cast_block.select(&nation_selector).next() {
-                    let nation_text = nation_el
-                        .text()
-                        .map(|t| t.trim())
-                        .filter(|t| !t.is_empty())
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    if !nation_text.is_empty() {
-                        cast_parts.push(nation_text);
+        if !text.contains("cineforum") {
+            continue;
+        }
+
+        // Walk up a few levels to find a container whose subtree holds film links.
+        let mut current = Some(h5);
+        for _ in 0..6 {
+            if let Some(cur) = current {
+                let parent = match cur.parent().and_then(ElementRef::wrap) {
+                    Some(p) => p,
+                    None => break,
+                };
+
+                let mut urls_in_container = Vec::new();
+                for link in parent.select(&link_selector) {
+                    if let Some(href) = link.value().attr("href")
+                        && href.contains("/film/")
+                    {
+                        let full_url = if href.starts_with("http") {
+                            href.to_string()
+                        } else {
+                            format!("https://www.enricopizzuti.it{}", href)
+                        };
+                        if seen_urls.insert(full_url.clone()) {
+                            urls_in_container.push(full_url);
+                        }
                     }
                 }
 
-                if let Some(cast_el) = cast_block.select(&cast_selector).next() {
-                    let cast_text = cast_el
-                        .text()
-                        .map(|t| t.trim())
-                        .filter(|t| !t.is_empty())
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    if !cast_text.is_empty() {
-                        cast_parts.push(cast_text);
-                    }
+                if !urls_in_container.is_empty() {
+                    film_urls.extend(urls_in_container);
+                    break;
                 }
+
+                current = parent.parent().and_then(ElementRef::wrap);
+            } else {
+                break;
+            }
+        }
+
+        // If we already found a suitable container, no need to check further h5s.
+        if !film_urls.is_empty() {
+            break;
+        }
+    }
+    Ok(film_urls)
+}
+
+/// A Cineforum film page: title, screening date/time, director/nation/cast, synopsis
+/// and the first still as poster. `None` if the page has no film description.
+fn parse_film_page(url: &str, body: &str, today: NaiveDate) -> Result<Option<Film>, ScrapeError> {
+    // Data comes from ".container.film-description" and ".film-content".
+    let film_container_selector = Selector::parse("div.container.film-description")?;
+    let film_date_selector = Selector::parse("div.film-date")?;
+    let film_cast_block_selector = Selector::parse("div.film-cast")?;
+    let director_selector = Selector::parse("div.director")?;
+    let nation_selector = Selector::parse("div.nazione")?;
+    let cast_selector = Selector::parse("div.cast")?;
+    let h1_selector = Selector::parse("h1")?;
+    // Synopsis and poster inside the film-content block
+    let film_content_selector = Selector::parse("div.film-content")?;
+    let film_text_selector = Selector::parse("div.film-text p")?;
+    let film_screens_img_selector = Selector::parse("div.film-screens img")?;
+
+    let doc = Html::parse_document(body);
+
+    // Find the main film description container.
+    let container = match doc.select(&film_container_selector).next() {
+        Some(c) => c,
+        None => {
+            // If the structure is not as expected, skip this film.
+            return Ok(None);
+        }
+    };
+
+    // Title
+    let title = container
+        .select(&h1_selector)
+        .next()
+        .map(|h1| {
+            h1.text()
+                .map(|t| t.trim())
+                .filter(|t| !t.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "Senza titolo".to_string());
+
+    // Date / showtime
+    let date_text = container
+        .select(&film_date_selector)
+        .next()
+        .map(|d| {
+            d.text()
+                .map(|t| t.trim())
+                .filter(|t| !t.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .filter(|s| !s.is_empty());
+
+    // Cast-related info: director, nation/year, full cast
+    let mut director = None;
+    let mut country = None;
+    let mut year = None;
+    let mut cast_parts: Vec<String> = Vec::new();
+
+    if let Some(cast_block) = container.select(&film_cast_block_selector).next() {
+        if let Some(dir_el) = cast_block.select(&director_selector).next() {
+            let dir_text = dir_el
+                .text()
+                .map(|t| t.trim())
+                .filter(|t| !t.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+            // "Regia di Wim Wenders"; any other wording stays in the cast text.
+            director = ["Regia di", "Regia:"]
+                .iter()
+                .find_map(|label| dir_text.strip_prefix(label))
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty());
+            if director.is_none() && !dir_text.is_empty() {
+                cast_parts.push(dir_text);
             }
+        }
 
-            let cast = if cast_parts.is_empty() {
-                None
+        if let Some(nation_el) = cast_block.select(&nation_selector).next() {
+            let nation_text = nation_el
+                .text()
+                .map(|t| t.trim())
+                .filter(|t| !t.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+            // "Giappone 2023", or "Francia, Belgio 2024"
+            let (nation, last) = nation_text
+                .rsplit_once(' ')
+                .unwrap_or((nation_text.as_str(), ""));
+            year = last.parse::<u32>().ok();
+            let nation = if year.is_some() {
+                nation
             } else {
-                Some(cast_parts.join(" | "))
+                nation_text.as_str()
             };
+            if !nation.is_empty() {
+                country = Some(nation.to_string());
+            }
+        }
 
-            let showtimes = date_text.clone().map(|d| vec![d.clone()]);
-
-            // Synopsis and poster from film-content section
-            let mut synopsis: Option<String> = None;
-            let mut poster_url: Option<String> = None;
-
-            if let Some(film_content) = doc.select(&film_content_selector).next() {
-                if let Some(text_el) = film_content.select(&film_text_selector).next() {
-                    let text = text_el
-                        .text()
-                        .map(|t| t.trim())
-                        .filter(|t| !t.is_empty())
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    if !text.is_empty() {
-                        synopsis = Some(text);
-                    }
-                }
-
-                if let Some(img_el) = film_content.select(&film_screens_img_selector).next()
-                    && let Some(src) = img_el.value().attr("src")
-                    && !src.trim().is_empty()
-                {
-                    poster_url = Some(src.to_string());
-                }
+        if let Some(cast_el) = cast_block.select(&cast_selector).next() {
+            let cast_text = cast_el
+                .text()
+                .map(|t| t.trim())
+                .filter(|t| !t.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+            if !cast_text.is_empty() {
+                cast_parts.push(cast_text);
             }
+        }
+    }
 
-            films.push(Film {
-                title,
-                url,
-                poster_url,
-                cast,
-                release_date: date_text,
-                running_time: None,
-                synopsis,
-                showtimes,
-            });
+    let cast = if cast_parts.is_empty() {
+        None
+    } else {
+        Some(cast_parts.join(" | "))
+    };
+
+    let showtimes = date_text.as_deref().and_then(|d| {
+        let date = showtime::parse_date(d, today)?;
+        let time = showtime::parse_time(d)?;
+        Showtime::at(date, time, chrono_tz::Europe::Rome).map(|st| vec![st])
+    });
+
+    // Synopsis and poster from film-content section
+    let mut synopsis: Option<String> = None;
+    let mut poster_url: Option<String> = None;
+
+    if let Some(film_content) = doc.select(&film_content_selector).next() {
+        if let Some(text_el) = film_content.select(&film_text_selector).next() {
+            let text = text_el
+                .text()
+                .map(|t| t.trim())
+                .filter(|t| !t.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+            if !text.is_empty() {
+                synopsis = Some(text);
+            }
         }
 
-        Ok(films)
+        if let Some(img_el) = film_content.select(&film_screens_img_selector).next()
+            && let Some(src) = img_el.value().attr("src")
+            && !src.trim().is_empty()
+        {
+            poster_url = Some(src.to_string());
+        }
     }
 
-    fn rss_filename(&self) -> String {
-        "docs/feeds/enrico_pizzuti.xml".to_string()
+    Ok(Some(Film {
+        title,
+        url: url.to_string(),
+        poster_url,
+        cast,
+        director,
+        release_date: date_text,
+        year,
+        country,
+        synopsis,
+        showtimes,
+        ..Default::default()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn parses_fixture() {
+        let urls = parse_cineforum(&fixtures::load("enrico_pizzuti", "home.html")).unwrap();
+        let mut films = Vec::new();
+        for url in &urls {
+            let page = format!(
+                "{}.html",
+                url.trim_end_matches('/').rsplit('/').next().unwrap()
+            );
+            let body = fixtures::load("enrico_pizzuti", &page);
+            films.extend(parse_film_page(url, &body, fixtures::today()).unwrap());
+        }
+        fixtures::assert_films("enrico_pizzuti", &films);
     }
 }
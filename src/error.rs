@@ -0,0 +1,96 @@
+//! Typed scraper errors, so a broken site can be told apart from "no films this week".
+
+use reqwest::StatusCode;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum ScrapeError {
+    /// The server answered with a non-success status.
+    Http { url: String, status: StatusCode },
+    /// Connection, TLS, timeout or body-read failure.
+    Network(reqwest::Error),
+    /// `--replay` has no saved response for this URL.
+    NotRecorded { url: String },
+    /// Invalid selector or page layout the scraper does not understand.
+    Parse(String),
+    /// An embedded or API JSON payload did not match the expected shape.
+    Json(serde_json::Error),
+    /// The scrape succeeded but returned no films where some were expected.
+    Empty,
+    /// Far fewer films than the last good run; likely a partially broken page.
+    TooFew { found: usize, previous: usize },
+}
+
+impl fmt::Display for ScrapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScrapeError::Http { url, status } => write!(f, "HTTP {status} from {url}"),
+            ScrapeError::Network(e) => write!(f, "network error: {e}"),
+            ScrapeError::NotRecorded { url } => write!(f, "no recorded response for {url}"),
+            ScrapeError::Parse(msg) => write!(f, "parse error: {msg}"),
+            ScrapeError::Json(e) => write!(f, "unexpected JSON: {e}"),
+            ScrapeError::Empty => write!(f, "no films found"),
+            ScrapeError::TooFew { found, previous } => {
+                write!(f, "only {found} films (last good run had {previous})")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScrapeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ScrapeError::Network(e) => Some(e),
+            ScrapeError::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ScrapeError {
+    fn from(e: reqwest::Error) -> Self {
+        match e.status() {
+            Some(status) => ScrapeError::Http {
+                url: e.url().map(|u| u.to_string()).unwrap_or_default(),
+                status,
+            },
+            None => ScrapeError::Network(e),
+        }
+    }
+}
+
+impl From<serde_json::Error> for ScrapeError {
+    fn from(e: serde_json::Error) -> Self {
+        ScrapeError::Json(e)
+    }
+}
+
+impl From<scraper::error::SelectorErrorKind<'_>> for ScrapeError {
+    fn from(e: scraper::error::SelectorErrorKind<'_>) -> Self {
+        ScrapeError::Parse(format!("selector error: {e}"))
+    }
+}
+
+impl From<String> for ScrapeError {
+    fn from(msg: String) -> Self {
+        ScrapeError::Parse(msg)
+    }
+}
+
+impl From<&str> for ScrapeError {
+    fn from(msg: &str) -> Self {
+        ScrapeError::Parse(msg.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selector_errors_become_parse_errors() {
+        let err: ScrapeError = scraper::Selector::parse("div[").unwrap_err().into();
+        assert!(matches!(err, ScrapeError::Parse(_)));
+        assert!(err.to_string().starts_with("parse error: selector error"));
+    }
+}
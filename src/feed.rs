@@ -0,0 +1,384 @@
+//! Output formats. The same films can be rendered as RSS 2.0, Atom 1.0, JSON Feed 1.1
+//! or an iCalendar file of screenings.
+
+use crate::{
+    Film, Locale, dedup, film_description_and_pub_date, generate_ics, generate_rss,
+    generate_rss_by_film, generate_rss_merged, group_description, group_films, last_change,
+};
+use atom_syndication::{
+    CategoryBuilder, ContentBuilder, EntryBuilder, FeedBuilder, LinkBuilder, PersonBuilder,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Channel metadata plus the films to publish, grouped by cinema.
+/// With a single source items keep their plain title; with several, titles are
+/// prefixed with the cinema name (as in [`generate_rss_merged`]), unless `grouping`
+/// is [`Grouping::Film`].
+pub struct FeedSpec<'a> {
+    pub title: &'a str,
+    pub link: &'a str,
+    pub description: &'a str,
+    pub locale: Locale,
+    pub sources: &'a [(&'a str, &'a [Film])],
+    pub grouping: Grouping,
+}
+
+/// How a feed with several cinemas lays out its items.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Grouping {
+    /// One item per film per cinema, titled "<cinema> - <film>".
+    #[default]
+    Cinema,
+    /// One item per film, listing every cinema showing it (see [`crate::dedup`]).
+    Film,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedFormat {
+    Rss,
+    Atom,
+    JsonFeed,
+    Ics,
+}
+
+impl FeedFormat {
+    pub const ALL: [FeedFormat; 4] = [
+        FeedFormat::Rss,
+        FeedFormat::Atom,
+        FeedFormat::JsonFeed,
+        FeedFormat::Ics,
+    ];
+
+    /// File extension used in `docs/feeds/<name>.<ext>`.
+    pub fn extension(self) -> &'static str {
+        match self {
+            FeedFormat::Rss => "xml",
+            FeedFormat::Atom => "atom",
+            FeedFormat::JsonFeed => "json",
+            FeedFormat::Ics => "ics",
+        }
+    }
+
+    pub fn render(self, spec: &FeedSpec) -> Result<String, Box<dyn std::error::Error>> {
+        match self {
+            FeedFormat::Rss => match spec.sources {
+                sources if spec.grouping == Grouping::Film => generate_rss_by_film(
+                    spec.title,
+                    spec.link,
+                    spec.description,
+                    spec.locale,
+                    sources,
+                ),
+                [(_, films)] => {
+                    generate_rss(films, spec.title, spec.link, spec.description, spec.locale)
+                }
+                sources => generate_rss_merged(
+                    spec.title,
+                    spec.link,
+                    spec.description,
+                    spec.locale,
+                    sources,
+                ),
+            },
+            FeedFormat::Atom => render_atom(spec),
+            FeedFormat::JsonFeed => render_json_feed(spec),
+            FeedFormat::Ics => Ok(generate_ics(spec.title, spec.sources)),
+        }
+    }
+}
+
+/// A feed item ready to be written out in any format.
+struct Entry<'a> {
+    film: &'a Film,
+    cinemas: Vec<&'a str>,
+    genres: Vec<String>,
+    title: String,
+    content_html: String,
+    /// See [`last_change`]: when the film was first seen, or its snapshot taken.
+    updated: DateTime<Utc>,
+    /// When the film was first seen, if known.
+    published: Option<DateTime<Utc>>,
+}
+
+fn entries<'a>(spec: &FeedSpec<'a>) -> Vec<Entry<'a>> {
+    if spec.grouping == Grouping::Film {
+        return group_films(spec.sources, dedup::RUNTIME_TOLERANCE)
+            .into_iter()
+            .map(|group| {
+                let merged = group.merged();
+                Entry {
+                    film: group.film(),
+                    cinemas: group.cinemas(),
+                    genres: merged.genres,
+                    title: group.film().title.clone(),
+                    content_html: group_description(&group, spec.locale),
+                    updated: last_change(group.showings.iter().map(|(_, f)| *f)),
+                    published: merged.first_seen,
+                }
+            })
+            .collect();
+    }
+    let merged = spec.sources.len() > 1;
+    let mut out = Vec::new();
+    for (cinema, films) in spec.sources {
+        for film in *films {
+            let (content_html, _) = film_description_and_pub_date(film, spec.locale);
+            out.push(Entry {
+                film,
+                cinemas: vec![cinema],
+                genres: film.genres.clone(),
+                title: if merged {
+                    format!("{} - {}", cinema, film.title)
+                } else {
+                    film.title.clone()
+                },
+                content_html,
+                updated: last_change([film]),
+                published: film.first_seen,
+            });
+        }
+    }
+    out
+}
+
+fn render_atom(spec: &FeedSpec) -> Result<String, Box<dyn std::error::Error>> {
+    let updated = last_change(spec.sources.iter().flat_map(|(_, films)| *films));
+    let entries: Vec<atom_syndication::Entry> = entries(spec)
+        .into_iter()
+        .map(|e| {
+            EntryBuilder::default()
+                .title(e.title)
+                .id(e.film.url.clone())
+                .updated(e.updated.fixed_offset())
+                .published(e.published.map(|p| p.fixed_offset()))
+                .authors(
+                    e.cinemas
+                        .iter()
+                        .map(|c| PersonBuilder::default().name(*c).build())
+                        .collect::<Vec<_>>(),
+                )
+                .categories(
+                    e.cinemas
+                        .iter()
+                        .map(|c| CategoryBuilder::default().term(*c).build())
+                        .chain(e.genres.iter().map(|g| {
+                            CategoryBuilder::default()
+                                .term(g.clone())
+                                .scheme(Some("genre".to_string()))
+                                .build()
+                        }))
+                        .collect::<Vec<_>>(),
+                )
+                .link(LinkBuilder::default().href(e.film.url.clone()).build())
+                .content(
+                    ContentBuilder::default()
+                        .content_type(Some("html".to_string()))
+                        .value(Some(e.content_html))
+                        .build(),
+                )
+                .build()
+        })
+        .collect();
+    let feed = FeedBuilder::default()
+        .title(spec.title)
+        .id(spec.link)
+        .updated(updated.fixed_offset())
+        .subtitle(Some(spec.description.into()))
+        .lang(Some(spec.locale.code().to_string()))
+        .link(LinkBuilder::default().href(spec.link).build())
+        .entries(entries)
+        .build();
+    Ok(feed.to_string())
+}
+
+#[derive(Serialize)]
+struct JsonFeed<'a> {
+    version: &'static str,
+    title: &'a str,
+    home_page_url: &'a str,
+    description: &'a str,
+    language: &'static str,
+    items: Vec<JsonItem<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonItem<'a> {
+    id: &'a str,
+    url: &'a str,
+    title: String,
+    content_html: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date_published: Option<String>,
+    date_modified: String,
+    authors: Vec<JsonAuthor<'a>>,
+    tags: Vec<&'a str>,
+}
+
+#[derive(Serialize)]
+struct JsonAuthor<'a> {
+    name: &'a str,
+}
+
+fn render_json_feed(spec: &FeedSpec) -> Result<String, Box<dyn std::error::Error>> {
+    let items = entries(spec)
+        .into_iter()
+        .map(|e| JsonItem {
+            id: &e.film.url,
+            url: &e.film.url,
+            title: e.title,
+            content_html: e.content_html,
+            image: e.film.poster_url.as_deref(),
+            date_published: e.published.map(|p| p.to_rfc3339()),
+            date_modified: e.updated.to_rfc3339(),
+            authors: e.cinemas.iter().map(|&name| JsonAuthor { name }).collect(),
+            tags: e.cinemas,
+        })
+        .collect();
+    let feed = JsonFeed {
+        version: "https://jsonfeed.org/version/1.1",
+        title: spec.title,
+        home_page_url: spec.link,
+        description: spec.description,
+        language: spec.locale.code(),
+        items,
+    };
+    Ok(serde_json::to_string_pretty(&feed)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn film(title: &str) -> Film {
+        Film {
+            title: title.to_string(),
+            url: format!("https://example.com/{title}"),
+            poster_url: Some("https://example.com/poster.jpg".to_string()),
+            running_time: Some(120),
+            synopsis: Some("Trama.".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn renders_every_format() {
+        let a = [film("Stalker")];
+        let b = [film("Solaris")];
+        let sources: [(&str, &[Film]); 2] = [("Cinema A", &a), ("Cinema B", &b)];
+        let spec = FeedSpec {
+            title: "Test",
+            link: "https://example.com/",
+            description: "Films",
+            locale: Locale::Italian,
+            sources: &sources,
+            grouping: Grouping::Cinema,
+        };
+
+        let atom: atom_syndication::Feed = FeedFormat::Atom.render(&spec).unwrap().parse().unwrap();
+        assert_eq!(atom.entries().len(), 2);
+        assert_eq!(atom.entries()[0].title().as_str(), "Cinema A - Stalker");
+        assert_eq!(atom.entries()[1].authors()[0].name(), "Cinema B");
+
+        let json: serde_json::Value =
+            serde_json::from_str(&FeedFormat::JsonFeed.render(&spec).unwrap()).unwrap();
+        assert_eq!(json["version"], "https://jsonfeed.org/version/1.1");
+        assert_eq!(json["items"][1]["tags"][0], "Cinema B");
+        assert_eq!(json["items"][0]["image"], "https://example.com/poster.jpg");
+
+        let rss = FeedFormat::Rss.render(&spec).unwrap();
+        assert!(rss.contains("<category>Cinema A</category>"));
+
+        let c = [film("Stalker")];
+        let sources: [(&str, &[Film]); 3] = [("Cinema A", &a), ("Cinema B", &b), ("Cinema C", &c)];
+        let by_film = FeedSpec {
+            sources: &sources,
+            grouping: Grouping::Film,
+            ..spec
+        };
+        let json: serde_json::Value =
+            serde_json::from_str(&FeedFormat::JsonFeed.render(&by_film).unwrap()).unwrap();
+        assert_eq!(json["items"].as_array().unwrap().len(), 2);
+        assert_eq!(json["items"][0]["title"], "Stalker");
+        assert_eq!(
+            json["items"][0]["tags"],
+            serde_json::json!(["Cinema A", "Cinema C"])
+        );
+        let rss = FeedFormat::Rss.render(&by_film).unwrap();
+        assert_eq!(rss.matches("<item>").count(), 2);
+    }
+
+    #[test]
+    fn renders_film_metadata() {
+        let stalker = Film {
+            director: Some("Andrej Tarkovskij".to_string()),
+            year: Some(1979),
+            country: Some("URSS".to_string()),
+            genres: vec!["Fantascienza".to_string(), "Drammatico".to_string()],
+            original_title: Some("Сталкер".to_string()),
+            ..film("Stalker")
+        };
+        let films = [stalker];
+        let sources: [(&str, &[Film]); 1] = [("Cinema A", &films)];
+        let spec = FeedSpec {
+            title: "Test",
+            link: "https://example.com/",
+            description: "Films",
+            locale: Locale::Italian,
+            sources: &sources,
+            grouping: Grouping::Cinema,
+        };
+
+        let rss = FeedFormat::Rss.render(&spec).unwrap();
+        assert!(rss.contains("Regia: Andrej Tarkovskij"));
+        assert!(rss.contains("Titolo originale: Сталкер"));
+        assert!(rss.contains("Genere: Fantascienza, Drammatico"));
+        assert!(rss.contains("Paese: URSS, 1979"));
+        assert!(rss.contains("<category domain=\"genre\">Drammatico</category>"));
+
+        let atom: atom_syndication::Feed = FeedFormat::Atom.render(&spec).unwrap().parse().unwrap();
+        let genres: Vec<&str> = atom.entries()[0]
+            .categories()
+            .iter()
+            .filter(|c| c.scheme() == Some("genre"))
+            .map(|c| c.term())
+            .collect();
+        assert_eq!(genres, ["Fantascienza", "Drammatico"]);
+    }
+    #[test]
+    fn unchanged_films_render_the_same_bytes() {
+        use chrono::TimeZone;
+        use std::time::Duration;
+
+        let seen = Utc.with_ymd_and_hms(2026, 2, 8, 6, 0, 0).unwrap();
+        let start = Utc.with_ymd_and_hms(2026, 2, 10, 20, 0, 0).unwrap();
+        let films = [Film {
+            first_seen: Some(seen),
+            showtimes: Some(vec![crate::Showtime::new(start.fixed_offset())]),
+            ..film("Stalker")
+        }];
+        let sources: [(&str, &[Film]); 1] = [("Cinema A", &films)];
+        let spec = FeedSpec {
+            title: "Test",
+            link: "https://example.com/",
+            description: "Films",
+            locale: Locale::Italian,
+            sources: &sources,
+            grouping: Grouping::Cinema,
+        };
+
+        // Two refreshes of the same data: same body, so the same ETag.
+        for format in [FeedFormat::Atom, FeedFormat::JsonFeed, FeedFormat::Ics] {
+            let first = format.render(&spec).unwrap();
+            std::thread::sleep(Duration::from_millis(5));
+            assert_eq!(first, format.render(&spec).unwrap(), "{format:?}");
+        }
+        let atom: atom_syndication::Feed = FeedFormat::Atom.render(&spec).unwrap().parse().unwrap();
+        assert_eq!(*atom.updated(), seen.fixed_offset());
+        let ics = FeedFormat::Ics.render(&spec).unwrap();
+        assert!(ics.contains("DTSTAMP:20260208T060000Z"));
+    }
+}
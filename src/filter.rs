@@ -0,0 +1,283 @@
+//! Franchise/blockbuster filter for the "art-house only" feed variants.
+//! Rules come from the `[filter]` table of the config; a film matching any of them
+//! is left out of the variant and reported with the rule that caught it.
+
+use crate::Film;
+use crate::dedup::normalize_title;
+use regex::Regex;
+use serde::Deserialize;
+use std::fmt;
+
+/// Filter rules as written in the config file.
+#[derive(Debug, Default, Deserialize)]
+pub struct FilterRules {
+    /// Titles (or parts of titles) to drop, e.g. "Spider-Man".
+    #[serde(default)]
+    pub titles: Vec<String>,
+    /// Words looked for in the title, director, cast, genres and synopsis, e.g. "supereroi".
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// Studios and distributors, looked for in the cast and synopsis, where the
+    /// cinemas that name them put them.
+    #[serde(default)]
+    pub distributors: Vec<String>,
+    /// Regular expressions matched against the raw title, e.g. `(?i)^fast & furious`.
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// Drop sequels: a number after the title ("Insidious 5", "Shrek II"),
+    /// "Capitolo 2"/"Part Two", or a known franchise (see `franchises`).
+    #[serde(default)]
+    pub sequels: bool,
+    /// Franchise names for the sequel detector. A title is caught when it starts with
+    /// one ("Avatar: Fuoco e cenere", "Toy Story 5"), not when it merely contains it.
+    #[serde(default)]
+    pub franchises: Vec<String>,
+}
+
+/// Why a film was filtered out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reason {
+    Title(String),
+    Keyword(String),
+    Distributor(String),
+    Pattern(String),
+    Sequel,
+    Franchise(String),
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reason::Title(t) => write!(f, "title \"{t}\""),
+            Reason::Keyword(k) => write!(f, "keyword \"{k}\""),
+            Reason::Distributor(d) => write!(f, "distributor \"{d}\""),
+            Reason::Pattern(p) => write!(f, "pattern /{p}/"),
+            Reason::Sequel => write!(f, "sequel"),
+            Reason::Franchise(name) => write!(f, "franchise \"{name}\""),
+        }
+    }
+}
+
+/// Compiled [`FilterRules`].
+pub struct FilmFilter {
+    /// (as configured, normalized) pairs, so reasons quote the config.
+    titles: Vec<(String, String)>,
+    keywords: Vec<(String, String)>,
+    distributors: Vec<(String, String)>,
+    patterns: Vec<Regex>,
+    sequels: bool,
+    franchises: Vec<(String, String)>,
+}
+
+/// Words that introduce a sequel number: "Capitolo 2", "Part Two".
+const PART_WORDS: &[&str] = &[
+    "capitolo", "chapter", "parte", "part", "episodio", "episode",
+];
+
+/// Roman sequel numbers; "v", "vi" and "x" are left out, they are too often initials
+/// or words ("Io vi troverò").
+const ROMAN: &[&str] = &["ii", "iii", "iv", "vii", "viii", "ix"];
+
+const NUMBER_WORDS: &[&str] = &[
+    "due", "tre", "quattro", "cinque", "two", "three", "four", "five", "ii", "iii", "iv",
+];
+
+impl FilmFilter {
+    pub fn new(rules: &FilterRules) -> Result<Self, regex::Error> {
+        let normalized = |list: &[String]| -> Vec<(String, String)> {
+            list.iter()
+                .map(|s| (s.clone(), normalize_title(s)))
+                .filter(|(_, n)| !n.is_empty())
+                .collect()
+        };
+        Ok(Self {
+            titles: normalized(&rules.titles),
+            keywords: normalized(&rules.keywords),
+            distributors: normalized(&rules.distributors),
+            patterns: rules
+                .patterns
+                .iter()
+                .map(|p| Regex::new(p))
+                .collect::<Result<_, _>>()?,
+            sequels: rules.sequels,
+            franchises: normalized(&rules.franchises),
+        })
+    }
+
+    /// The first rule `film` breaks, or `None` if it belongs in the art-house feed.
+    pub fn check(&self, film: &Film) -> Option<Reason> {
+        let title = normalize_title(&film.title);
+        let text = normalize_title(&format!(
+            "{} {} {} {}",
+            film.director.as_deref().unwrap_or_default(),
+            film.cast.as_deref().unwrap_or_default(),
+            film.genres.join(" "),
+            film.synopsis.as_deref().unwrap_or_default()
+        ));
+
+        if let Some((t, _)) = self.titles.iter().find(|(_, n)| contains_words(&title, n)) {
+            return Some(Reason::Title(t.clone()));
+        }
+        if let Some(p) = self.patterns.iter().find(|p| p.is_match(&film.title)) {
+            return Some(Reason::Pattern(p.as_str().to_string()));
+        }
+        if let Some((k, _)) = self
+            .keywords
+            .iter()
+            .find(|(_, n)| contains_words(&title, n) || contains_words(&text, n))
+        {
+            return Some(Reason::Keyword(k.clone()));
+        }
+        if let Some((d, _)) = self
+            .distributors
+            .iter()
+            .find(|(_, n)| contains_words(&text, n))
+        {
+            return Some(Reason::Distributor(d.clone()));
+        }
+        if self.sequels {
+            if let Some((name, _)) = self
+                .franchises
+                .iter()
+                .find(|(_, n)| starts_with_words(&title, n))
+            {
+                return Some(Reason::Franchise(name.clone()));
+            }
+            if looks_like_sequel(&film.title) {
+                return Some(Reason::Sequel);
+            }
+        }
+        None
+    }
+
+    /// Split `films` into those kept and those dropped, with the reason for each.
+    pub fn split<'a>(&self, films: &'a [Film]) -> (Vec<Film>, Vec<(&'a Film, Reason)>) {
+        let mut kept = Vec::new();
+        let mut dropped = Vec::new();
+        for film in films {
+            match self.check(film) {
+                Some(reason) => dropped.push((film, reason)),
+                None => kept.push(film.clone()),
+            }
+        }
+        (kept, dropped)
+    }
+}
+
+/// Whether the normalized `needle` appears in `haystack` as whole words.
+pub(crate) fn contains_words(haystack: &str, needle: &str) -> bool {
+    format!(" {haystack} ").contains(&format!(" {needle} "))
+}
+
+/// Whether the normalized `haystack` opens with the words of `needle`: "avatar fuoco e
+/// cenere" starts with "avatar", "i saw the tv glow" does not start with "saw".
+fn starts_with_words(haystack: &str, needle: &str) -> bool {
+    format!("{haystack} ").starts_with(&format!("{needle} "))
+}
+
+/// A sequel number closing the title or the part before its subtitle: "Insidious 5:
+/// La porta rossa", "Shrek II", or a part number anywhere: "Dune - Parte due".
+/// Numbers elsewhere ("Le 5 leggende"), leading ones ("28 anni dopo"), years and
+/// numbers past 9 ("Apollo 13") are not.
+fn looks_like_sequel(title: &str) -> bool {
+    let sequel_number = |head: &str| {
+        let head = normalize_title(head);
+        let words: Vec<&str> = head.split_whitespace().collect();
+        words.len() > 1
+            && words.last().is_some_and(|w| {
+                w.parse::<u32>().is_ok_and(|n| (2..=9).contains(&n)) || ROMAN.contains(w)
+            })
+    };
+    let heads = [
+        Some(title),
+        title.split_once(':').map(|(head, _)| head),
+        title.split_once(" - ").map(|(head, _)| head),
+    ];
+    if heads.into_iter().flatten().any(sequel_number) {
+        return true;
+    }
+    let title = normalize_title(title);
+    let words: Vec<&str> = title.split_whitespace().collect();
+    words.windows(2).any(|pair| {
+        PART_WORDS.contains(&pair[0])
+            && (NUMBER_WORDS.contains(&pair[1]) || pair[1].parse::<u32>().is_ok())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn film(title: &str, synopsis: Option<&str>) -> Film {
+        Film {
+            title: title.to_string(),
+            url: String::new(),
+            synopsis: synopsis.map(String::from),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn catches_franchises_and_keeps_art_house() {
+        let rules = FilterRules {
+            titles: vec!["Spider-Man".to_string()],
+            keywords: vec!["supereroi".to_string()],
+            distributors: vec!["Marvel Studios".to_string()],
+            patterns: vec!["(?i)^fast (&|and) furious".to_string()],
+            sequels: true,
+            franchises: vec!["Avatar".to_string(), "Saw".to_string()],
+        };
+        let filter = FilmFilter::new(&rules).unwrap();
+        let check = |title, synopsis| filter.check(&film(title, synopsis));
+
+        assert_eq!(
+            check("SPIDER-MAN: BRAND NEW DAY", None),
+            Some(Reason::Title("Spider-Man".to_string()))
+        );
+        assert_eq!(
+            check("Fast and Furious 11", None),
+            Some(Reason::Pattern("(?i)^fast (&|and) furious".to_string()))
+        );
+        assert_eq!(
+            check("Thunderbolts*", Some("Prodotto da Marvel Studios.")),
+            Some(Reason::Distributor("Marvel Studios".to_string()))
+        );
+        assert_eq!(
+            check("Avatar: Fuoco e cenere", None),
+            Some(Reason::Franchise("Avatar".to_string()))
+        );
+        assert_eq!(
+            check("Insidious 5: La porta rossa", None),
+            Some(Reason::Sequel)
+        );
+        assert_eq!(
+            check("Saw", None),
+            Some(Reason::Franchise("Saw".to_string()))
+        );
+        assert_eq!(check("Dune - Parte due", None), Some(Reason::Sequel));
+
+        assert_eq!(check("Shrek II", None), Some(Reason::Sequel));
+
+        assert_eq!(check("I Saw the TV Glow", None), None);
+        assert_eq!(check("28 anni dopo", None), None);
+        assert_eq!(check("Io vi troverò", None), None);
+        assert_eq!(check("Dio vi perdoni", None), None);
+        assert_eq!(check("Apollo 13", None), None);
+        assert_eq!(check("Le 5 leggende", None), None);
+        assert_eq!(check("Il Maestro e Margherita (2024)", None), None);
+        assert_eq!(check("Hamnet: Nel nome del figlio", None), None);
+        assert_eq!(
+            check("La grazia", Some("Un film di Paolo Sorrentino.")),
+            None
+        );
+    }
+
+    #[test]
+    fn bad_pattern_is_an_error() {
+        let rules = FilterRules {
+            patterns: vec!["(".to_string()],
+            ..FilterRules::default()
+        };
+        assert!(FilmFilter::new(&rules).is_err());
+    }
+}
@@ -0,0 +1,54 @@
+//! Offline scraper tests: saved pages live in `tests/fixtures/<scraper>/`, next to
+//! `expected.json`, the films the scraper must parse from them. A selector that stops
+//! matching shows up as a diff against that file, without touching the network.
+//!
+//! After a deliberate parser change, regenerate the expected files with
+//! `UPDATE_FIXTURES=1 cargo test` and review the diff. `tests/fixtures/README.md`
+//! explains how to replace the pages with captures recorded with `--record`.
+
+use chrono::NaiveDate;
+use cinema_scrape::Film;
+use std::fs;
+use std::path::PathBuf;
+
+fn dir(scraper: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(scraper)
+}
+
+/// A saved page (or API response) from `tests/fixtures/<scraper>/<file>`.
+pub fn load(scraper: &str, file: &str) -> String {
+    let path = dir(scraper).join(file);
+    fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display()))
+}
+
+/// The "today" every fixture was saved on: Monday 9 February 2026.
+pub fn today() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 2, 9).unwrap()
+}
+
+/// Compare parsed films with `tests/fixtures/<scraper>/expected.json`.
+pub fn assert_films(scraper: &str, films: &[Film]) {
+    assert!(
+        !films.is_empty(),
+        "{scraper}: no films parsed from fixtures"
+    );
+    let path = dir(scraper).join("expected.json");
+    let actual = serde_json::to_string_pretty(films).unwrap() + "\n";
+    if std::env::var_os("UPDATE_FIXTURES").is_some() {
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_else(|e| {
+        panic!(
+            "{}: {e} (run with UPDATE_FIXTURES=1 to create it)",
+            path.display()
+        )
+    });
+    assert!(
+        expected == actual,
+        "{scraper}: parsed films differ from {}\n--- expected\n{expected}\n--- actual\n{actual}",
+        path.display()
+    );
+}
@@ -0,0 +1,394 @@
+//! Archive of every run in a SQLite database (default `<state dir>/history.sqlite`),
+//! so past programmes can be queried: which cinemas showed a film, and for how long.
+//!
+//! Tables:
+//! - `runs`: one row per run, with its time.
+//! - `cinemas`: one row per cinema name.
+//! - `films`: one row per film, matched with [`dedup::same_film`] so the same film at
+//!   different cinemas is one row, and two films sharing a title are not. The key is the
+//!   normalized title plus the year and director known when the film was first seen.
+//! - `listings`: which films each cinema had in programme at each run, with the film's URL there.
+//! - `screenings`: every screening ever seen, once, with the run that first saw it.
+
+use chrono::{DateTime, Utc};
+use cinema_scrape::{Film, dedup};
+use rusqlite::{Connection, OptionalExtension, params};
+use std::io::{self, Write};
+use std::path::Path;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    started_at TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS cinemas (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE
+);
+CREATE TABLE IF NOT EXISTS films (
+    id INTEGER PRIMARY KEY,
+    key TEXT NOT NULL UNIQUE,
+    title TEXT NOT NULL,
+    release_date TEXT,
+    running_time INTEGER,
+    year INTEGER,
+    director TEXT
+);
+CREATE TABLE IF NOT EXISTS listings (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    cinema_id INTEGER NOT NULL REFERENCES cinemas(id),
+    film_id INTEGER NOT NULL REFERENCES films(id),
+    url TEXT NOT NULL,
+    PRIMARY KEY (run_id, cinema_id, film_id)
+);
+CREATE TABLE IF NOT EXISTS screenings (
+    cinema_id INTEGER NOT NULL REFERENCES cinemas(id),
+    film_id INTEGER NOT NULL REFERENCES films(id),
+    start TEXT NOT NULL,
+    hall TEXT NOT NULL DEFAULT '',
+    first_run_id INTEGER NOT NULL REFERENCES runs(id),
+    PRIMARY KEY (cinema_id, film_id, start, hall)
+);
+";
+
+pub struct History {
+    conn: Connection,
+}
+
+/// One cinema's run of a film, as answered by [`History::film`].
+#[derive(Debug, PartialEq)]
+pub struct FilmStay {
+    pub title: String,
+    pub cinema: String,
+    /// First and last run that had the film in programme.
+    pub first_seen: String,
+    pub last_seen: String,
+    pub screenings: u32,
+}
+
+impl History {
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        Self::init(Connection::open(path)?)
+    }
+
+    fn init(conn: Connection) -> rusqlite::Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        // Databases from before films were told apart by year and director.
+        let has_year = conn
+            .prepare("SELECT 1 FROM pragma_table_info('films') WHERE name = 'year'")?
+            .exists([])?;
+        if !has_year {
+            conn.execute_batch(
+                "ALTER TABLE films ADD COLUMN year INTEGER;
+                 ALTER TABLE films ADD COLUMN director TEXT;",
+            )?;
+        }
+        Ok(Self { conn })
+    }
+
+    /// Store one run: the films every cinema published fresh at `started_at`.
+    /// Films served from a snapshot were not seen in this run and are skipped.
+    pub fn record(
+        &mut self,
+        started_at: DateTime<Utc>,
+        results: &[(&str, &[Film])],
+    ) -> rusqlite::Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO runs (started_at) VALUES (?1)",
+            params![started_at.to_rfc3339()],
+        )?;
+        let run_id = tx.last_insert_rowid();
+        for (cinema, films) in results {
+            tx.execute(
+                "INSERT OR IGNORE INTO cinemas (name) VALUES (?1)",
+                params![cinema],
+            )?;
+            let cinema_id: i64 = tx.query_row(
+                "SELECT id FROM cinemas WHERE name = ?1",
+                params![cinema],
+                |row| row.get(0),
+            )?;
+            for film in films.iter().filter(|f| f.stale_since.is_none()) {
+                let film_id = film_id(&tx, film)?;
+                tx.execute(
+                    "INSERT OR IGNORE INTO listings (run_id, cinema_id, film_id, url)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![run_id, cinema_id, film_id, film.url],
+                )?;
+                for showtime in film.showtimes.iter().flatten() {
+                    tx.execute(
+                        "INSERT OR IGNORE INTO screenings (cinema_id, film_id, start, hall, first_run_id)
+                         VALUES (?1, ?2, ?3, ?4, ?5)",
+                        params![
+                            cinema_id,
+                            film_id,
+                            showtime.start.to_rfc3339(),
+                            showtime.hall.as_deref().unwrap_or_default(),
+                            run_id
+                        ],
+                    )?;
+                }
+            }
+        }
+        tx.commit()
+    }
+
+    /// Every cinema that had a film whose title contains `title` (ignoring case and
+    /// accents) in programme, optionally only in runs of `year`.
+    pub fn film(&self, title: &str, year: Option<i32>) -> rusqlite::Result<Vec<FilmStay>> {
+        let pattern = format!("%{}%", dedup::normalize_title(title));
+        let year = year.map(|y| y.to_string());
+        let mut stmt = self.conn.prepare(
+            "SELECT f.title, c.name, min(r.started_at), max(r.started_at),
+                    (SELECT count(*) FROM screenings s
+                     WHERE s.film_id = f.id AND s.cinema_id = c.id
+                       AND (?2 IS NULL OR substr(s.start, 1, 4) = ?2))
+             FROM listings l
+             JOIN runs r ON r.id = l.run_id
+             JOIN cinemas c ON c.id = l.cinema_id
+             JOIN films f ON f.id = l.film_id
+             WHERE substr(f.key, 1, instr(f.key || '|', '|') - 1) LIKE ?1 AND (?2 IS NULL OR substr(r.started_at, 1, 4) = ?2)
+             GROUP BY f.id, c.id
+             ORDER BY f.title, min(r.started_at), c.name",
+        )?;
+        let rows = stmt.query_map(params![pattern, year], |row| {
+            Ok(FilmStay {
+                title: row.get(0)?,
+                cinema: row.get(1)?,
+                first_seen: row.get(2)?,
+                last_seen: row.get(3)?,
+                screenings: row.get(4)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Time of the most recent run, if any.
+    pub fn last_run(&self) -> rusqlite::Result<Option<String>> {
+        self.conn
+            .query_row("SELECT max(started_at) FROM runs", [], |row| row.get(0))
+            .optional()
+            .map(Option::flatten)
+    }
+
+    /// Every screening as CSV (start, cinema, title, hall, url), oldest first.
+    pub fn export_csv(&self, out: &mut impl Write) -> Result<(), Box<dyn std::error::Error>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT s.start, c.name, f.title, s.hall,
+                    (SELECT l.url FROM listings l
+                     WHERE l.film_id = f.id AND l.cinema_id = c.id
+                     ORDER BY l.run_id DESC LIMIT 1)
+             FROM screenings s
+             JOIN cinemas c ON c.id = s.cinema_id
+             JOIN films f ON f.id = s.film_id
+             ORDER BY s.start, c.name, f.title",
+        )?;
+        writeln!(out, "start,cinema,title,hall,url")?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let fields: Vec<String> = (0..5)
+                .map(|i| {
+                    row.get::<_, Option<String>>(i)
+                        .map(Option::unwrap_or_default)
+                })
+                .collect::<Result<_, _>>()?;
+            let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+            writeln!(out, "{}", line.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// Row of `film` in `films`: the first stored film with its title that
+/// [`dedup::same_film`] accepts, updated with what `film` adds, or a new row.
+fn film_id(conn: &Connection, film: &Film) -> rusqlite::Result<i64> {
+    let title = dedup::normalize_title(&film.title);
+    let year = dedup::year(film);
+    let director = dedup::director(film);
+
+    let mut stmt = conn.prepare(
+        "SELECT id, title, year, director, running_time FROM films
+         WHERE key = ?1 OR key LIKE ?1 || '|%'
+         ORDER BY id",
+    )?;
+    let stored = stmt
+        .query_map(params![title], |row| {
+            let film = Film {
+                title: row.get(1)?,
+                year: row.get(2)?,
+                director: row.get(3)?,
+                running_time: row.get(4)?,
+                ..Default::default()
+            };
+            Ok((row.get::<_, i64>(0)?, film))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    if let Some((id, _)) = stored
+        .iter()
+        .find(|(_, s)| dedup::same_film(s, film, dedup::RUNTIME_TOLERANCE))
+    {
+        conn.execute(
+            "UPDATE films SET
+                 release_date = coalesce(release_date, ?2),
+                 running_time = coalesce(running_time, ?3),
+                 year = coalesce(year, ?4),
+                 director = coalesce(director, ?5)
+             WHERE id = ?1",
+            params![id, film.release_date, film.running_time, year, director],
+        )?;
+        return Ok(*id);
+    }
+
+    // The title alone while nothing else is known, so older rows keep their key.
+    let key = if year.is_none() && director.is_none() {
+        title
+    } else {
+        format!(
+            "{title}|{}|{}",
+            year.map(|y| y.to_string()).unwrap_or_default(),
+            director.as_deref().unwrap_or_default()
+        )
+    };
+    conn.execute(
+        "INSERT INTO films (key, title, release_date, running_time, year, director)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            key,
+            film.title,
+            film.release_date,
+            film.running_time,
+            year,
+            director
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Write `stays` as CSV or as an aligned table.
+pub fn print_stays(stays: &[FilmStay], csv: bool) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    if csv {
+        writeln!(out, "title,cinema,first_seen,last_seen,screenings")?;
+        for s in stays {
+            writeln!(
+                out,
+                "{},{},{},{},{}",
+                csv_field(&s.title),
+                csv_field(&s.cinema),
+                s.first_seen,
+                s.last_seen,
+                s.screenings
+            )?;
+        }
+        return Ok(());
+    }
+    for s in stays {
+        let day = |t: &str| t.get(..10).unwrap_or(t).to_string();
+        writeln!(
+            out,
+            "{:<32} {:<36} {} → {}  {} screenings",
+            s.title,
+            s.cinema,
+            day(&s.first_seen),
+            day(&s.last_seen),
+            s.screenings
+        )?;
+    }
+    Ok(())
+}
+
+/// A CSV field, quoted when it holds a comma, quote or line break (RFC 4180).
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use cinema_scrape::Showtime;
+
+    fn film(title: &str, url: &str, day: u32) -> Film {
+        let start = chrono_tz::Europe::Rome
+            .with_ymd_and_hms(2026, 2, day, 20, 30, 0)
+            .unwrap()
+            .fixed_offset();
+        Film {
+            title: title.to_string(),
+            url: url.to_string(),
+            running_time: Some(185),
+            showtimes: Some(vec![Showtime::new(start)]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn answers_which_cinemas_showed_a_film() {
+        let mut history = History::init(Connection::open_in_memory().unwrap()).unwrap();
+        let monday = Utc.with_ymd_and_hms(2026, 2, 9, 6, 0, 0).unwrap();
+
+        let rex = [film("Barry Lyndon", "https://rex/barry", 10)];
+        let edera = [film("BARRY LYNDON (V.O.)", "https://edera/barry", 12)];
+        history
+            .record(monday, &[("Cinema Rex", &rex), ("Cinema Edera", &edera)])
+            .unwrap();
+        // A week later only the Rex still has it, with the same screening again.
+        let rex_later = [film("Barry Lyndon", "https://rex/barry", 10)];
+        history
+            .record(monday + Duration::days(7), &[("Cinema Rex", &rex_later)])
+            .unwrap();
+
+        let stays = history.film("barry lyndon", Some(2026)).unwrap();
+        let cinemas: Vec<(&str, u32)> = stays
+            .iter()
+            .map(|s| (s.cinema.as_str(), s.screenings))
+            .collect();
+        assert_eq!(cinemas, [("Cinema Edera", 1), ("Cinema Rex", 1)]);
+        assert!(stays[1].last_seen.starts_with("2026-02-16"));
+        assert!(history.film("barry lyndon", Some(2025)).unwrap().is_empty());
+
+        let mut csv = Vec::new();
+        history.export_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 3);
+        assert!(csv.contains(",Cinema Edera,Barry Lyndon,,https://edera/barry"));
+    }
+    #[test]
+    fn keeps_films_sharing_a_title_apart() {
+        let mut history = History::init(Connection::open_in_memory().unwrap()).unwrap();
+        let monday = Utc.with_ymd_and_hms(2026, 2, 9, 6, 0, 0).unwrap();
+
+        let nolan = Film {
+            director: Some("Christopher Nolan".to_string()),
+            year: Some(2026),
+            ..film("Odissea", "https://space/odissea", 10)
+        };
+        let classic = Film {
+            year: Some(1997),
+            ..film("Odissea", "https://edera/odissea", 11)
+        };
+        // No year or director: the same film as whichever it does not contradict.
+        let bare = film("ODISSEA (V.O.)", "https://rex/odissea", 12);
+        history
+            .record(
+                monday,
+                &[("Space", &[nolan]), ("Edera", &[classic]), ("Rex", &[bare])],
+            )
+            .unwrap();
+
+        let films: i64 = history
+            .conn
+            .query_row("SELECT count(*) FROM films", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(films, 2);
+        assert_eq!(history.film("odissea", None).unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,340 @@
+//! Shared HTTP client with bounded parallelism.
+//! Every request takes a permit for its host and a global permit before it is sent,
+//! and keeps both until the response body has been read. Scrapers can therefore fan
+//! out freely: the limits decide how many requests really hit the network at once.
+//!
+//! The client can also record every response to a directory (`--record`) and serve
+//! them back later without touching the network (`--replay`). Each response is saved
+//! as `<host>/<path>-<hash>.body` (the raw bytes) next to a `.json` file holding its
+//! URL, status and content type, so a recorded run can be inspected by hand.
+
+use crate::{ScrapeError, fnv1a};
+use chrono::{DateTime, Utc};
+use reqwest::header::{CONTENT_TYPE, HeaderName, HeaderValue};
+use reqwest::{Client, IntoUrl, ResponseBuilderExt, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Default number of requests in flight across all hosts.
+pub const DEFAULT_MAX_CONCURRENCY: usize = 16;
+/// Default number of requests in flight against a single host.
+pub const DEFAULT_PER_HOST: usize = 2;
+
+/// File in a recording directory holding the time the recording started.
+const RECORDED_AT: &str = "recorded_at";
+
+/// Where responses come from.
+enum Mode {
+    /// The network.
+    Live,
+    /// The network, saving every response under this directory.
+    Record(PathBuf),
+    /// Responses saved by an earlier recording; the network is never used.
+    Replay(PathBuf),
+}
+
+/// A `reqwest::Client` wrapper that enforces a global and a per-host concurrency limit.
+pub struct HttpClient {
+    inner: Client,
+    global: Arc<Semaphore>,
+    per_host: usize,
+    hosts: Mutex<HashMap<String, Arc<Semaphore>>>,
+    mode: Mode,
+}
+
+impl HttpClient {
+    pub fn new(inner: Client, max_concurrency: usize, per_host: usize) -> Self {
+        Self {
+            inner,
+            global: Arc::new(Semaphore::new(max_concurrency.max(1))),
+            per_host: per_host.max(1),
+            hosts: Mutex::new(HashMap::new()),
+            mode: Mode::Live,
+        }
+    }
+
+    /// Save every response under `dir`, stamped with `started`, the time the run
+    /// considers "now" (see [`crate::clock`]).
+    pub fn recording(mut self, dir: PathBuf, started: DateTime<Utc>) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(RECORDED_AT), started.to_rfc3339() + "\n")?;
+        self.mode = Mode::Record(dir);
+        Ok(self)
+    }
+
+    /// Serve the responses saved under `dir` instead of using the network. Also
+    /// returns the time that recording started.
+    pub fn replaying(mut self, dir: PathBuf) -> io::Result<(Self, DateTime<Utc>)> {
+        let stamp = fs::read_to_string(dir.join(RECORDED_AT))?;
+        let started = DateTime::parse_from_rfc3339(stamp.trim())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+            .with_timezone(&Utc);
+        self.mode = Mode::Replay(dir);
+        Ok((self, started))
+    }
+
+    pub fn get<U: IntoUrl>(&self, url: U) -> RequestBuilder<'_> {
+        RequestBuilder {
+            client: self,
+            inner: self.inner.get(url),
+        }
+    }
+
+    fn host_semaphore(&self, host: &str) -> Arc<Semaphore> {
+        let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        hosts
+            .entry(host.to_string())
+            .or_insert_with(|| Arc::new(Semaphore::new(self.per_host)))
+            .clone()
+    }
+}
+
+/// Request builder mirroring the subset of `reqwest::RequestBuilder` the scrapers use.
+pub struct RequestBuilder<'a> {
+    client: &'a HttpClient,
+    inner: reqwest::RequestBuilder,
+}
+
+impl RequestBuilder<'_> {
+    pub fn header<K, V>(mut self, key: K, value: V) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<::http::Error>,
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<::http::Error>,
+    {
+        self.inner = self.inner.header(key, value);
+        self
+    }
+
+    pub fn query<T: Serialize + ?Sized>(mut self, query: &T) -> Self {
+        self.inner = self.inner.query(query);
+        self
+    }
+
+    /// Wait for a host permit, then a global one, and send the request.
+    /// When replaying, answer from the recording instead.
+    pub async fn send(self) -> Result<Response, ScrapeError> {
+        let (client, request) = self.inner.build_split();
+        let request = request?;
+        if let Mode::Replay(ref dir) = self.client.mode {
+            return Ok(Response {
+                inner: replay(dir, request.url())?,
+                _permits: None,
+            });
+        }
+        let host = request.url().host_str().unwrap_or_default().to_string();
+
+        // Host first: a request queued behind a busy host must not hold a global slot.
+        let host_permit = self
+            .client
+            .host_semaphore(&host)
+            .acquire_owned()
+            .await
+            .expect("host semaphore is never closed");
+        let global_permit = self
+            .client
+            .global
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("global semaphore is never closed");
+
+        let url = request.url().clone();
+        let mut inner = client.execute(request).await?;
+        if let Mode::Record(ref dir) = self.client.mode {
+            inner = record(dir, &url, inner).await?;
+        }
+        Ok(Response {
+            inner,
+            _permits: Some((host_permit, global_permit)),
+        })
+    }
+}
+
+/// What is saved next to each recorded body.
+#[derive(Serialize, Deserialize)]
+struct Recorded {
+    /// The requested URL.
+    url: String,
+    /// The URL the response came from, after redirects.
+    final_url: String,
+    status: u16,
+    content_type: Option<String>,
+}
+
+/// File of a recorded response: the host as a directory, then a readable form of the
+/// path and query and a hash of the full URL, e.g.
+/// `www.berlinale.de/en-2026-programme-202608333.html-0123456789abcdef.body`.
+fn recording_path(dir: &Path, url: &Url, extension: &str) -> PathBuf {
+    let host = url.host_str().unwrap_or("no-host");
+    let rest = format!("{}?{}", url.path(), url.query().unwrap_or_default());
+    let mut name: String = rest
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    name = name.trim_matches('-').chars().take(80).collect();
+    if !name.is_empty() {
+        name.push('-');
+    }
+    dir.join(host).join(format!(
+        "{name}{:016x}.{extension}",
+        fnv1a(url.as_str().bytes())
+    ))
+}
+
+/// Read the whole response, save it and hand back an identical one.
+async fn record(
+    dir: &Path,
+    url: &Url,
+    response: reqwest::Response,
+) -> reqwest::Result<reqwest::Response> {
+    let status = response.status();
+    let final_url = response.url().clone();
+    let content_type = response.headers().get(CONTENT_TYPE).cloned();
+    let body = response.bytes().await?;
+
+    let meta = Recorded {
+        url: url.to_string(),
+        final_url: final_url.to_string(),
+        status: status.as_u16(),
+        content_type: content_type
+            .as_ref()
+            .and_then(|v| v.to_str().ok())
+            .map(String::from),
+    };
+    let save = || -> io::Result<()> {
+        let body_path = recording_path(dir, url, "body");
+        if let Some(parent) = body_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(body_path, &body)?;
+        let json = serde_json::to_string_pretty(&meta).map_err(io::Error::other)?;
+        fs::write(recording_path(dir, url, "json"), json + "\n")
+    };
+    // A response that could not be saved is still used; the replay will miss it.
+    if let Err(e) = save() {
+        eprintln!("Could not record {url}: {e}");
+    }
+
+    Ok(rebuild(status, final_url, content_type, body.to_vec()))
+}
+
+/// The recorded response for `url`, or [`ScrapeError::NotRecorded`].
+fn replay(dir: &Path, url: &Url) -> Result<reqwest::Response, ScrapeError> {
+    let load = || -> Option<reqwest::Response> {
+        let json = fs::read_to_string(recording_path(dir, url, "json")).ok()?;
+        let meta: Recorded = serde_json::from_str(&json).ok()?;
+        let body = fs::read(recording_path(dir, url, "body")).ok()?;
+        let content_type = meta
+            .content_type
+            .and_then(|ct| HeaderValue::from_str(&ct).ok());
+        Some(rebuild(
+            StatusCode::from_u16(meta.status).ok()?,
+            Url::parse(&meta.final_url).ok()?,
+            content_type,
+            body,
+        ))
+    };
+    load().ok_or_else(|| ScrapeError::NotRecorded {
+        url: url.to_string(),
+    })
+}
+
+/// A response with the given status, URL, content type and body, as if it had just
+/// been read from the network.
+fn rebuild(
+    status: StatusCode,
+    url: Url,
+    content_type: Option<HeaderValue>,
+    body: Vec<u8>,
+) -> reqwest::Response {
+    let mut builder = ::http::Response::builder().status(status).url(url);
+    if let Some(content_type) = content_type {
+        builder = builder.header(CONTENT_TYPE, content_type);
+    }
+    builder
+        .body(body)
+        .expect("status and headers come from a valid response")
+        .into()
+}
+
+/// Response that holds its concurrency permits until the body is consumed or dropped.
+pub struct Response {
+    inner: reqwest::Response,
+    _permits: Option<(OwnedSemaphorePermit, OwnedSemaphorePermit)>,
+}
+
+impl Response {
+    pub fn status(&self) -> StatusCode {
+        self.inner.status()
+    }
+
+    pub fn error_for_status(self) -> reqwest::Result<Self> {
+        let Response { inner, _permits } = self;
+        Ok(Response {
+            inner: inner.error_for_status()?,
+            _permits,
+        })
+    }
+
+    pub async fn text(self) -> reqwest::Result<String> {
+        self.inner.text().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hosts_share_one_semaphore() {
+        let client = HttpClient::new(Client::new(), 4, 2);
+        let a = client.host_semaphore("example.com");
+        let b = client.host_semaphore("example.com");
+        let c = client.host_semaphore("example.org");
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(!Arc::ptr_eq(&a, &c));
+        assert_eq!(a.available_permits(), 2);
+    }
+
+    #[tokio::test]
+    async fn replays_what_was_recorded() {
+        let dir = std::env::temp_dir().join(format!("cinema-scrape-http-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let url = Url::parse("https://www.example.it/film/la-grazia?ref_date=2026-02-09").unwrap();
+        // Latin-1 bytes and an error status must both survive the round trip.
+        let body = b"Citt\xe0 aperta".to_vec();
+        let content_type = HeaderValue::from_static("text/html; charset=iso-8859-1");
+        let live = rebuild(
+            StatusCode::NOT_FOUND,
+            url.clone(),
+            Some(content_type),
+            body.clone(),
+        );
+        record(&dir, &url, live).await.unwrap();
+
+        let replayed = replay(&dir, &url).unwrap();
+        assert_eq!(replayed.status(), StatusCode::NOT_FOUND);
+        assert_eq!(replayed.url(), &url);
+        assert_eq!(replayed.text().await.unwrap(), "Città aperta");
+
+        let other = Url::parse("https://www.example.it/film/altro").unwrap();
+        assert!(matches!(
+            replay(&dir, &other),
+            Err(ScrapeError::NotRecorded { .. })
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
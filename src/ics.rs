@@ -0,0 +1,160 @@
+//! iCalendar (RFC 5545) export: one VEVENT per screening, so a feed can be
+//! subscribed to from a calendar app.
+
+use crate::{Film, fnv1a, last_change};
+use chrono::{DateTime, Duration, Utc};
+
+/// Generate an iCalendar file with every screening of every cinema.
+/// Each event's UID is derived from the film URL, start time and hall, so a
+/// re-generated calendar updates existing events instead of duplicating them.
+pub fn generate_ics(calendar_name: &str, sources: &[(&str, &[Film])]) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//cinema-scrape//cinema-scrape//IT".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!("X-WR-CALNAME:{}", escape(calendar_name)),
+    ];
+    for (cinema_name, films) in sources {
+        for film in *films {
+            for st in film.showtimes.iter().flatten() {
+                let start = st.start.with_timezone(&Utc);
+                let end = st.end.map(|e| e.with_timezone(&Utc)).or_else(|| {
+                    film.running_time
+                        .map(|minutes| start + Duration::minutes(i64::from(minutes)))
+                });
+                let location = match st.hall {
+                    Some(ref hall) => format!("{cinema_name}, {hall}"),
+                    None => cinema_name.to_string(),
+                };
+                let mut description = film.url.clone();
+                if let Some(ref synopsis) = film.synopsis {
+                    description.push_str("\n\n");
+                    description.push_str(synopsis);
+                }
+                let mut summary = film.title.clone();
+                if let Some(ref version) = st.version {
+                    summary.push_str(&format!(" ({version})"));
+                }
+
+                lines.push("BEGIN:VEVENT".to_string());
+                lines.push(format!("UID:{}", uid(film, start, st.hall.as_deref())));
+                lines.push(format!("DTSTAMP:{}", utc_stamp(last_change([film]))));
+                lines.push(format!("DTSTART:{}", utc_stamp(start)));
+                if let Some(end) = end {
+                    lines.push(format!("DTEND:{}", utc_stamp(end)));
+                }
+                lines.push(format!("SUMMARY:{}", escape(&summary)));
+                lines.push(format!("LOCATION:{}", escape(&location)));
+                lines.push(format!("DESCRIPTION:{}", escape(&description)));
+                lines.push(format!(
+                    "URL:{}",
+                    st.ticket_url.as_deref().unwrap_or(&film.url)
+                ));
+                lines.push("END:VEVENT".to_string());
+            }
+        }
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut out = String::new();
+    for line in lines {
+        fold_into(&mut out, &line);
+    }
+    out
+}
+
+fn utc_stamp(dt: DateTime<Utc>) -> String {
+    dt.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// "20261017T183000Z-<hash of url and hall>@cinema-scrape": stable across runs.
+fn uid(film: &Film, start: DateTime<Utc>, hall: Option<&str>) -> String {
+    let hash = fnv1a(
+        film.url
+            .bytes()
+            .chain([0])
+            .chain(hall.unwrap_or("").bytes()),
+    );
+    format!("{}-{hash:016x}@cinema-scrape", utc_stamp(start))
+}
+
+/// Escape a TEXT value (RFC 5545 §3.3.11).
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Append a content line folded at 75 octets, without splitting UTF-8 characters.
+fn fold_into(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Showtime;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn one_event_per_screening_with_runtime_end() {
+        let day = NaiveDate::from_ymd_opt(2026, 10, 17).unwrap();
+        let at = |h, m| {
+            Showtime::at(
+                day,
+                chrono::NaiveTime::from_hms_opt(h, m, 0).unwrap(),
+                chrono_tz::Europe::Rome,
+            )
+            .unwrap()
+        };
+        let film = Film {
+            title: "Stalker".to_string(),
+            url: "https://example.com/stalker".to_string(),
+            running_time: Some(161),
+            synopsis: Some("La Zona, il Pedinatore; lo Scrittore, il Professore.".repeat(3)),
+            showtimes: Some(vec![
+                at(17, 0),
+                at(20, 30).with_hall(Some("Sala 2".to_string())),
+            ]),
+            ..Default::default()
+        };
+        let films = [film];
+        let ics = generate_ics("Trieste", &[("Cinema Ariston", &films)]);
+
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(ics.contains("DTSTART:20261017T150000Z\r\nDTEND:20261017T174100Z"));
+        assert!(ics.contains("LOCATION:Cinema Ariston\\, Sala 2"));
+        assert!(ics.lines().all(|l| l.len() <= 75));
+        // Same input, same UIDs.
+        let uids = |s: &str| {
+            s.lines()
+                .filter(|l| l.starts_with("UID:"))
+                .map(String::from)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            uids(&ics),
+            uids(&generate_ics("Trieste", &[("Cinema Ariston", &films)]))
+        );
+    }
+}
@@ -0,0 +1,350 @@
+//! Audio and subtitle languages of a screening, read from the tags cinemas put in
+//! titles, session attributes or next to the times: "V.O.", "V.O.S.", "OV",
+//! "versione originale sottotitolata", "sott. it.", "English subtitles", etc.
+
+use crate::Locale;
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+/// What is known about a screening's languages. Languages are ISO 639-1 codes ("en").
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Languages {
+    /// Shown in the original language rather than dubbed.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub original_version: bool,
+    /// Spoken language, when the cinema names it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subtitles: Option<String>,
+}
+
+/// (code, Italian name, English name, words that name the language).
+const LANGUAGES: &[(&str, &str, &str, &[&str])] = &[
+    (
+        "it",
+        "italiano",
+        "Italian",
+        &["italiano", "italiana", "italiani", "italian", "ita"],
+    ),
+    (
+        "en",
+        "inglese",
+        "English",
+        &["inglese", "inglesi", "english", "eng"],
+    ),
+    (
+        "fr",
+        "francese",
+        "French",
+        &["francese", "francesi", "french", "francais", "fra"],
+    ),
+    (
+        "de",
+        "tedesco",
+        "German",
+        &[
+            "tedesco", "tedesca", "tedeschi", "german", "deutsch", "deu", "ger",
+        ],
+    ),
+    (
+        "es",
+        "spagnolo",
+        "Spanish",
+        &["spagnolo", "spagnola", "spanish", "espanol", "spa"],
+    ),
+    (
+        "pt",
+        "portoghese",
+        "Portuguese",
+        &["portoghese", "portuguese"],
+    ),
+    ("ru", "russo", "Russian", &["russo", "russa", "russian"]),
+    (
+        "uk",
+        "ucraino",
+        "Ukrainian",
+        &["ucraino", "ucraina", "ukrainian"],
+    ),
+    ("pl", "polacco", "Polish", &["polacco", "polacca", "polish"]),
+    ("sv", "svedese", "Swedish", &["svedese", "swedish"]),
+    ("da", "danese", "Danish", &["danese", "danish"]),
+    ("nl", "olandese", "Dutch", &["olandese", "dutch"]),
+    ("el", "greco", "Greek", &["greco", "greca", "greek"]),
+    ("tr", "turco", "Turkish", &["turco", "turca", "turkish"]),
+    ("ar", "arabo", "Arabic", &["arabo", "araba", "arabic"]),
+    ("he", "ebraico", "Hebrew", &["ebraico", "hebrew"]),
+    (
+        "fa",
+        "persiano",
+        "Persian",
+        &["persiano", "farsi", "persian"],
+    ),
+    ("hi", "hindi", "Hindi", &["hindi"]),
+    (
+        "zh",
+        "cinese",
+        "Chinese",
+        &["cinese", "mandarino", "chinese", "mandarin"],
+    ),
+    ("ja", "giapponese", "Japanese", &["giapponese", "japanese"]),
+    ("ko", "coreano", "Korean", &["coreano", "coreana", "korean"]),
+];
+
+/// Two-letter codes, only taken as a language right after a subtitles word ("sott. it.").
+const CODES: &[&str] = &["it", "en", "fr", "de", "es"];
+
+const SUBTITLE_WORDS: &[&str] = &[
+    "sub",
+    "subs",
+    "sott",
+    "sottotitoli",
+    "sottotitolo",
+    "sottotitolato",
+    "sottotitolata",
+    "sottotitolati",
+    "subtitles",
+    "subtitled",
+];
+
+/// Lowercase, accent-free words of a tag, split on anything but letters and digits
+/// and between digits and letters: "20.00V.O.S" -> ["2000", "vos"], "35mm" -> ["35", "mm"].
+pub(crate) fn words(text: &str) -> Vec<String> {
+    let folded: String = text
+        .nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .collect::<String>()
+        .to_lowercase()
+        .replace('.', "");
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut prev_digit = false;
+    for (at, c) in folded.char_indices() {
+        let boundary = !c.is_alphanumeric() || c.is_ascii_digit() != prev_digit;
+        if boundary && let Some(s) = start.take() {
+            words.push(folded[s..at].to_string());
+        }
+        if c.is_alphanumeric() && start.is_none() {
+            start = Some(at);
+        }
+        prev_digit = c.is_ascii_digit();
+    }
+    if let Some(s) = start {
+        words.push(folded[s..].to_string());
+    }
+    words
+}
+
+fn language(word: &str, codes: bool) -> Option<&'static str> {
+    LANGUAGES
+        .iter()
+        .find(|(code, _, _, words)| words.contains(&word) || (codes && *code == word))
+        .filter(|(code, _, _, _)| !codes || CODES.contains(code) || word.len() > 2)
+        .map(|(code, _, _, _)| *code)
+}
+
+impl Languages {
+    /// Languages named in `text`; `Languages::default()` when it has no such tag.
+    pub fn parse(text: &str) -> Self {
+        let words = words(text);
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        let next = |i: usize| words.get(i + 1).copied().unwrap_or_default();
+
+        let mut out = Self::default();
+        let mut i = 0;
+        while i < words.len() {
+            let word = words[i];
+            match word {
+                "vo" | "ov" | "omu" | "vost" => out.original_version = true,
+                // "V.O.S.": versione originale sottotitolata, in Italian.
+                "vos" => {
+                    out.original_version = true;
+                    out.subtitles.get_or_insert_with(|| "it".to_string());
+                }
+                "originale" | "original"
+                    if (i > 0 && matches!(words[i - 1], "versione" | "lingua"))
+                        || matches!(next(i), "version" | "language") =>
+                {
+                    out.original_version = true;
+                    // "versione originale russa"
+                    if let Some(code) = language(next(i), false) {
+                        out.audio = Some(code.to_string());
+                        i += 1;
+                    }
+                }
+                // "in lingua inglese", "in English", "versione italiana"
+                "lingua" | "in" | "versione" if language(next(i), false).is_some() => {
+                    let code = language(next(i), false).unwrap_or_default();
+                    out.original_version |= code != "it";
+                    out.audio = Some(code.to_string());
+                    i += 1;
+                }
+                "doppiato" | "doppiata" | "dubbed" => {
+                    out.original_version = false;
+                    let code = match next(i) {
+                        "in" => language(words.get(i + 2).copied().unwrap_or_default(), false),
+                        w => language(w, false),
+                    };
+                    out.audio = Some(code.unwrap_or("it").to_string());
+                }
+                w if SUBTITLE_WORDS.contains(&w) => {
+                    out.original_version = true;
+                    // "sottotitoli in italiano", "sub ita", "sott. it.", "English subtitles"
+                    let after = match next(i) {
+                        "in" | "con" => words.get(i + 2).copied().unwrap_or_default(),
+                        w => w,
+                    };
+                    // "English subtitles", in English only.
+                    let before = match i {
+                        0 => "",
+                        _ if w.starts_with("sub") => words[i - 1],
+                        _ => "",
+                    };
+                    let code = language(after, true)
+                        .or_else(|| language(before, false))
+                        .or_else(|| w.starts_with("sott").then_some("it"));
+                    if let Some(code) = code {
+                        out.subtitles = Some(code.to_string());
+                        if language(after, true).is_some() {
+                            i += if matches!(next(i), "in" | "con") {
+                                2
+                            } else {
+                                1
+                            };
+                        }
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        out
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Fill in what `self` does not know from `other`, e.g. a screening's own tag
+    /// completed by the film's.
+    pub fn or(self, other: &Languages) -> Self {
+        if self.is_empty() {
+            return other.clone();
+        }
+        // A dubbed screening of an original-version film, or the other way round.
+        if self.original_version != other.original_version {
+            return self;
+        }
+        Self {
+            audio: self.audio.or_else(|| other.audio.clone()),
+            subtitles: self.subtitles.or_else(|| other.subtitles.clone()),
+            ..self
+        }
+    }
+
+    /// "V.O. in russo, sottotitoli in italiano" / "Original version in Russian, Italian
+    /// subtitles"; `None` for dubbed screenings and when nothing is known.
+    pub fn label(&self, locale: Locale) -> Option<String> {
+        if !self.original_version {
+            return None;
+        }
+        let name = |code: &str| {
+            LANGUAGES
+                .iter()
+                .find(|(c, _, _, _)| *c == code)
+                .map(|(_, it, en, _)| match locale {
+                    Locale::Italian => it.to_string(),
+                    Locale::English => en.to_string(),
+                })
+                .unwrap_or_else(|| code.to_string())
+        };
+        let mut out = match (locale, &self.audio) {
+            (Locale::Italian, Some(audio)) => format!("V.O. in {}", name(audio)),
+            (Locale::Italian, None) => "V.O.".to_string(),
+            (Locale::English, Some(audio)) => format!("Original version in {}", name(audio)),
+            (Locale::English, None) => "Original version".to_string(),
+        };
+        match (locale, &self.subtitles) {
+            (Locale::Italian, Some(subs)) => {
+                out.push_str(&format!(", sottotitoli in {}", name(subs)))
+            }
+            (Locale::English, Some(subs)) => out.push_str(&format!(", {} subtitles", name(subs))),
+            (_, None) => {}
+        }
+        Some(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> (bool, Option<String>, Option<String>) {
+        let l = Languages::parse(text);
+        (l.original_version, l.audio, l.subtitles)
+    }
+
+    fn tags(
+        original_version: bool,
+        audio: Option<&str>,
+        subtitles: Option<&str>,
+    ) -> (bool, Option<String>, Option<String>) {
+        (
+            original_version,
+            audio.map(String::from),
+            subtitles.map(String::from),
+        )
+    }
+
+    #[test]
+    fn reads_version_tags() {
+        assert_eq!(parse("BARRY LYNDON (V.O.)"), tags(true, None, None));
+        assert_eq!(parse("20.00V.O.S"), tags(true, None, Some("it")));
+        assert_eq!(parse("v.o. sott. it."), tags(true, None, Some("it")));
+        assert_eq!(
+            parse("versione originale russa"),
+            tags(true, Some("ru"), None)
+        );
+        assert_eq!(
+            parse("versione originale con sottotitoli italiani"),
+            tags(true, None, Some("it"))
+        );
+        assert_eq!(
+            parse("In English with Italian subtitles"),
+            tags(true, Some("en"), Some("it"))
+        );
+        assert_eq!(
+            parse("Odissea (OV, English subtitles)"),
+            tags(true, None, Some("en"))
+        );
+        assert_eq!(
+            parse("in lingua inglese sub ita"),
+            tags(true, Some("en"), Some("it"))
+        );
+        assert_eq!(parse("Versione italiana"), tags(false, Some("it"), None));
+        assert_eq!(parse("Doppiato in italiano"), tags(false, Some("it"), None));
+        // Titles that only look like tags.
+        assert_eq!(parse("It Follows"), tags(false, None, None));
+        assert_eq!(parse("La lingua dei segni"), tags(false, None, None));
+
+        let label = Languages::parse("versione originale russa, sottotitoli in italiano");
+        assert_eq!(
+            label.label(Locale::Italian).unwrap(),
+            "V.O. in russo, sottotitoli in italiano"
+        );
+        assert_eq!(
+            label.label(Locale::English).unwrap(),
+            "Original version in Russian, Italian subtitles"
+        );
+        assert_eq!(Languages::parse("Doppiato").label(Locale::Italian), None);
+
+        // A screening's own tag wins over the film's, and is completed by it.
+        let film = Languages::parse("versione originale russa");
+        assert!(!Languages::parse("Doppiato").or(&film).original_version);
+        assert_eq!(
+            Languages::parse("V.O.S").or(&film).audio.as_deref(),
+            Some("ru")
+        );
+    }
+}
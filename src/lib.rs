@@ -1,28 +1,118 @@
-use reqwest::Client;
+pub mod clock;
+pub mod dedup;
+pub mod digest;
+pub mod error;
+pub mod feed;
+pub mod filter;
+pub mod http;
+pub mod ics;
+pub mod language;
+pub mod notify;
+pub mod projection;
+pub mod showtime;
+pub mod site;
+pub mod watchlist;
+
+use chrono::{DateTime, Utc};
 use rss::{Category, ChannelBuilder, ItemBuilder};
+use serde::{Deserialize, Serialize};
+
+pub use dedup::{FilmGroup, group_films};
+pub use digest::{Digest, Period, render_digest};
+pub use error::ScrapeError;
+pub use feed::{FeedFormat, FeedSpec, Grouping};
+pub use filter::{FilmFilter, FilterRules};
+pub use http::HttpClient;
+pub use ics::generate_ics;
+pub use language::Languages;
+pub use notify::{Notice, Notifier};
+pub use projection::Format;
+pub use showtime::{Locale, Showtime};
+pub use site::{SiteFeed, render_site};
+pub use watchlist::{WatchRule, Watchlist};
 
 /// Common film data structure that all scrapers should produce
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Film {
     pub title: String,
     pub url: String,
     pub poster_url: Option<String>,
     pub cast: Option<String>,
+    /// Director(s), comma separated, for scrapers whose pages name them apart from the cast.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub director: Option<String>,
     pub release_date: Option<String>,
+    /// Production year.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub year: Option<u32>,
+    /// Production country or countries, as the cinema writes them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub genres: Vec<String>,
+    /// Title in the original language, when the cinema translates it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_title: Option<String>,
+    /// Spoken language, as the cinema writes it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
     pub running_time: Option<u32>, // in minutes
     pub synopsis: Option<String>,
-    /// Showtimes as "Lunedì 9 Febbraio ore 17:15", "Martedì 10 Febbraio ore 19:10", etc.
-    pub showtimes: Option<Vec<String>>,
+    /// Screenings; rendered as "Lunedì 9 Febbraio ore 17:15", etc. via [`Showtime::display`].
+    pub showtimes: Option<Vec<Showtime>>,
+    /// Set when the film comes from a last-good snapshot instead of a fresh scrape:
+    /// the time that snapshot was taken.
+    #[serde(skip)]
+    pub stale_since: Option<DateTime<Utc>>,
+    /// When the film first appeared in a scrape of its cinema; carried from run to run
+    /// by the snapshots and published as the item date.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_seen: Option<DateTime<Utc>>,
+}
+
+impl Film {
+    /// Tag every screening with the languages of its own version tag, or else those
+    /// the title ("Odissea (V.O.)") or [`Film::language`] name, and with the
+    /// projection formats of the title ("Avatar 3D").
+    pub fn tag_showtimes(&mut self) {
+        let film = Languages::parse(&self.title).or(&Languages::parse(
+            self.language.as_deref().unwrap_or_default(),
+        ));
+        let formats = Format::detect(&self.title);
+        for st in self.showtimes.iter_mut().flatten() {
+            st.languages = std::mem::take(&mut st.languages).or(&film);
+            for format in &formats {
+                if !st.formats.contains(format) {
+                    st.formats.push(*format);
+                }
+            }
+        }
+    }
+
+    /// The film with only the screenings `keep` accepts, or `None` if there are none.
+    pub fn only_screenings(&self, keep: impl Fn(&Showtime) -> bool) -> Option<Film> {
+        let showtimes: Vec<Showtime> = self
+            .showtimes
+            .iter()
+            .flatten()
+            .filter(|st| keep(st))
+            .cloned()
+            .collect();
+        (!showtimes.is_empty()).then(|| Film {
+            showtimes: Some(showtimes),
+            ..self.clone()
+        })
+    }
 }
 
 /// Trait that all cinema scrapers must implement
 #[async_trait::async_trait]
 pub trait CinemaScraper {
     /// Fetch films from the cinema website
-    async fn fetch_films(&self, client: &Client) -> Result<Vec<Film>, Box<dyn std::error::Error>>;
+    async fn fetch_films(&self, client: &HttpClient) -> Result<Vec<Film>, ScrapeError>;
 
     /// Optional: warm-up request to get cookies/auth (default: no-op)
-    async fn warm_up(&self, _client: &Client) -> Result<(), Box<dyn std::error::Error>> {
+    async fn warm_up(&self, _client: &HttpClient) -> Result<(), ScrapeError> {
         Ok(())
     }
 
@@ -30,15 +120,119 @@ pub trait CinemaScraper {
     fn rss_filename(&self) -> String;
 }
 
-/// Build description and optional pub_date for a film (shared by generate_rss and generate_rss_merged).
-fn film_description_and_pub_date(film: &Film) -> (String, Option<String>) {
+/// Genres from a list as cinemas write it, e.g. "Drammatico, Documentario" or
+/// "Commedia / Romantico".
+pub fn split_genres(list: &str) -> Vec<String> {
+    list.split([',', '/'])
+        .map(str::trim)
+        .filter(|g| !g.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// File-name-safe identifier, e.g.
+/// "Cinema Ariston Trieste (La Cappella Underground)" -> "cinema-ariston-trieste-la-cappella-underground"
+pub fn slug(name: &str) -> String {
+    let mut out = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            out.push(c.to_ascii_lowercase());
+        } else if !out.ends_with('-') && !out.is_empty() {
+            out.push('-');
+        }
+    }
+    out.trim_end_matches('-').to_string()
+}
+
+/// 64-bit FNV-1a, for names and IDs that must not change between runs: unlike
+/// `DefaultHasher`, it is the same on every Rust version.
+pub fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// When `films` last changed: the newest `first_seen` or `stale_since` among them, or
+/// the Unix epoch if none has either. Feeds are stamped with this instead of the time
+/// of the run, so regenerating unchanged data gives the same bytes (and ETag).
+pub fn last_change<'a>(films: impl IntoIterator<Item = &'a Film>) -> DateTime<Utc> {
+    films
+        .into_iter()
+        .flat_map(|f| [f.first_seen, f.stale_since])
+        .flatten()
+        .max()
+        .unwrap_or(DateTime::UNIX_EPOCH)
+}
+
+/// Merge the films a scraper collected from several programme days: one film per URL,
+/// in the order first seen, with every showtime sorted and listed once.
+pub fn merge_days(films: Vec<Film>) -> Vec<Film> {
+    let mut merged: Vec<Film> = Vec::new();
+    for film in films {
+        match merged.iter_mut().find(|f| f.url == film.url) {
+            Some(existing) => {
+                if let Some(more) = film.showtimes {
+                    existing.showtimes.get_or_insert_with(Vec::new).extend(more);
+                }
+            }
+            None => merged.push(film),
+        }
+    }
+    for film in &mut merged {
+        if let Some(ref mut showtimes) = film.showtimes {
+            showtimes.sort_by_key(|st| st.start);
+            // Copies of a screening need not end up adjacent: another hall can start
+            // at the same time, so compare with every screening at that time.
+            let mut unique: Vec<Showtime> = Vec::with_capacity(showtimes.len());
+            for st in showtimes.drain(..) {
+                let seen = unique
+                    .iter()
+                    .rev()
+                    .take_while(|u| u.start == st.start)
+                    .any(|u| *u == st);
+                if !seen {
+                    unique.push(st);
+                }
+            }
+            *showtimes = unique;
+        }
+    }
+    merged
+}
+
+/// Build description and optional pub_date for a film (shared by every output format).
+pub(crate) fn film_description_and_pub_date(
+    film: &Film,
+    locale: Locale,
+) -> (String, Option<String>) {
     let mut description_parts = Vec::new();
+    if let Some(since) = film.stale_since {
+        description_parts.push(stale_notice(since, clock::now(), locale));
+    }
     if let Some(ref synopsis) = film.synopsis {
         description_parts.push(synopsis.clone());
     }
+    if let Some(ref director) = film.director {
+        description_parts.push(format!("Regia: {}", director));
+    }
     if let Some(ref cast) = film.cast {
         description_parts.push(format!("Cast: {}", cast));
     }
+    if let Some(ref original_title) = film.original_title {
+        description_parts.push(format!("Titolo originale: {}", original_title));
+    }
+    if !film.genres.is_empty() {
+        description_parts.push(format!("Genere: {}", film.genres.join(", ")));
+    }
+    match (&film.country, film.year) {
+        (Some(country), Some(year)) => description_parts.push(format!("Paese: {country}, {year}")),
+        (Some(country), None) => description_parts.push(format!("Paese: {country}")),
+        (None, Some(year)) => description_parts.push(format!("Anno: {year}")),
+        (None, None) => {}
+    }
+    if let Some(ref language) = film.language {
+        description_parts.push(format!("Lingua: {}", language));
+    }
     if let Some(ref date) = film.release_date {
         description_parts.push(format!("Data: {}", date));
     }
@@ -51,6 +245,10 @@ fn film_description_and_pub_date(film: &Film) -> (String, Option<String>) {
     if let Some(ref showtimes) = film.showtimes
         && !showtimes.is_empty()
     {
+        let showtimes: Vec<String> = showtimes
+            .iter()
+            .map(|s| site::booking_link(s, s.display(locale)))
+            .collect();
         description_parts.push(format!("Orari: {}", showtimes.join(", ")));
     }
     let description = if description_parts.is_empty() {
@@ -58,17 +256,62 @@ fn film_description_and_pub_date(film: &Film) -> (String, Option<String>) {
     } else {
         description_parts.join("<br/>\n")
     };
-    let pub_date = film.release_date.as_ref().and_then(|date_str| {
-        if date_str.contains("Febbraio")
-            || date_str.contains("Gennaio")
-            || date_str.contains("Marzo")
+    let pub_date = film.first_seen.map(|seen| seen.to_rfc2822());
+    (description, pub_date)
+}
+
+/// Description of a film shown at several cinemas: the shared metadata, then one
+/// line per cinema with a link to its page and its showtimes.
+pub(crate) fn group_description(group: &FilmGroup, locale: Locale) -> String {
+    let (mut description, _) = film_description_and_pub_date(&group.merged(), locale);
+    let now = clock::now();
+    for (cinema, film) in &group.showings {
+        let mut line = format!("<a href=\"{}\">{}</a>", film.url, cinema);
+        if let Some(ref showtimes) = film.showtimes
+            && !showtimes.is_empty()
         {
-            Some(chrono::Utc::now().to_rfc2822())
-        } else {
-            None
+            let showtimes: Vec<String> = showtimes
+                .iter()
+                .map(|s| site::booking_link(s, s.display(locale)))
+                .collect();
+            line.push_str(&format!(": {}", showtimes.join(", ")));
         }
-    });
-    (description, pub_date)
+        if let Some(since) = film.stale_since {
+            line.push_str(&format!(" ({})", stale_notice(since, now, locale)));
+        }
+        description.push_str("<br/>\n");
+        description.push_str(&line);
+    }
+    description
+}
+
+/// Warning shown on items served from a snapshot, e.g. "⚠ Programmazione non aggiornata da 3 giorni.".
+fn stale_notice(since: DateTime<Utc>, now: DateTime<Utc>, locale: Locale) -> String {
+    let age = now - since;
+    let (n, unit_it, unit_en) = if age.num_days() >= 1 {
+        (age.num_days(), ("giorno", "giorni"), ("day", "days"))
+    } else {
+        (age.num_hours().max(1), ("ora", "ore"), ("hour", "hours"))
+    };
+    match locale {
+        Locale::Italian => {
+            let unit = if n == 1 { unit_it.0 } else { unit_it.1 };
+            format!("⚠ Programmazione non aggiornata da {n} {unit}.")
+        }
+        Locale::English => {
+            let unit = if n == 1 { unit_en.0 } else { unit_en.1 };
+            format!("⚠ Schedule not updated for {n} {unit}.")
+        }
+    }
+}
+
+/// One RSS category per genre of `film`, in the "genre" domain so readers can tell
+/// them from the cinema categories.
+fn genre_categories(film: &Film) -> impl Iterator<Item = Category> + '_ {
+    film.genres.iter().map(|genre| Category {
+        name: genre.clone(),
+        domain: Some("genre".to_string()),
+    })
 }
 
 /// Generate RSS feed from a list of films (single cinema).
@@ -77,10 +320,11 @@ pub fn generate_rss(
     channel_title: &str,
     channel_link: &str,
     channel_description: &str,
+    locale: Locale,
 ) -> Result<String, Box<dyn std::error::Error>> {
     let mut items = Vec::new();
     for film in films {
-        let (description, pub_date) = film_description_and_pub_date(film);
+        let (description, pub_date) = film_description_and_pub_date(film, locale);
         let guid = rss::Guid {
             value: film.url.clone(),
             permalink: true,
@@ -90,7 +334,8 @@ pub fn generate_rss(
             .title(film.title.clone())
             .link(film.url.clone())
             .description(description)
-            .guid(guid);
+            .guid(guid)
+            .categories(genre_categories(film).collect::<Vec<_>>());
         if let Some(date) = pub_date {
             item_builder.pub_date(date);
         }
@@ -100,6 +345,7 @@ pub fn generate_rss(
         .title(channel_title)
         .link(channel_link)
         .description(channel_description)
+        .language(locale.code().to_string())
         .items(items)
         .build();
     let mut buf = Vec::new();
@@ -112,6 +358,7 @@ pub fn generate_rss_merged(
     channel_title: &str,
     channel_link: &str,
     channel_description: &str,
+    locale: Locale,
     sources: &[(&str, &[Film])],
 ) -> Result<String, Box<dyn std::error::Error>> {
     let mut items = Vec::new();
@@ -121,7 +368,7 @@ pub fn generate_rss_merged(
             domain: None,
         };
         for film in *films {
-            let (description, pub_date) = film_description_and_pub_date(film);
+            let (description, pub_date) = film_description_and_pub_date(film, locale);
             let guid = rss::Guid {
                 value: film.url.clone(),
                 permalink: true,
@@ -136,7 +383,11 @@ pub fn generate_rss_merged(
                 .link(film.url.clone())
                 .description(description)
                 .guid(guid)
-                .categories(vec![category.clone()]);
+                .categories(
+                    std::iter::once(category.clone())
+                        .chain(genre_categories(film))
+                        .collect::<Vec<_>>(),
+                );
             if let Some(date) = pub_date {
                 item_builder.pub_date(date);
             }
@@ -147,9 +398,96 @@ pub fn generate_rss_merged(
         .title(channel_title)
         .link(channel_link)
         .description(channel_description)
+        .language(locale.code().to_string())
         .items(items)
         .build();
     let mut buf = Vec::new();
     channel.write_to(&mut buf)?;
     Ok(String::from_utf8(buf)?)
 }
+
+/// Generate a single RSS feed with one item per film: the same film at several cinemas
+/// (matched by [`dedup::same_film`]) becomes one item listing every cinema and its
+/// showtimes, with one category per cinema.
+pub fn generate_rss_by_film(
+    channel_title: &str,
+    channel_link: &str,
+    channel_description: &str,
+    locale: Locale,
+    sources: &[(&str, &[Film])],
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut items = Vec::new();
+    for group in group_films(sources, dedup::RUNTIME_TOLERANCE) {
+        let film = group.film();
+        let merged = group.merged();
+        let categories: Vec<Category> = group
+            .cinemas()
+            .into_iter()
+            .map(|cinema| Category {
+                name: cinema.to_string(),
+                domain: None,
+            })
+            .chain(genre_categories(&merged))
+            .collect();
+        let guid = rss::Guid {
+            value: film.url.clone(),
+            permalink: true,
+        };
+        let mut item_builder = ItemBuilder::default();
+        item_builder
+            .title(film.title.clone())
+            .link(film.url.clone())
+            .description(group_description(&group, locale))
+            .guid(guid)
+            .categories(categories);
+        if let Some(seen) = merged.first_seen {
+            item_builder.pub_date(seen.to_rfc2822());
+        }
+        items.push(item_builder.build());
+    }
+    let channel = ChannelBuilder::default()
+        .title(channel_title)
+        .link(channel_link)
+        .description(channel_description)
+        .language(locale.code().to_string())
+        .items(items)
+        .build();
+    let mut buf = Vec::new();
+    channel.write_to(&mut buf)?;
+    Ok(String::from_utf8(buf)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, NaiveTime, Timelike};
+
+    #[test]
+    fn merge_days_lists_each_screening_once() {
+        let day = NaiveDate::from_ymd_opt(2026, 10, 17).unwrap();
+        let at = |hour, hall: &str| {
+            let time = NaiveTime::from_hms_opt(hour, 0, 0).unwrap();
+            Showtime::at(day, time, chrono_tz::Europe::Rome)
+                .unwrap()
+                .with_hall(Some(hall.to_string()))
+        };
+        let film = |showtimes| Film {
+            title: "Stalker".to_string(),
+            url: "https://example.com/stalker".to_string(),
+            showtimes: Some(showtimes),
+            ..Default::default()
+        };
+        // Two listing days both give Sala 1 and Sala 2 at 21:00.
+        let films = merge_days(vec![
+            film(vec![at(21, "Sala 1"), at(21, "Sala 2")]),
+            film(vec![at(18, "Sala 1"), at(21, "Sala 1"), at(21, "Sala 2")]),
+        ]);
+        let halls: Vec<(u32, &str)> = films[0]
+            .showtimes
+            .iter()
+            .flatten()
+            .map(|st| (st.start.hour(), st.hall.as_deref().unwrap_or_default()))
+            .collect();
+        assert_eq!(halls, [(18, "Sala 1"), (21, "Sala 1"), (21, "Sala 2")]);
+    }
+}
@@ -3,54 +3,187 @@ mod cinema_edera;
 mod cinema_padova;
 mod cinema_trieste_scraper;
 mod cinemazero;
-mod cineplex_moderno;
-mod cinergia_conegliano;
+mod config;
+mod eighteen_tickets;
+mod email;
 mod enrico_pizzuti;
+#[cfg(test)]
+mod fixtures;
+mod history;
 mod multi_astra;
 mod new_bev;
 mod porto_astra;
 mod rassegne_cristallo;
 mod rassegne_edera;
+mod serve;
+mod snapshot;
 mod space_cinema;
 mod vista_theatre;
 
-use berlinale::BerlinaleScraper;
-use cinema_edera::CinemaEderaScraper;
-use cinema_padova::FeedPadovaScraper;
-use cinema_scrape::{CinemaScraper, Film, generate_rss, generate_rss_merged};
-use cinema_trieste_scraper::CinemaTriesteScraper;
-use cinemazero::CinemazeroScraper;
-use cineplex_moderno::CineplexModernoScraper;
-use cinergia_conegliano::CinergiaConeglianoScraper;
-use clap::{Parser, ValueEnum};
-use enrico_pizzuti::EnricoPizzutiScraper;
-use multi_astra::MultiAstraScraper;
-use new_bev::NewBevScraper;
-use porto_astra::PortoAstraScraper;
-use rassegne_cristallo::RassegneScraperCristallo;
-use rassegne_edera::RassegneScraperEdera;
-use space_cinema::SpaceCinemaScraper;
-use vista_theatre::VistaTheatreScraper;
+use chrono::{DateTime, Duration, Utc};
+use cinema_scrape::notify;
+use cinema_scrape::{
+    CinemaScraper, Digest, FeedFormat, FeedSpec, Film, FilmFilter, Format, Grouping, HttpClient,
+    Languages, Locale, Notice, Notifier, Period, ScrapeError, Showtime, SiteFeed, Watchlist, clock,
+    fnv1a, http, last_change, merge_days, render_digest, render_site, showtime, split_genres,
+};
+use clap::{Parser, Subcommand};
+use config::{CinemaConfig, Config, FeedConfig};
+use futures::future::join_all;
+use history::History;
+use snapshot::{Changes, SnapshotStore};
 use std::fs;
-
-/// Which single feed to generate. If omitted, all feeds are generated.
-#[derive(Clone, PartialEq, ValueEnum)]
-#[value(rename_all = "lowercase")]
-enum Feed {
-    Multisala,
-    Padova,
-    Trieste,
-    Rassegne,
-    Berlinale,
-    Tarantino,
-    VistaTheatre,
-}
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Feed of the films that appeared since the previous run: `docs/feeds/novita.xml`.
+const NOVITA_FEED: &str = "novita";
+
+/// Feed of the films matching the `[[watch]]` entries: `docs/feeds/watchlist.xml`.
+const WATCHLIST_FEED: &str = "watchlist";
+/// Feeds of some screenings at every cinema, written on full runs:
+/// (name, title, description, which screenings).
+type ScreeningFeed = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&Showtime) -> bool,
+);
+const SCREENING_FEEDS: [ScreeningFeed; 2] = [
+    (
+        "vo",
+        "Versione originale",
+        "Proiezioni in versione originale, in tutti i cinema.",
+        |st| st.languages.original_version,
+    ),
+    (
+        "pellicola",
+        "In pellicola",
+        "Proiezioni in pellicola (16mm, 35mm, 70mm), in tutti i cinema.",
+        |st| st.formats.iter().any(|f| f.is_analog()),
+    ),
+];
 
 #[derive(Parser)]
 struct Args {
-    /// Generate only this feed (default: all feeds)
+    /// Feed/cinema registry (TOML)
+    #[arg(long, default_value = "cinemas.toml")]
+    config: PathBuf,
+    /// Generate only this feed, by name (default: all feeds)
+    #[arg(long)]
+    feed: Option<String>,
+    /// Maximum number of HTTP requests in flight across all sites
+    #[arg(long, default_value_t = http::DEFAULT_MAX_CONCURRENCY)]
+    max_concurrency: usize,
+    /// Maximum number of HTTP requests in flight against a single host
+    #[arg(long, default_value_t = http::DEFAULT_PER_HOST)]
+    per_host: usize,
+    /// Directory holding the last good result of every cinema
+    #[arg(long, default_value = "state")]
+    state_dir: PathBuf,
+    /// Serve the last good snapshot when a scrape returns fewer than this
+    /// fraction of its film count
+    #[arg(long, default_value_t = 0.5)]
+    min_ratio: f64,
+    /// Never serve snapshots older than this many days
+    #[arg(long, default_value_t = 7)]
+    max_stale_days: i64,
+    /// Save every HTTP response under this directory, for a later --replay
+    #[arg(long, conflicts_with = "replay")]
+    record: Option<PathBuf>,
+    /// Serve the HTTP responses saved by --record instead of using the network
+    #[arg(long)]
+    replay: Option<PathBuf>,
+    /// List the venues of The Space Cinema (id, name, city) and exit
     #[arg(long)]
-    feed: Option<Feed>,
+    space_venues: bool,
+    /// Also publish the Space Cinema venues whose name or city contains this text,
+    /// each as its own feed "space-<venue>" (repeatable)
+    #[arg(long = "space", value_name = "NAME_OR_CITY")]
+    space: Vec<String>,
+    /// Publish the venues picked with --space as one feed with this name instead
+    #[arg(long, requires = "space", value_name = "FEED")]
+    space_feed: Option<String>,
+    /// After the run, email the programme of today (daily) or of the next seven days
+    /// (weekly) as configured in the [email] table
+    #[arg(long, value_name = "daily|weekly")]
+    digest: Option<Period>,
+    /// SQLite archive of every run [default: <state dir>/history.sqlite]
+    #[arg(long, global = true)]
+    history_db: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Query the archive of past runs instead of scraping
+    History {
+        #[command(subcommand)]
+        query: HistoryQuery,
+    },
+    /// Serve the feeds and the programme site over HTTP, scraping on a schedule or
+    /// when a request finds them older than --ttl
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: SocketAddr,
+        /// Scrape again when a request finds content older than this many minutes
+        #[arg(long, default_value_t = 60)]
+        ttl: u64,
+        /// Scrape every this many minutes in the background instead, from launch
+        #[arg(long, value_name = "MINUTES")]
+        every: Option<u64>,
+    },
+}
+
+#[derive(Subcommand)]
+enum HistoryQuery {
+    /// Which cinemas showed a film, from when to when, and how many screenings
+    Film {
+        /// Title or part of it; case and accents are ignored
+        title: String,
+        /// Only runs in this year
+        #[arg(long)]
+        year: Option<i32>,
+        /// Print CSV instead of a table
+        #[arg(long)]
+        csv: bool,
+    },
+    /// Every screening ever seen, as CSV
+    Export {
+        /// Write to this file instead of standard output
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Answer a `history` subcommand from the archive.
+fn query_history(db: &Path, query: &HistoryQuery) -> Result<(), Box<dyn std::error::Error>> {
+    if !db.exists() {
+        return Err(format!("no history at {} (run a scrape first)", db.display()).into());
+    }
+    let history = History::open(db)?;
+    match query {
+        HistoryQuery::Film { title, year, csv } => {
+            let stays = history.film(title, *year)?;
+            if stays.is_empty() && !csv {
+                let last = history.last_run()?.unwrap_or_else(|| "never".to_string());
+                println!("No film matching '{title}' (archive last updated {last})");
+            }
+            history::print_stays(&stays, *csv)?;
+        }
+        HistoryQuery::Export { output: Some(path) } => {
+            let mut file = std::io::BufWriter::new(fs::File::create(path)?);
+            history.export_csv(&mut file)?;
+        }
+        HistoryQuery::Export { output: None } => {
+            history.export_csv(&mut std::io::stdout().lock())?;
+        }
+    }
+    Ok(())
 }
 
 fn print_films(films: &[Film]) {
@@ -60,9 +193,27 @@ fn print_films(films: &[Film]) {
         if let Some(ref poster) = film.poster_url {
             println!("POSTER      : {}", poster);
         }
+        if let Some(ref director) = film.director {
+            println!("DIRECTOR    : {}", director);
+        }
         if let Some(ref cast) = film.cast {
             println!("CAST        : {}", cast);
         }
+        if let Some(ref title) = film.original_title {
+            println!("ORIG. TITLE : {}", title);
+        }
+        if !film.genres.is_empty() {
+            println!("GENRES      : {}", film.genres.join(", "));
+        }
+        if let Some(ref country) = film.country {
+            println!("COUNTRY     : {}", country);
+        }
+        if let Some(year) = film.year {
+            println!("YEAR        : {}", year);
+        }
+        if let Some(ref language) = film.language {
+            println!("LANGUAGE    : {}", language);
+        }
         if let Some(ref date) = film.release_date {
             println!("RELEASE DATE: {}", date);
         }
@@ -81,279 +232,760 @@ fn print_films(films: &[Film]) {
     }
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    fs::create_dir_all("docs/feeds")?;
-    let client = reqwest::Client::builder().cookie_store(true).build()?;
-    let feed_filter = Args::parse().feed;
-
-    const SPACE_NAME: &str = "The Space Cinema - Silea";
-    const EDERA_NAME: &str = "Cinema Multisala Edera";
-    const MANZONI_NAME: &str = "Cinema Multisala Manzoni";
-    const CINERGIA_NAME: &str = "Cinergia Conegliano";
-    const PIZZUTI_NAME: &str = "Circolo Enrico Pizzuti";
-    const CINEMAZERO_NAME: &str = "Cinemazero Pordenone";
-
-    // --- multisala ---
-    if feed_filter.is_none() || feed_filter.as_ref() == Some(&Feed::Multisala) {
-        let showing_date = std::env::var("SHOWING_DATE")
-            .unwrap_or_else(|_| chrono::Local::now().format("%Y-%m-%dT00:00:00").to_string());
-        let space_scraper = SpaceCinemaScraper::new(1009, showing_date);
-        let edera_scraper = CinemaEderaScraper::new(
-            "https://www.cinemaedera.it/i-film-della-settimana.html".to_string(),
-        );
-        let manzoni_scraper = CinemaEderaScraper::new(
-            "https://www.cinemamanzoni.it/i-film-della-settimana.html".to_string(),
-        );
-        let cinergia_scraper =
-            CinergiaConeglianoScraper::new("https://coneglianocinergia.18tickets.it/".to_string());
-        let cinemazero_scraper =
-            CinemazeroScraper::new("https://cinemazero.it/programmazione/".to_string());
-
-        println!("=== Fetching from The Space Cinema ===\n");
-        space_scraper.warm_up(&client).await?;
-        let space_films = space_scraper.fetch_films(&client).await.unwrap_or_default();
-        print_films(&space_films);
-
-        println!("\n=== Fetching from Cinema Edera ===\n");
-        let edera_films = edera_scraper.fetch_films(&client).await.unwrap_or_default();
-        print_films(&edera_films);
-
-        println!("\n=== Fetching from Cinema Manzoni ===\n");
-        let manzoni_films = manzoni_scraper
-            .fetch_films(&client)
-            .await
-            .unwrap_or_default();
-        print_films(&manzoni_films);
+/// Warm up and scrape one cinema. An empty result is an error unless the
+/// cinema is configured with `allow_empty = true`.
+async fn fetch_cinema(
+    cinema: &CinemaConfig,
+    client: &HttpClient,
+) -> Result<Vec<Film>, ScrapeError> {
+    let scraper = cinema.scraper.build();
+    if let Err(e) = scraper.warm_up(client).await {
+        eprintln!("Warm-up failed for {}: {e}", cinema.name);
+    }
+    let mut films = scraper.fetch_films(client).await?;
+    if films.is_empty() && !cinema.allow_empty {
+        return Err(ScrapeError::Empty);
+    }
+    films.iter_mut().for_each(Film::tag_showtimes);
+    Ok(films)
+}
 
-        println!("\n=== Fetching from Cinergia Conegliano ===\n");
-        let cinergia_films = cinergia_scraper
-            .fetch_films(&client)
-            .await
-            .unwrap_or_default();
-        print_films(&cinergia_films);
+/// One line of the end-of-run report.
+struct Outcome<'a> {
+    feed: &'a str,
+    cinema: &'a CinemaConfig,
+    result: Result<usize, ScrapeError>,
+    /// On failure: films served from the snapshot and when it was taken.
+    fallback: Option<(usize, DateTime<Utc>)>,
+    /// On success: what changed since the previous good run.
+    changes: Changes,
+}
 
-        println!("\n=== Fetching from Cinemazero Pordenone ===\n");
-        let cinemazero_films = cinemazero_scraper
-            .fetch_films(&client)
-            .await
-            .unwrap_or_default();
-        print_films(&cinemazero_films);
-
-        let rss_xml = generate_rss_merged(
-            "Film in programmazione",
-            "https://github.com/",
-            "RSS unificato: The Space Cinema (Silea), Cinema Multisala Edera, Cinema Manzoni, Cinergia Conegliano, Cinemazero Pordenone.",
-            &[
-                (SPACE_NAME, space_films.as_slice()),
-                (EDERA_NAME, edera_films.as_slice()),
-                (MANZONI_NAME, manzoni_films.as_slice()),
-                (CINERGIA_NAME, cinergia_films.as_slice()),
-                (CINEMAZERO_NAME, cinemazero_films.as_slice()),
-            ],
-        )?;
-        let feed_path = "docs/feeds/multisala.xml";
-        fs::write(feed_path, rss_xml)?;
-        println!("✓ Merged RSS feed saved to: {}", feed_path);
+impl Outcome<'_> {
+    fn health(&self) -> serve::Health {
+        serve::Health {
+            feed: self.feed.to_string(),
+            cinema: self.cinema.name.clone(),
+            required: self.cinema.required,
+            ok: self.result.is_ok(),
+            films: self.result.as_ref().ok().copied(),
+            error: self.result.as_ref().err().map(|e| e.to_string()),
+            snapshot_from: self.fallback.map(|(_, since)| since),
+        }
     }
+}
 
-    // --- padova ---
-    if feed_filter.is_none() || feed_filter.as_ref() == Some(&Feed::Padova) {
-        let padova_scraper =
-            FeedPadovaScraper::new("https://www.cinemarex.it/programmazione".to_string());
-        let porto_astra_scraper =
-            PortoAstraScraper::new("https://portoastra.it/questa-settimana/".to_string());
-        let cineplex_moderno_scraper =
-            CineplexModernoScraper::new("https://pv.cineplexmoderno.18tickets.it".to_string());
-        let multi_astra_scraper =
-            MultiAstraScraper::new("https://multiastra.it/film-della-settimana/".to_string());
-
-        println!("\n=== Fetching from Cinema Rex Padova ===\n");
-        let padova_films = match padova_scraper.fetch_films(&client).await {
-            Ok(films) => films,
+fn print_summary(outcomes: &[Outcome]) {
+    println!("\n=== Summary ===\n");
+    for o in outcomes {
+        let required = if o.cinema.required { " (required)" } else { "" };
+        match &o.result {
+            Ok(n) => {
+                let mut changes = Vec::new();
+                if !o.changes.added.is_empty() {
+                    changes.push(format!("new: {}", o.changes.added.join(", ")));
+                }
+                if !o.changes.removed.is_empty() {
+                    changes.push(format!("gone: {}", o.changes.removed.join(", ")));
+                }
+                if o.changes.new_showtimes > 0 {
+                    changes.push(format!("{} new showtimes", o.changes.new_showtimes));
+                }
+                let changes = if changes.is_empty() {
+                    String::new()
+                } else {
+                    format!(" ({})", changes.join("; "))
+                };
+                println!("OK    {:<14} {}: {n} films{changes}", o.feed, o.cinema.name)
+            }
             Err(e) => {
-                eprintln!("Error fetching Cinema Rex Padova films: {e}");
-                Vec::new()
+                let fallback = match o.fallback {
+                    Some((n, since)) => {
+                        format!(
+                            " (serving {n} films from {})",
+                            since.format("%Y-%m-%d %H:%M UTC")
+                        )
+                    }
+                    None => String::new(),
+                };
+                println!(
+                    "FAIL  {:<14} {}{required}: {e}{fallback}",
+                    o.feed, o.cinema.name
+                )
             }
-        };
-        print_films(&padova_films);
+        }
+    }
+}
 
-        println!("\n=== Fetching from Cinema Porto Astra Padova ===\n");
-        let porto_astra_films = porto_astra_scraper
-            .fetch_films(&client)
-            .await
-            .unwrap_or_default();
-        print_films(&porto_astra_films);
+/// Films published by one feed, per cinema (fresh or from a snapshot).
+type FeedResults<'a> = Vec<(&'a str, Vec<Film>)>;
 
-        println!("\n=== Fetching from Cineplex Moderno Due Carrare Padova ===\n");
-        let cineplex_moderno_films = cineplex_moderno_scraper
-            .fetch_films(&client)
-            .await
-            .unwrap_or_default();
-        print_films(&cineplex_moderno_films);
+fn sources<'a>(results: &'a FeedResults) -> Vec<(&'a str, &'a [Film])> {
+    results
+        .iter()
+        .map(|(name, films)| (*name, films.as_slice()))
+        .collect()
+}
 
-        println!("\n=== Fetching from Multi Astra Padova ===\n");
-        let multi_astra_films = multi_astra_scraper
-            .fetch_films(&client)
-            .await
-            .unwrap_or_default();
-        print_films(&multi_astra_films);
-
-        let padova_rss_xml = generate_rss_merged(
-            "Film in programmazione a Padova",
-            "https://portoastra.it/questa-settimana/",
-            "Programmazione Cinema Rex Padova, Cinema Porto Astra, Cineplex Moderno Due Carrare e Multi Astra.",
-            &[
-                ("Cinema Rex Padova", padova_films.as_slice()),
-                ("Cinema Porto Astra", porto_astra_films.as_slice()),
-                (
-                    "Cineplex Moderno Due Carrare",
-                    cineplex_moderno_films.as_slice(),
-                ),
-                ("Multi Astra Padova", multi_astra_films.as_slice()),
-            ],
-        )?;
-        let padova_feed_path = padova_scraper.rss_filename();
-        fs::write(&padova_feed_path, padova_rss_xml)?;
-        println!("✓ Padova RSS feed saved to: {}", padova_feed_path);
+/// The films of every cinema of `results`, each cinema once even when several feeds
+/// list it (its first listing is kept).
+fn unique_cinemas<'a>(
+    results: impl IntoIterator<Item = &'a FeedResults<'a>>,
+) -> Vec<(&'a str, &'a [Film])> {
+    let mut cinemas: Vec<(&str, &[Film])> = Vec::new();
+    for (cinema, films) in results.into_iter().flatten() {
+        if !cinemas.iter().any(|(name, _)| name == cinema) {
+            cinemas.push((cinema, films));
+        }
     }
+    cinemas
+}
 
-    // --- trieste ---
-    if feed_filter.is_none() || feed_filter.as_ref() == Some(&Feed::Trieste) {
-        let trieste_scraper = CinemaTriesteScraper::new();
+/// Channel of a configured feed, under `title`, publishing `sources`.
+fn feed_spec<'a>(
+    feed: &'a FeedConfig,
+    title: &'a str,
+    sources: &'a [(&'a str, &'a [Film])],
+) -> FeedSpec<'a> {
+    FeedSpec {
+        title,
+        link: &feed.link,
+        description: &feed.description,
+        locale: feed.language,
+        sources,
+        grouping: feed.group_by,
+    }
+}
 
-        println!("\n=== Fetching from Cinema Ariston Trieste (La Cappella Underground) ===\n");
-        let trieste_films = trieste_scraper
-            .fetch_films(&client)
-            .await
-            .unwrap_or_default();
-        print_films(&trieste_films);
-
-        let trieste_rss_xml = generate_rss(
-            &trieste_films,
-            "Cinema Ariston Trieste - La Cappella Underground",
-            "https://www.lacappellaunderground.org/ariston/programma/",
-            "Programmazione Cinema Ariston - La Cappella Underground",
-        )?;
-        let trieste_feed_path = trieste_scraper.rss_filename();
-        fs::write(&trieste_feed_path, trieste_rss_xml)?;
-        println!("✓ Trieste RSS feed saved to: {}", trieste_feed_path);
+/// Render one feed in every format, as `feeds/<name>.<ext>`.
+fn render_feed(
+    name: &str,
+    spec: &FeedSpec,
+    files: &mut Vec<(String, String)>,
+) -> Result<(), String> {
+    for format in FeedFormat::ALL {
+        let contents = format
+            .render(spec)
+            .map_err(|e| format!("cannot render feed {name} as {format:?}: {e}"))?;
+        files.push((format!("feeds/{name}.{}", format.extension()), contents));
     }
+    Ok(())
+}
 
-    // --- rassegne ---
-    if feed_filter.is_none() || feed_filter.as_ref() == Some(&Feed::Rassegne) {
-        let rassegne_scraper = RassegneScraperCristallo::new(
-            "https://www.cinemacristallo.com/rassegna-film-dautore/".to_string(),
-        );
-        let edera_rassegne_scraper =
-            RassegneScraperEdera::new("https://www.cinemaedera.it/rassegne.html".to_string());
-        let pizzuti_scraper =
-            EnricoPizzutiScraper::new("https://www.enricopizzuti.it/".to_string());
-
-        println!("\n=== Fetching from Cinema Cristallo Oderzo - Rassegna Film d'Autore ===\n");
-        let rassegne_films = rassegne_scraper
-            .fetch_films(&client)
-            .await
-            .unwrap_or_default();
-        print_films(&rassegne_films);
+/// Render the static programme site (index, one page per feed, one per film).
+fn render_programme(published: &[(&FeedConfig, FeedResults)]) -> Vec<(String, String)> {
+    let sources: Vec<Vec<(&str, &[Film])>> = published
+        .iter()
+        .map(|(_, results)| sources(results))
+        .collect();
+    let feeds: Vec<SiteFeed> = published
+        .iter()
+        .zip(&sources)
+        .map(|((feed, _), sources)| SiteFeed {
+            name: &feed.name,
+            spec: FeedSpec {
+                title: &feed.title,
+                link: &feed.link,
+                description: &feed.description,
+                locale: feed.language,
+                sources,
+                grouping: feed.group_by,
+            },
+        })
+        .collect();
+    let updated = last_change(sources.iter().flatten().flat_map(|(_, films)| *films));
+    render_site(&feeds, updated)
+}
 
-        println!("\n=== Fetching from Cinema Edera - Rassegne ===\n");
-        let edera_rassegne_films = edera_rassegne_scraper
-            .fetch_films(&client)
-            .await
-            .unwrap_or_default();
-        print_films(&edera_rassegne_films);
+/// Write a run's files under docs/. A full run rebuilds the site, and film pages of
+/// films no longer showing must not linger.
+fn write_files(files: &[(String, String)], full: bool) -> std::io::Result<()> {
+    if full {
+        let _ = fs::remove_dir_all("docs/films");
+    }
+    for (path, contents) in files {
+        let path = Path::new("docs").join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, contents)?;
+        if path.starts_with("docs/feeds") {
+            println!("✓ Feed saved to: {}", path.display());
+        }
+    }
+    if full {
+        println!("✓ Programme site saved to: docs/index.html");
+    }
+    Ok(())
+}
 
-        println!("\n=== Fetching from Circolo Enrico Pizzuti ===\n");
-        let pizzuti_films = pizzuti_scraper
-            .fetch_films(&client)
-            .await
-            .unwrap_or_default();
-        print_films(&pizzuti_films);
-
-        let rassegne_rss_xml = generate_rss_merged(
-            "Rassegne",
-            "https://github.com/",
-            "Rassegne di Cinema Cristallo Oderzo, Cinema Edera e Circolo Enrico Pizzuti.",
-            &[
-                ("Cinema Cristallo Oderzo", rassegne_films.as_slice()),
-                ("Cinema Edera", edera_rassegne_films.as_slice()),
-                (PIZZUTI_NAME, pizzuti_films.as_slice()),
-            ],
-        )?;
-        let rassegne_feed_path = rassegne_scraper.rss_filename();
-        fs::write(&rassegne_feed_path, rassegne_rss_xml)?;
-        println!("✓ Rassegne RSS feed saved to: {}", rassegne_feed_path);
+/// Everything a run needs, set up once from the command line and the config.
+struct Pipeline {
+    args: Args,
+    config: Config,
+    filter: Option<FilmFilter>,
+    watchlist: Option<Watchlist>,
+    /// Each notifier with the events it wants.
+    notifiers: Vec<(Vec<notify::Event>, Box<dyn Notifier>)>,
+    client: HttpClient,
+    history_db: PathBuf,
+}
+
+/// What one run publishes under docs/, and how every cinema fared.
+struct Run<'a> {
+    files: Vec<(String, String)>,
+    outcomes: Vec<Outcome<'a>>,
+    /// With `--digest`: the email to send, unless nothing is showing.
+    digest: Option<Digest>,
+    /// Films that appeared since the previous run, for the notifiers.
+    notices: Vec<Notice>,
+}
+
+impl Pipeline {
+    /// Whether every feed is generated; the site links every feed, and the novità
+    /// feed covers every cinema, so both are only rebuilt on full runs.
+    fn full(&self) -> bool {
+        self.args.feed.is_none()
     }
 
-    // --- berlinale ---
-    if feed_filter.is_none() || feed_filter.as_ref() == Some(&Feed::Berlinale) {
-        let berlinale_scraper = BerlinaleScraper::new(
-            "https://www.berlinale.de/en/programme/on-sale-from-today.html".to_string(),
-        );
+    /// Scrape every selected feed and render what it publishes. Snapshots and the
+    /// history archive are updated on the way, except when replaying: a replay neither
+    /// reads nor changes the live state, so it gives the same output every time.
+    async fn run(&self) -> Result<Run<'_>, String> {
+        let args = &self.args;
+        let feeds: Vec<&FeedConfig> = self
+            .config
+            .feeds
+            .iter()
+            .filter(|f| args.feed.as_ref().is_none_or(|name| name == &f.name))
+            .collect();
+
+        // Every cinema of every selected feed is scraped concurrently; the client's
+        // limits keep any single site from receiving more than a few requests at once.
+        let started = Instant::now();
+        let scraped = join_all(feeds.iter().map(|feed| {
+            join_all(
+                feed.cinemas
+                    .iter()
+                    .map(|cinema| fetch_cinema(cinema, &self.client)),
+            )
+        }))
+        .await;
+        println!("Scraped in {:.1}s", started.elapsed().as_secs_f64());
+
+        let replay = args.replay.is_some();
+        let snapshots = SnapshotStore::new(&args.state_dir);
+        let max_stale = Duration::days(args.max_stale_days);
+        let mut files = Vec::new();
+        let mut outcomes = Vec::new();
+        let mut published: Vec<(&FeedConfig, FeedResults)> = Vec::new();
+        let run_at = clock::now();
+        for (feed, films_per_cinema) in feeds.into_iter().zip(scraped) {
+            let mut results: FeedResults = Vec::new();
+            for (cinema, result) in feed.cinemas.iter().zip(films_per_cinema) {
+                println!("\n=== Fetching from {} ===\n", cinema.name);
+                let previous = if replay {
+                    None
+                } else {
+                    snapshots.load(&feed.name, &cinema.name)
+                };
+                let result = result.and_then(|films| match previous {
+                    Some(ref prev)
+                        if !(films.is_empty() && cinema.allow_empty)
+                            && snapshot::too_few(films.len(), prev.films.len(), args.min_ratio) =>
+                    {
+                        Err(ScrapeError::TooFew {
+                            found: films.len(),
+                            previous: prev.films.len(),
+                        })
+                    }
+                    _ => Ok(films),
+                });
+                let films = match result {
+                    Ok(mut films) => {
+                        let changes = snapshot::track(previous.as_ref(), &mut films, run_at);
+                        print_films(&films);
+                        if !replay && let Err(e) = snapshots.save(&feed.name, &cinema.name, &films)
+                        {
+                            eprintln!("Could not save snapshot for {}: {e}", cinema.name);
+                        }
+                        outcomes.push(Outcome {
+                            feed: &feed.name,
+                            cinema,
+                            result: Ok(films.len()),
+                            fallback: None,
+                            changes,
+                        });
+                        films
+                    }
+                    Err(e) => {
+                        eprintln!("Error fetching {} films: {e}", cinema.name);
+                        let previous =
+                            previous.filter(|p| clock::now() - p.fetched_at <= max_stale);
+                        let fallback = previous.as_ref().map(|p| (p.films.len(), p.fetched_at));
+                        outcomes.push(Outcome {
+                            feed: &feed.name,
+                            cinema,
+                            result: Err(e),
+                            fallback,
+                            changes: Changes::default(),
+                        });
+                        previous.map(|p| p.into_stale_films()).unwrap_or_default()
+                    }
+                };
+                results.push((cinema.name.as_str(), films));
+            }
 
-        println!("\n=== Fetching from Berlinale ===\n");
-        let berlinale_films = berlinale_scraper
-            .fetch_films(&client)
-            .await
-            .unwrap_or_default();
-        print_films(&berlinale_films);
-
-        let berlinale_rss_xml = generate_rss(
-            &berlinale_films,
-            "Berlinale - Berlin International Film Festival",
-            "https://www.berlinale.de/en/programme/on-sale-from-today.html",
-            "Films in the Berlinale programme (on sale / in programme).",
-        )?;
-        let berlinale_feed_path = berlinale_scraper.rss_filename();
-        fs::write(&berlinale_feed_path, berlinale_rss_xml)?;
-        println!("✓ Berlinale RSS feed saved to: {}", berlinale_feed_path);
+            render_feed(
+                &feed.name,
+                &feed_spec(feed, &feed.title, &sources(&results)),
+                &mut files,
+            )?;
+            if let Some(ref name) = feed.by_film {
+                let sources = sources(&results);
+                let spec = FeedSpec {
+                    grouping: Grouping::Film,
+                    ..feed_spec(feed, &feed.title, &sources)
+                };
+                render_feed(name, &spec, &mut files)?;
+            }
+
+            // Art-house variant: the same feed without the films the filter catches.
+            if let Some(ref filter) = self.filter {
+                let name = format!("{}-arthouse", feed.name);
+                let mut kept_results: FeedResults = Vec::new();
+                for (cinema, films) in &results {
+                    let (kept, dropped) = filter.split(films);
+                    for (film, reason) in dropped {
+                        println!("Filtered from {name}: {cinema} - {} ({reason})", film.title);
+                    }
+                    kept_results.push((cinema, kept));
+                }
+                let title = format!("{} (art-house)", feed.title);
+                render_feed(
+                    &name,
+                    &feed_spec(feed, &title, &sources(&kept_results)),
+                    &mut files,
+                )?;
+            }
+            published.push((feed, results));
+        }
+
+        let archived: Vec<(&str, &[Film])> = published
+            .iter()
+            .flat_map(|(_, results)| sources(results))
+            .collect();
+        if !replay
+            && let Err(e) =
+                History::open(&self.history_db).and_then(|mut h| h.record(run_at, &archived))
+        {
+            eprintln!(
+                "Could not archive this run in {}: {e}",
+                self.history_db.display()
+            );
+        }
+
+        let cinemas = unique_cinemas(published.iter().map(|(_, results)| results));
+        // Films that appeared since the previous run, per cinema, for the novità feed
+        // and the notifiers.
+        let mut novita: FeedResults = cinemas
+            .iter()
+            .map(|(cinema, films)| {
+                let changes = outcomes
+                    .iter()
+                    .find(|o| o.cinema.name == *cinema)
+                    .map(|o| &o.changes);
+                let new: Vec<Film> = films
+                    .iter()
+                    .filter(|f| changes.is_some_and(|c| c.added.contains(&f.title)))
+                    .cloned()
+                    .collect();
+                (*cinema, new)
+            })
+            .collect();
+
+        if self.full() {
+            files.extend(render_programme(&published));
+            novita.retain(|(_, films)| !films.is_empty());
+            let sources = sources(&novita);
+            render_feed(
+                NOVITA_FEED,
+                &FeedSpec {
+                    title: "Novità in programmazione",
+                    link: published.first().map_or("", |(feed, _)| feed.link.as_str()),
+                    description: "Film entrati in programmazione dall'ultimo aggiornamento, in tutti i cinema.",
+                    locale: Locale::Italian,
+                    sources: &sources,
+                    grouping: Grouping::Film,
+                },
+                &mut files,
+            )?;
+        }
+
+        if self.full() {
+            for (name, title, description, keep) in SCREENING_FEEDS {
+                let mut kept: FeedResults = cinemas
+                    .iter()
+                    .map(|(cinema, films)| {
+                        let films = films.iter().filter_map(|f| f.only_screenings(keep));
+                        (*cinema, films.collect())
+                    })
+                    .collect();
+                kept.retain(|(_, films)| !films.is_empty());
+                let sources = sources(&kept);
+                render_feed(
+                    name,
+                    &FeedSpec {
+                        title,
+                        link: published.first().map_or("", |(feed, _)| feed.link.as_str()),
+                        description,
+                        locale: Locale::Italian,
+                        sources: &sources,
+                        grouping: Grouping::Film,
+                    },
+                    &mut files,
+                )?;
+            }
+        }
+
+        if self.full()
+            && let Some(ref watchlist) = self.watchlist
+        {
+            let mut watched: FeedResults = Vec::new();
+            for &(cinema, films) in &cinemas {
+                let mut hits = Vec::new();
+                for film in films {
+                    if let Some(rule) = watchlist.check(cinema, film) {
+                        println!("Watchlist: {cinema} - {} ({rule})", film.title);
+                        hits.push(film.clone());
+                    }
+                }
+                watched.push((cinema, hits));
+            }
+            watched.retain(|(_, films)| !films.is_empty());
+            let sources = sources(&watched);
+            render_feed(
+                WATCHLIST_FEED,
+                &FeedSpec {
+                    title: "Watchlist",
+                    link: published.first().map_or("", |(feed, _)| feed.link.as_str()),
+                    description: "Film della watchlist in programmazione, in tutti i cinema.",
+                    locale: Locale::Italian,
+                    sources: &sources,
+                    grouping: Grouping::Film,
+                },
+                &mut files,
+            )?;
+        }
+        let digest = match (args.digest, &self.config.email) {
+            (Some(period), Some(email)) => {
+                let cinemas = unique_cinemas(
+                    published
+                        .iter()
+                        .filter(|(feed, _)| {
+                            email.feeds.is_empty() || email.feeds.contains(&feed.name)
+                        })
+                        .map(|(_, results)| results),
+                );
+                render_digest(&cinemas, period, showtime::today(), email.language)
+            }
+            _ => None,
+        };
+        let notices = novita
+            .iter()
+            .flat_map(|(cinema, films)| films.iter().map(move |film| (*cinema, film)))
+            .map(|(cinema, film)| Notice {
+                cinema: cinema.to_string(),
+                film: film.clone(),
+                watch: self
+                    .watchlist
+                    .as_ref()
+                    .and_then(|w| w.check(cinema, film))
+                    .map(|rule| rule.to_string()),
+            })
+            .collect();
+        Ok(Run {
+            files,
+            outcomes,
+            digest,
+            notices,
+        })
     }
 
-    // --- tarantino ---
-    if feed_filter.is_none() || feed_filter.as_ref() == Some(&Feed::Tarantino) {
-        let new_bev_scraper = NewBevScraper::new();
+    /// Push the run's new films to every notifier that wants them. Failures are
+    /// reported but do not fail the run; replays never notify.
+    async fn notify(&self, notices: &[Notice]) {
+        if self.args.replay.is_some() {
+            return;
+        }
+        let client = reqwest::Client::new();
+        for (events, notifier) in &self.notifiers {
+            let wanted: Vec<Notice> = notices
+                .iter()
+                .filter(|n| events.iter().any(|e| e.wants(n)))
+                .cloned()
+                .collect();
+            if wanted.is_empty() {
+                continue;
+            }
+            match notifier.notify(&client, &wanted).await {
+                Ok(()) => println!("✓ {} new films sent to {}", wanted.len(), notifier.name()),
+                Err(e) => eprintln!("Could not notify {}: {e}", notifier.name()),
+            }
+        }
+    }
 
-        println!("\n=== Fetching from The New Beverly Cinema ===\n");
-        let new_bev_films = new_bev_scraper
-            .fetch_films(&client)
-            .await
-            .unwrap_or_default();
-        print_films(&new_bev_films);
-
-        let new_bev_rss_xml = generate_rss(
-            &new_bev_films,
-            "The New Beverly Cinema",
-            "https://thenewbev.com/schedule/",
-            "Schedule and program for The New Beverly Cinema (Quentin Tarantino's revival theater in Los Angeles).",
-        )?;
-        let new_bev_feed_path = new_bev_scraper.rss_filename();
-        fs::write(&new_bev_feed_path, new_bev_rss_xml)?;
-        println!("✓ New Beverly RSS feed saved to: {}", new_bev_feed_path);
+    /// One refresh of `serve` mode: a run, kept in memory instead of written to docs/.
+    async fn publish(&self) -> Result<serve::Published, String> {
+        let run = self.run().await?;
+        self.notify(&run.notices).await;
+        print_summary(&run.outcomes);
+        Ok(serve::Published {
+            health: run.outcomes.iter().map(Outcome::health).collect(),
+            files: run.files,
+        })
     }
+}
 
-    // --- vista theatre ---
-    if feed_filter.is_none() || feed_filter.as_ref() == Some(&Feed::VistaTheatre) {
-        let vista_scraper =
-            VistaTheatreScraper::new("https://www.vistatheaterhollywood.com/".to_string());
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    let history_db = args
+        .history_db
+        .clone()
+        .unwrap_or_else(|| args.state_dir.join("history.sqlite"));
+    if let Some(Command::History { ref query }) = args.command {
+        return query_history(&history_db, query);
+    }
+    let mut config = Config::load(&args.config)?;
+    let filter = match config.filter {
+        Some(ref rules) => {
+            Some(FilmFilter::new(rules).map_err(|e| format!("invalid [filter] pattern: {e}"))?)
+        }
+        None => None,
+    };
+    let watchlist = if config.watchlist.is_empty() {
+        None
+    } else {
+        Some(Watchlist::new(&config.watchlist)?)
+    };
+
+    let mut client = HttpClient::new(
+        reqwest::Client::builder().cookie_store(true).build()?,
+        args.max_concurrency,
+        args.per_host,
+    );
+    // A recorded run and its replays share one "now", so they produce the same output.
+    if let Some(ref dir) = args.record {
+        let started = Utc::now();
+        clock::pin(started);
+        client = client.recording(dir.clone(), started)?;
+        println!("Recording HTTP responses to {}", dir.display());
+    } else if let Some(ref dir) = args.replay {
+        let (replaying, started) = client
+            .replaying(dir.clone())
+            .map_err(|e| format!("cannot replay {}: {e}", dir.display()))?;
+        clock::pin(started);
+        client = replaying;
+        println!(
+            "Replaying HTTP responses from {} (recorded {})",
+            dir.display(),
+            started.format("%Y-%m-%d %H:%M UTC")
+        );
+    }
 
-        println!("\n=== Fetching from Vista Theater Hollywood ===\n");
-        let vista_films = vista_scraper
-            .fetch_films(&client)
+    if args.space_venues || !args.space.is_empty() {
+        let venues = space_cinema::fetch_venues(&client)
             .await
-            .unwrap_or_default();
-        print_films(&vista_films);
-
-        let vista_rss_xml = generate_rss(
-            &vista_films,
-            "Vista Theater Hollywood",
-            "https://www.vistatheaterhollywood.com/",
-            "Now playing at Vista Theater Hollywood — 35mm and 70mm presentations.",
-        )?;
-        let vista_feed_path = vista_scraper.rss_filename();
-        fs::write(&vista_feed_path, vista_rss_xml)?;
-        println!("✓ Vista Theatre RSS feed saved to: {}", vista_feed_path);
+            .map_err(|e| format!("cannot list Space Cinema venues: {e}"))?;
+        if args.space_venues {
+            for venue in &venues {
+                println!("{:>6}  {:<32} {}", venue.id, venue.name, venue.city);
+            }
+            return Ok(());
+        }
+        config.feeds.extend(config::space_feeds(
+            &venues,
+            &args.space,
+            args.space_feed.as_deref(),
+        )?);
+    }
+
+    if let Some(ref name) = args.feed
+        && !config.feeds.iter().any(|f| &f.name == name)
+    {
+        let available: Vec<&str> = config.feeds.iter().map(|f| f.name.as_str()).collect();
+        return Err(format!(
+            "unknown feed '{name}' (available: {})",
+            available.join(", ")
+        )
+        .into());
+    }
+
+    if args.digest.is_some() {
+        let Some(ref email) = config.email else {
+            return Err("--digest needs an [email] table in the config".into());
+        };
+        if let Some(name) = email
+            .feeds
+            .iter()
+            .find(|name| !config.feeds.iter().any(|f| f.name == **name))
+        {
+            return Err(format!("unknown feed '{name}' in [email] feeds").into());
+        }
+    }
+
+    let notifiers = config
+        .notifiers
+        .iter()
+        .map(|n| Ok((n.events.clone(), n.build()?)))
+        .collect::<Result<_, String>>()?;
+    let pipeline = Pipeline {
+        args,
+        config,
+        filter,
+        watchlist,
+        notifiers,
+        client,
+        history_db,
+    };
+    if let Some(Command::Serve { addr, ttl, every }) = pipeline.args.command {
+        let schedule = match every {
+            Some(minutes) => {
+                serve::Schedule::Every(std::time::Duration::from_secs(minutes.max(1) * 60))
+            }
+            None => serve::Schedule::Lazy(std::time::Duration::from_secs(ttl * 60)),
+        };
+        let pipeline = Arc::new(pipeline);
+        let refresh: serve::Refresh = Box::new(move || {
+            let pipeline = pipeline.clone();
+            Box::pin(async move { pipeline.publish().await })
+        });
+        serve::serve(addr, schedule, refresh).await?;
+        return Ok(());
+    }
+    let run = pipeline.run().await?;
+    write_files(&run.files, pipeline.full())?;
+    pipeline.notify(&run.notices).await;
+    if let Some(period) = pipeline.args.digest
+        && let Some(ref email) = pipeline.config.email
+    {
+        match run.digest {
+            Some(ref digest) => {
+                email::send(email, digest)
+                    .await
+                    .map_err(|e| format!("cannot send the {period} digest: {e}"))?;
+                println!("✓ {period} digest sent to: {}", email.to.join(", "));
+            }
+            None => println!("Nothing showing for the {period} digest; no email sent"),
+        }
+    }
+    let outcomes = run.outcomes;
+    print_summary(&outcomes);
+    let failed_required: Vec<&str> = outcomes
+        .iter()
+        .filter(|o| o.cinema.required && o.result.is_err())
+        .map(|o| o.cinema.name.as_str())
+        .collect();
+    if !failed_required.is_empty() {
+        return Err(format!("required cinemas failed: {}", failed_required.join(", ")).into());
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    /// Every file under `dir` with its contents.
+    fn files_under(dir: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+        let mut files = BTreeMap::new();
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                files.extend(files_under(&path));
+            } else {
+                files.insert(path.clone(), fs::read(&path).unwrap());
+            }
+        }
+        files
+    }
+
+    #[tokio::test]
+    async fn replays_leave_the_live_state_alone() {
+        let recording = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/replay");
+        let state =
+            std::env::temp_dir().join(format!("cinema-scrape-replay-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&state);
+
+        // A live run already left a snapshot and an archive behind.
+        let old = [Film {
+            title: "Old Film".to_string(),
+            url: "https://www.vistatheaterhollywood.com/old".to_string(),
+            ..Default::default()
+        }];
+        SnapshotStore::new(&state)
+            .save("vista_theatre", "Vista Theater", &old)
+            .unwrap();
+        let history_db = state.join("history.sqlite");
+        History::open(&history_db)
+            .unwrap()
+            .record(Utc::now(), &[("Vista Theater", &old)])
+            .unwrap();
+        let before = files_under(&state);
+
+        let config: Config = toml::from_str(
+            r#"
+            [[feed]]
+            name = "vista_theatre"
+            title = "Vista Theater Hollywood"
+            link = "https://www.vistatheaterhollywood.com/"
+            description = "Vista"
+            language = "en"
+
+            [[feed.cinema]]
+            name = "Vista Theater"
+            kind = "vista_theatre"
+            url = "https://www.vistatheaterhollywood.com/"
+            "#,
+        )
+        .unwrap();
+        let args = Args::parse_from([
+            "cinema-scrape".as_ref(),
+            "--replay".as_ref(),
+            recording.as_os_str(),
+            "--state-dir".as_ref(),
+            state.as_os_str(),
+        ]);
+        let (client, started) = HttpClient::new(reqwest::Client::new(), 1, 1)
+            .replaying(recording.clone())
+            .unwrap();
+        clock::pin(started);
+        let pipeline = Pipeline {
+            args,
+            config,
+            filter: None,
+            watchlist: None,
+            notifiers: Vec::new(),
+            client,
+            history_db,
+        };
+
+        let first = pipeline.run().await.unwrap();
+        assert!(first.outcomes.iter().all(|o| o.result.is_ok()));
+        let second = pipeline.run().await.unwrap();
+        assert_eq!(first.files, second.files);
+        // The snapshot's film is neither published nor replaced.
+        assert!(
+            first
+                .files
+                .iter()
+                .all(|(_, body)| !body.contains("Old Film"))
+        );
+        assert_eq!(files_under(&state), before);
+
+        fs::remove_dir_all(&state).unwrap();
+    }
+}
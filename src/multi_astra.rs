@@ -2,10 +2,12 @@
 //! Listing: https://multiastra.it/film-della-settimana/
 //! Film page: https://multiastra.it/film/barry-lyndon (title, poster, regia, cast, genere, durata, sinossi, orari)
 
-use crate::{CinemaScraper, Film};
-use reqwest::{Client, header};
+use crate::{CinemaScraper, Film, HttpClient, ScrapeError, Showtime, showtime, split_genres};
+use chrono::NaiveDate;
+use futures::future::join_all;
+use reqwest::header;
 use scraper::{Html, Selector};
-use std::collections::HashSet;
+use std::collections::BTreeSet;
 
 const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) \
      AppleWebKit/537.36 (KHTML, like Gecko) Chrome/143.0.0.0 Safari/537.36";
@@ -69,7 +71,7 @@ impl MultiAstraScraper {
 
 #[async_trait::async_trait]
 impl CinemaScraper for MultiAstraScraper {
-    async fn fetch_films(&self, client: &Client) -> Result<Vec<Film>, Box<dyn std::error::Error>> {
+    async fn fetch_films(&self, client: &HttpClient) -> Result<Vec<Film>, ScrapeError> {
         let resp = client
             .get(LISTING_URL)
             .header(header::USER_AGENT, USER_AGENT)
@@ -78,48 +80,14 @@ impl CinemaScraper for MultiAstraScraper {
             .error_for_status()?;
         let body = resp.text().await?;
 
-        let urls: HashSet<String> = {
-            let listing = Html::parse_document(&body);
-            let link_sel = Selector::parse("a[href*=\"/film/\"]")?;
-            let mut urls = HashSet::new();
-            for a in listing.select(&link_sel) {
-                let href = match a.value().attr("href") {
-                    Some(h) => h.trim(),
-                    None => continue,
-                };
-                if href.is_empty() {
-                    continue;
-                }
-                let lower = href.to_lowercase();
-                if lower.contains("18tickets") || lower.contains("multiastra.18tickets") {
-                    continue;
-                }
-                let full = if lower.starts_with("http") {
-                    if lower.contains("multiastra.it/film/") {
-                        href.to_string()
-                    } else {
-                        continue;
-                    }
-                } else if href.starts_with("/film/") {
-                    format!("{}{}", BASE, href)
-                } else {
-                    continue;
-                };
-                let normalized = full
-                    .replace("http://", "https://")
-                    .replace("https://www.multiastra.it", BASE)
-                    .replace("http://www.multiastra.it", BASE);
-                urls.insert(normalized);
-            }
-            urls
-        };
+        let urls = parse_listing(&body)?;
 
         if urls.is_empty() {
             return Ok(Vec::new());
         }
 
-        let mut films = Vec::new();
-        for url in urls {
+        let today = showtime::today();
+        let details = urls.into_iter().map(|url| async move {
             let resp = match client
                 .get(&url)
                 .header(header::USER_AGENT, USER_AGENT)
@@ -127,236 +95,306 @@ impl CinemaScraper for MultiAstraScraper {
                 .await
             {
                 Ok(r) => r,
-                Err(_) => continue,
+                Err(_) => return None,
             };
             let resp = match resp.error_for_status() {
                 Ok(r) => r,
-                Err(_) => continue,
+                Err(_) => return None,
             };
             let body = match resp.text().await {
                 Ok(b) => b,
-                Err(_) => continue,
+                Err(_) => return None,
             };
-            let doc = Html::parse_document(&body);
+            parse_film_page(&url, &body, today)
+        });
+        let films: Vec<Film> = join_all(details).await.into_iter().flatten().collect();
 
-            // Title: class "title" first (per user), then h1/h2/h3
-            let mut title = None;
-            if let Ok(title_sel) = Selector::parse(".title")
-                && let Some(el) = doc.select(&title_sel).next()
-            {
-                let t = el
-                    .text()
-                    .map(|s| s.trim())
-                    .filter(|s| !s.is_empty())
-                    .collect::<Vec<_>>()
-                    .join(" ");
-                if !t.is_empty() {
-                    title = Some(t);
-                }
-            }
-            if title.is_none()
-                && let Ok(h_sel) = Selector::parse("h1, h2, h3")
-            {
-                for h in doc.select(&h_sel) {
-                    let t = h
-                        .text()
-                        .map(|s| s.trim())
-                        .filter(|s| !s.is_empty())
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    if !t.is_empty() && !t.eq("ORARI") && !t.contains("Articoli") {
-                        title = Some(t);
-                        break;
-                    }
-                }
+        Ok(films)
+    }
+
+    fn rss_filename(&self) -> String {
+        "docs/feeds/padova.xml".to_string()
+    }
+}
+
+/// multiastra.it film page URLs linked from the listing (not the 18tickets ones), sorted.
+fn parse_listing(body: &str) -> Result<Vec<String>, ScrapeError> {
+    let listing = Html::parse_document(body);
+    let link_sel = Selector::parse("a[href*=\"/film/\"]")?;
+    let mut urls = BTreeSet::new();
+    for a in listing.select(&link_sel) {
+        let href = match a.value().attr("href") {
+            Some(h) => h.trim(),
+            None => continue,
+        };
+        if href.is_empty() {
+            continue;
+        }
+        let lower = href.to_lowercase();
+        if lower.contains("18tickets") || lower.contains("multiastra.18tickets") {
+            continue;
+        }
+        let full = if lower.starts_with("http") {
+            if lower.contains("multiastra.it/film/") {
+                href.to_string()
+            } else {
+                continue;
             }
-            let title = match title {
-                Some(t) => t,
-                None => continue,
-            };
+        } else if href.starts_with("/film/") {
+            format!("{}{}", BASE, href)
+        } else {
+            continue;
+        };
+        let normalized = full
+            .replace("http://", "https://")
+            .replace("https://www.multiastra.it", BASE)
+            .replace("http://www.multiastra.it", BASE);
+        urls.insert(normalized);
+    }
+    Ok(urls.into_iter().collect())
+}
 
-            // Poster: og:image then first img
-            let poster_url = Selector::parse("meta[property=\"og:image\"]")
-                .ok()
-                .and_then(|sel| {
-                    doc.select(&sel)
-                        .next()
-                        .and_then(|m| m.value().attr("content").map(String::from))
-                })
-                .or_else(|| {
-                    Selector::parse("img[src]").ok().and_then(|sel| {
-                        doc.select(&sel).find_map(|img| {
-                            let src = img.value().attr("src")?;
-                            let s = src.trim();
-                            if s.starts_with("http")
-                                && !s.contains("logo")
-                                && !s.contains("cookie")
-                                && !s.contains("astra_181")
-                                && !s.contains("127.png")
-                            {
-                                Some(s.to_string())
-                            } else {
-                                None
-                            }
-                        })
-                    })
-                });
+/// A film page: title, poster, Regia/Attori/Genere/Durata, the synopsis after the
+/// metadata and the ORARI section. `None` if the page has no title.
+fn parse_film_page(url: &str, body: &str, today: NaiveDate) -> Option<Film> {
+    let doc = Html::parse_document(body);
 
-            let all_text: Vec<String> = doc
-                .root_element()
+    // Title: class "title" first (per user), then h1/h2/h3
+    let mut title = None;
+    if let Ok(title_sel) = Selector::parse(".title")
+        && let Some(el) = doc.select(&title_sel).next()
+    {
+        let t = el
+            .text()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !t.is_empty() {
+            title = Some(t);
+        }
+    }
+    if title.is_none()
+        && let Ok(h_sel) = Selector::parse("h1, h2, h3")
+    {
+        for h in doc.select(&h_sel) {
+            let t = h
                 .text()
-                .map(|t| t.trim())
-                .filter(|t| !t.is_empty())
-                .map(String::from)
-                .collect();
-
-            let mut regia = None;
-            let mut attori = None;
-            let mut genere = None;
-            let mut running_time = None;
-            let mut synopsis_parts = Vec::new();
-            let mut after_metadata = false;
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+            if !t.is_empty() && !t.eq("ORARI") && !t.contains("Articoli") {
+                title = Some(t);
+                break;
+            }
+        }
+    }
+    let title = title?;
 
-            for (i, line) in all_text.iter().enumerate() {
-                if line.starts_with("Regia:") {
-                    let v = line.trim_start_matches("Regia:").trim();
-                    let v = if v.is_empty() || v == "." {
-                        all_text.get(i + 1).map(String::as_str).unwrap_or("").trim()
-                    } else {
-                        v
-                    };
-                    if !v.is_empty() && v != "." {
-                        regia = Some(v.to_string());
-                    }
-                } else if line.starts_with("Attori:") {
-                    let v = line.trim_start_matches("Attori:").trim();
-                    let v = if v.is_empty() || v == "." {
-                        all_text.get(i + 1).map(String::as_str).unwrap_or("").trim()
-                    } else {
-                        v
-                    };
-                    if !v.is_empty() && v != "." {
-                        attori = Some(v.to_string());
-                    }
-                } else if line.starts_with("Genere:") {
-                    let v = line.trim_start_matches("Genere:").trim();
-                    let v = if v.is_empty() || v == "." {
-                        all_text.get(i + 1).map(String::as_str).unwrap_or("").trim()
-                    } else {
-                        v
-                    };
-                    if !v.is_empty() && v != "." {
-                        genere = Some(v.to_string());
-                    }
-                } else if line.starts_with("Durata:") {
-                    let rest = line.trim_start_matches("Durata:").trim();
-                    if let Some(num_str) = rest.split_whitespace().next() {
-                        running_time = num_str.parse::<u32>().ok();
-                    }
-                    after_metadata = true;
-                } else if after_metadata {
-                    if line.eq("ORARI")
-                        || line.contains("ALTRI FILM")
-                        || line.contains("Articoli correlati")
+    // Poster: og:image then first img
+    let poster_url = Selector::parse("meta[property=\"og:image\"]")
+        .ok()
+        .and_then(|sel| {
+            doc.select(&sel)
+                .next()
+                .and_then(|m| m.value().attr("content").map(String::from))
+        })
+        .or_else(|| {
+            Selector::parse("img[src]").ok().and_then(|sel| {
+                doc.select(&sel).find_map(|img| {
+                    let src = img.value().attr("src")?;
+                    let s = src.trim();
+                    if s.starts_with("http")
+                        && !s.contains("logo")
+                        && !s.contains("cookie")
+                        && !s.contains("astra_181")
+                        && !s.contains("127.png")
                     {
-                        break;
-                    }
-                    if line.starts_with("//") || line.contains("carica l'") {
-                        break;
-                    }
-                    let clean = line.split(" //").next().unwrap_or(line).trim();
-                    if clean.len() > 50
-                        && !clean.contains("Sito ufficiale")
-                        && !clean.contains("Nazionalità")
-                        && !clean.contains("Distribuzione")
-                        && !clean.contains("Home")
-                        && !clean.contains("Film della settimana")
-                        && !clean.contains("function ")
-                        && !clean.contains("frame.htm")
-                    {
-                        synopsis_parts.push(clean.to_string());
+                        Some(s.to_string())
+                    } else {
+                        None
                     }
-                }
-            }
+                })
+            })
+        });
 
-            let cast = match (regia.as_ref(), attori.as_ref(), genere.as_ref()) {
-                (Some(r), Some(a), Some(g)) => {
-                    Some(format!("Regia: {}. Attori: {}. Genere: {}", r, a, g))
-                }
-                (Some(r), Some(a), None) => Some(format!("Regia: {}. Attori: {}", r, a)),
-                (Some(r), None, Some(g)) => Some(format!("Regia: {}. Genere: {}", r, g)),
-                (None, Some(a), Some(g)) => Some(format!("Attori: {}. Genere: {}", a, g)),
-                (Some(r), None, None) => Some(format!("Regia: {}", r)),
-                (None, Some(a), None) => Some(format!("Attori: {}", a)),
-                (None, None, Some(g)) => Some(format!("Genere: {}", g)),
-                (None, None, None) => None,
-            };
-            let cast = cast.filter(|s| {
-                !s.contains("Regia: .") && !s.contains("Attori: .") && !s.eq("Genere: .")
-            });
+    let all_text: Vec<String> = doc
+        .root_element()
+        .text()
+        .map(|t| t.trim())
+        .filter(|t| !t.is_empty())
+        .map(String::from)
+        .collect();
 
-            let synopsis = if synopsis_parts.is_empty() {
-                None
+    let mut regia = None;
+    let mut attori = None;
+    let mut genere = None;
+    let mut running_time = None;
+    let mut synopsis_parts = Vec::new();
+    let mut after_metadata = false;
+
+    for (i, line) in all_text.iter().enumerate() {
+        if line.starts_with("Regia:") {
+            let v = line.trim_start_matches("Regia:").trim();
+            let v = if v.is_empty() || v == "." {
+                all_text.get(i + 1).map(String::as_str).unwrap_or("").trim()
+            } else {
+                v
+            };
+            if !v.is_empty() && v != "." {
+                regia = Some(v.to_string());
+            }
+        } else if line.starts_with("Attori:") {
+            let v = line.trim_start_matches("Attori:").trim();
+            let v = if v.is_empty() || v == "." {
+                all_text.get(i + 1).map(String::as_str).unwrap_or("").trim()
+            } else {
+                v
+            };
+            if !v.is_empty() && v != "." {
+                attori = Some(v.to_string());
+            }
+        } else if line.starts_with("Genere:") {
+            let v = line.trim_start_matches("Genere:").trim();
+            let v = if v.is_empty() || v == "." {
+                all_text.get(i + 1).map(String::as_str).unwrap_or("").trim()
             } else {
-                Some(synopsis_parts.join(" "))
+                v
             };
+            if !v.is_empty() && v != "." {
+                genere = Some(v.to_string());
+            }
+        } else if line.starts_with("Durata:") {
+            let rest = line.trim_start_matches("Durata:").trim();
+            if let Some(num_str) = rest.split_whitespace().next() {
+                running_time = num_str.parse::<u32>().ok();
+            }
+            after_metadata = true;
+        } else if after_metadata {
+            if line.eq("ORARI")
+                || line.contains("ALTRI FILM")
+                || line.contains("Articoli correlati")
+            {
+                break;
+            }
+            if line.starts_with("//") || line.contains("carica l'") {
+                break;
+            }
+            let clean = line.split(" //").next().unwrap_or(line).trim();
+            if clean.len() > 50
+                && !clean.contains("Sito ufficiale")
+                && !clean.contains("Nazionalità")
+                && !clean.contains("Distribuzione")
+                && !clean.contains("Home")
+                && !clean.contains("Film della settimana")
+                && !clean.contains("function ")
+                && !clean.contains("frame.htm")
+            {
+                synopsis_parts.push(clean.to_string());
+            }
+        }
+    }
 
-            // ORARI: day lines (Lunedì 16/03) and time lines (20.00 or 20.00V.O.S). Order can be time then day.
-            let showtimes = {
-                let orari_start = all_text.iter().position(|l| l.eq("ORARI"));
-                let orari_end = all_text
-                    .iter()
-                    .position(|l| l.contains("ALTRI FILM") || l.contains("Articoli correlati"));
-                let start = orari_start.unwrap_or(0);
-                let end = orari_end.unwrap_or(all_text.len());
-                let slice = &all_text[start..end];
-                let mut showtimes_vec = Vec::new();
-                let mut time_buf = Vec::new();
-                let mut last_day: Option<String> = None;
-                for line in slice {
-                    if is_day_line(line) {
-                        let day_clean = line.trim().trim_matches('*').trim().to_string();
-                        if let Some(ref d) = last_day
-                            && !time_buf.is_empty()
-                        {
-                            showtimes_vec.push(format!("{} ore {}", d, time_buf.join(", ")));
-                        }
-                        last_day = Some(day_clean);
-                        time_buf.clear();
-                    } else {
-                        for t in parse_time_tokens(line) {
-                            time_buf.push(t);
-                        }
-                    }
-                }
-                if let Some(ref d) = last_day
-                    && !time_buf.is_empty()
-                {
-                    showtimes_vec.push(format!("{} ore {}", d, time_buf.join(", ")));
+    let genres = genere.as_deref().map(split_genres).unwrap_or_default();
+
+    let synopsis = if synopsis_parts.is_empty() {
+        None
+    } else {
+        Some(synopsis_parts.join(" "))
+    };
+
+    // ORARI: day lines (Lunedì 16/03) and time lines (20.00 or 20.00V.O.S). Order can be time then day.
+    let showtimes = {
+        let orari_start = all_text.iter().position(|l| l.eq("ORARI"));
+        let orari_end = all_text
+            .iter()
+            .position(|l| l.contains("ALTRI FILM") || l.contains("Articoli correlati"));
+        let start = orari_start.unwrap_or(0);
+        let end = orari_end.unwrap_or(all_text.len());
+        let slice = &all_text[start..end];
+        let mut showtimes_vec: Vec<Showtime> = Vec::new();
+        // Times with the version tag that follows them on their line ("V.O.S").
+        let mut time_buf: Vec<(String, Option<String>)> = Vec::new();
+        let mut last_day: Option<String> = None;
+        let mut flush = |day: &str, times: &mut Vec<(String, Option<String>)>| {
+            if let Some(date) = showtime::parse_date(day, today) {
+                showtimes_vec.extend(times.iter().filter_map(|(t, version)| {
+                    let time = showtime::parse_time(t)?;
+                    Showtime::at(date, time, chrono_tz::Europe::Rome)
+                        .map(|st| st.with_version(version.clone()))
+                }));
+            }
+            times.clear();
+        };
+        for line in slice {
+            if is_day_line(line) {
+                let day_clean = line.trim().trim_matches('*').trim().to_string();
+                if let Some(ref d) = last_day {
+                    flush(d, &mut time_buf);
                 }
-                if showtimes_vec.is_empty() {
-                    None
-                } else {
-                    Some(showtimes_vec)
+                last_day = Some(day_clean);
+                time_buf.clear();
+            } else {
+                let tokens = parse_time_tokens(line);
+                let version = tokens
+                    .last()
+                    .and_then(|last| line.rfind(last.as_str()).map(|at| at + last.len()))
+                    .map(|end| line[end..].trim())
+                    .filter(|rest| !rest.is_empty())
+                    .map(String::from);
+                for t in tokens {
+                    time_buf.push((t, version.clone()));
                 }
-            };
-
-            films.push(Film {
-                title,
-                url: url.clone(),
-                poster_url,
-                cast,
-                release_date: None,
-                running_time,
-                synopsis,
-                showtimes,
-            });
+            }
         }
+        if let Some(ref d) = last_day {
+            flush(d, &mut time_buf);
+        }
+        if showtimes_vec.is_empty() {
+            None
+        } else {
+            Some(showtimes_vec)
+        }
+    };
 
-        Ok(films)
-    }
+    Some(Film {
+        title,
+        url: url.to_string(),
+        poster_url,
+        cast: attori,
+        director: regia,
+        genres,
+        running_time,
+        synopsis,
+        showtimes,
+        ..Default::default()
+    })
+}
 
-    fn rss_filename(&self) -> String {
-        "docs/feeds/padova.xml".to_string()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn parses_fixture() {
+        let urls =
+            parse_listing(&fixtures::load("multi_astra", "film-della-settimana.html")).unwrap();
+        let films: Vec<Film> = urls
+            .iter()
+            .filter_map(|url| {
+                let page = format!(
+                    "{}.html",
+                    url.trim_end_matches('/').rsplit('/').next().unwrap()
+                );
+                parse_film_page(
+                    url,
+                    &fixtures::load("multi_astra", &page),
+                    fixtures::today(),
+                )
+            })
+            .collect();
+        fixtures::assert_films("multi_astra", &films);
     }
 }
@@ -2,8 +2,10 @@
 //! Schedule: https://thenewbev.com/schedule/
 //! Each program page has synopsis, Director/Writer/Starring/Year/Country/Format/Running time.
 
-use crate::{CinemaScraper, Film};
-use reqwest::{Client, header};
+use crate::{CinemaScraper, Film, Format, HttpClient, ScrapeError, Showtime, showtime};
+use chrono::NaiveDate;
+use futures::future::join_all;
+use reqwest::header;
 use scraper::{Html, Selector};
 use std::collections::HashMap;
 
@@ -12,11 +14,11 @@ const SCHEDULE_URL: &str = "https://thenewbev.com/schedule/";
 const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) \
      AppleWebKit/537.36 (KHTML, like Gecko) Chrome/143.0.0.0 Safari/537.36";
 
-/// One screening from the schedule (before merging by URL).
+/// One schedule card (before merging by URL); a card can list several start times.
 struct ScheduleEntry {
     title: String,
     url: String,
-    showtime: String,
+    showtimes: Vec<Showtime>,
     poster_url: Option<String>,
 }
 
@@ -24,7 +26,7 @@ struct ScheduleEntry {
 struct UniqueProgram {
     title: String,
     url: String,
-    showtimes: Vec<String>,
+    showtimes: Vec<Showtime>,
     poster_url: Option<String>,
 }
 
@@ -42,7 +44,7 @@ impl NewBevScraper {
 
 #[async_trait::async_trait]
 impl CinemaScraper for NewBevScraper {
-    async fn fetch_films(&self, client: &Client) -> Result<Vec<Film>, Box<dyn std::error::Error>> {
+    async fn fetch_films(&self, client: &HttpClient) -> Result<Vec<Film>, ScrapeError> {
         let resp = client
             .get(&self.schedule_url)
             .header(header::USER_AGENT, USER_AGENT)
@@ -51,52 +53,18 @@ impl CinemaScraper for NewBevScraper {
             .error_for_status()?;
         let body = resp.text().await?;
 
-        let entries = parse_schedule(&body)?;
+        let entries = parse_schedule(&body, showtime::today_in(chrono_tz::America::Los_Angeles))?;
         if entries.is_empty() {
             return Ok(Vec::new());
         }
 
-        // Deduplicate by program URL: one fetch per unique link, merge showtimes.
-        let mut by_url: HashMap<String, UniqueProgram> = HashMap::new();
-        for entry in entries {
-            by_url
-                .entry(entry.url.clone())
-                .or_insert_with(|| UniqueProgram {
-                    title: entry.title.clone(),
-                    url: entry.url.clone(),
-                    showtimes: Vec::new(),
-                    poster_url: entry.poster_url.clone(),
-                })
-                .showtimes
-                .push(entry.showtime);
-        }
-        let unique: Vec<UniqueProgram> = by_url.into_values().collect();
-
-        let mut films = Vec::with_capacity(unique.len());
-        for program in unique {
-            let (synopsis, cast, running_time, poster_from_page) =
-                fetch_program_page(client, &program.url).await;
-
-            let poster_url = poster_from_page.or(program.poster_url);
-            let cast = if cast.is_empty() { None } else { Some(cast) };
-            let synopsis = if synopsis.is_empty() {
-                None
-            } else {
-                Some(synopsis)
-            };
-
-            films.push(Film {
-                title: program.title,
-                url: program.url,
-                poster_url,
-                cast,
-                release_date: None,
-                running_time,
-                synopsis,
-                showtimes: Some(program.showtimes),
+        let details = unique_programs(entries)
+            .into_iter()
+            .map(|program| async move {
+                let page = fetch_program_page(client, &program.url).await;
+                program_film(program, page.as_deref())
             });
-        }
-        Ok(films)
+        Ok(join_all(details).await)
     }
 
     fn rss_filename(&self) -> String {
@@ -104,7 +72,7 @@ impl CinemaScraper for NewBevScraper {
     }
 }
 
-fn parse_schedule(html: &str) -> Result<Vec<ScheduleEntry>, Box<dyn std::error::Error>> {
+fn parse_schedule(html: &str, today: NaiveDate) -> Result<Vec<ScheduleEntry>, ScrapeError> {
     let doc = Html::parse_document(html);
     let card_sel = Selector::parse("article.event-card").map_err(|e| e.to_string())?;
     let link_sel = Selector::parse("a[href*='/program/']").map_err(|e| e.to_string())?;
@@ -174,12 +142,16 @@ fn parse_schedule(html: &str) -> Result<Vec<ScheduleEntry>, Box<dyn std::error::
             .select(&time_sel)
             .map(|t| t.text().collect::<String>().trim().to_string())
             .collect();
-        let showtime = if times.is_empty() {
-            format!("{} {} {}", day, month, numb)
-        } else {
-            format!("{} {} {} - {}", day, month, numb, times.join(" / "))
-        };
-        let showtime = showtime.trim().to_string();
+        let date = showtime::parse_date(&format!("{} {} {}", day, month, numb), today);
+        let showtimes: Vec<Showtime> = date
+            .map(|date| {
+                times
+                    .iter()
+                    .filter_map(|t| showtime::parse_time(t))
+                    .filter_map(|t| Showtime::at(date, t, chrono_tz::America::Los_Angeles))
+                    .collect()
+            })
+            .unwrap_or_default();
 
         let poster_url = link
             .select(&img_sel)
@@ -199,39 +171,103 @@ fn parse_schedule(html: &str) -> Result<Vec<ScheduleEntry>, Box<dyn std::error::
         entries.push(ScheduleEntry {
             title,
             url,
-            showtime,
+            showtimes,
             poster_url,
         });
     }
     Ok(entries)
 }
 
-/// Fetch program page and return (synopsis, cast_info, running_time_minutes, poster_url).
-async fn fetch_program_page(
-    client: &Client,
-    url: &str,
-) -> (String, String, Option<u32>, Option<String>) {
-    let resp = match client
+/// Fetch a program page; `None` if the request fails (the film keeps its schedule data).
+async fn fetch_program_page(client: &HttpClient, url: &str) -> Option<String> {
+    let resp = client
         .get(url)
         .header(header::USER_AGENT, USER_AGENT)
         .send()
         .await
-    {
-        Ok(r) => r,
-        Err(_) => return (String::new(), String::new(), None, None),
-    };
-    let resp = match resp.error_for_status() {
-        Ok(r) => r,
-        Err(_) => return (String::new(), String::new(), None, None),
+        .ok()?
+        .error_for_status()
+        .ok()?;
+    resp.text().await.ok()
+}
+
+/// Deduplicate by program URL: one page fetch per unique link, showtimes merged,
+/// in schedule order.
+fn unique_programs(entries: Vec<ScheduleEntry>) -> Vec<UniqueProgram> {
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut unique: Vec<UniqueProgram> = Vec::new();
+    for entry in entries {
+        let i = *index.entry(entry.url.clone()).or_insert_with(|| {
+            unique.push(UniqueProgram {
+                title: entry.title.clone(),
+                url: entry.url.clone(),
+                showtimes: Vec::new(),
+                poster_url: entry.poster_url.clone(),
+            });
+            unique.len() - 1
+        });
+        unique[i].showtimes.extend(entry.showtimes);
+    }
+    unique
+}
+
+/// Combine a program's schedule data with its (optional) program page.
+fn program_film(program: UniqueProgram, page: Option<&str>) -> Film {
+    let page = page.map(parse_program_page).unwrap_or_default();
+
+    let poster_url = page.poster_url.or(program.poster_url);
+    let cast = if page.cast.is_empty() {
+        None
+    } else {
+        Some(page.cast)
     };
-    let body = match resp.text().await {
-        Ok(b) => b,
-        Err(_) => return (String::new(), String::new(), None, None),
+    let synopsis = if page.synopsis.is_empty() {
+        None
+    } else {
+        Some(page.synopsis)
     };
-    parse_program_page(&body)
+
+    Film {
+        title: program.title,
+        url: program.url,
+        poster_url,
+        cast,
+        director: page.director,
+        year: page.year,
+        country: page.country,
+        running_time: page.running_time,
+        synopsis,
+        showtimes: if program.showtimes.is_empty() {
+            None
+        } else {
+            Some(
+                program
+                    .showtimes
+                    .into_iter()
+                    .map(|st| st.with_formats(&page.formats))
+                    .collect(),
+            )
+        },
+        ..Default::default()
+    }
+}
+
+/// What a program page adds to the schedule data.
+#[derive(Default)]
+struct ProgramPage {
+    synopsis: String,
+    /// Writer and Starring, as "Label: value" parts.
+    cast: String,
+    /// The print being shown, e.g. 35mm.
+    formats: Vec<Format>,
+    director: Option<String>,
+    year: Option<u32>,
+    country: Option<String>,
+    running_time: Option<u32>,
+    poster_url: Option<String>,
 }
 
-fn parse_program_page(html: &str) -> (String, String, Option<u32>, Option<String>) {
+fn parse_program_page(html: &str) -> ProgramPage {
     let doc = Html::parse_document(html);
 
     // Poster: og:image first, then first .movie__poster img or .movie-mast__poster-img
@@ -266,7 +302,11 @@ fn parse_program_page(html: &str) -> (String, String, Option<u32>, Option<String
 
     // Director, Writer, Starring, Year, Country, Format, Running Time (site uses one <dl> per label)
     let mut running_time: Option<u32> = None;
+    let mut director: Option<String> = None;
+    let mut year: Option<u32> = None;
+    let mut country: Option<String> = None;
     let mut cast_parts: Vec<String> = Vec::new();
+    let mut formats: Vec<Format> = Vec::new();
     let dt_sel = Selector::parse("dl dt").ok();
     let dd_sel = Selector::parse("dl dd").ok();
     if let (Some(ref dt_sel), Some(ref dd_sel)) = (dt_sel, dd_sel) {
@@ -285,14 +325,21 @@ fn parse_program_page(html: &str) -> (String, String, Option<u32>, Option<String
                 if let Ok(n) = mins.parse::<u32>() {
                     running_time = Some(n);
                 }
-            } else if !dd.is_empty()
-                && (dt.eq_ignore_ascii_case("Director")
-                    || dt.eq_ignore_ascii_case("Writer")
-                    || dt.eq_ignore_ascii_case("Starring")
-                    || dt.eq_ignore_ascii_case("Year")
-                    || dt.eq_ignore_ascii_case("Country")
-                    || dt.eq_ignore_ascii_case("Format"))
-            {
+            } else if dd.is_empty() {
+                continue;
+            } else if dt.eq_ignore_ascii_case("Director") {
+                director = Some(dd.to_string());
+            } else if dt.eq_ignore_ascii_case("Year") {
+                year = dd.parse().ok();
+            } else if dt.eq_ignore_ascii_case("Country") {
+                country = Some(dd.to_string());
+            } else if dt.eq_ignore_ascii_case("Format") {
+                for format in Format::detect(dd) {
+                    if !formats.contains(&format) {
+                        formats.push(format);
+                    }
+                }
+            } else if dt.eq_ignore_ascii_case("Writer") || dt.eq_ignore_ascii_case("Starring") {
                 cast_parts.push(format!("{}: {}", dt, dd));
             }
         }
@@ -339,7 +386,34 @@ fn parse_program_page(html: &str) -> (String, String, Option<u32>, Option<String
         }
     }
 
-    let synopsis = synopsis_parts.join("\n\n");
-    let cast = cast_parts.join(" | ");
-    (synopsis, cast, running_time, poster_url)
+    ProgramPage {
+        synopsis: synopsis_parts.join("\n\n"),
+        cast: cast_parts.join(" | "),
+        formats,
+        director,
+        year,
+        country,
+        running_time,
+        poster_url,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn parses_fixture() {
+        let schedule = fixtures::load("new_bev", "schedule.html");
+        let entries = parse_schedule(&schedule, fixtures::today()).unwrap();
+        let films: Vec<Film> = unique_programs(entries)
+            .into_iter()
+            .map(|program| {
+                let page = format!("{}.html", program.url.rsplit('/').next().unwrap());
+                program_film(program, Some(&fixtures::load("new_bev", &page)))
+            })
+            .collect();
+        fixtures::assert_films("new_bev", &films);
+    }
 }
@@ -0,0 +1,496 @@
+//! Chat notifications: after a run, films that appeared at a cinema are pushed to
+//! team chats. Each destination implements [`Notifier`]: a generic JSON webhook, a
+//! Telegram bot, a Matrix room or a Mastodon account. Every base URL is configurable,
+//! so a self-hosted server (or a test's mock) can stand in for the public one.
+
+use crate::{Film, Locale, Showtime};
+use chrono::{DateTime, Utc};
+use reqwest::{Client, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fmt;
+
+/// A film that appeared at a cinema since the previous run.
+#[derive(Debug, Clone)]
+pub struct Notice {
+    pub cinema: String,
+    pub film: Film,
+    /// The watchlist entry the film matches, if any.
+    pub watch: Option<String>,
+}
+
+/// What a notifier is told about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Event {
+    /// Every new film.
+    New,
+    /// Only new films matching the watchlist.
+    Watchlist,
+}
+
+impl Event {
+    pub fn wants(self, notice: &Notice) -> bool {
+        match self {
+            Event::New => true,
+            Event::Watchlist => notice.watch.is_some(),
+        }
+    }
+}
+
+impl Notice {
+    /// First screening after `now`.
+    pub fn next_showtime(&self, now: DateTime<Utc>) -> Option<&Showtime> {
+        self.film
+            .showtimes
+            .iter()
+            .flatten()
+            .filter(|s| s.start >= now)
+            .min_by_key(|s| s.start)
+    }
+
+    /// Plain-text message: title and cinema, next screening and its booking link,
+    /// watchlist entry, link.
+    pub fn text(&self, locale: Locale, now: DateTime<Utc>) -> String {
+        let mut lines = vec![format!("🎬 {} - {}", self.film.title, self.cinema)];
+        if let Some(next) = self.next_showtime(now) {
+            let label = match locale {
+                Locale::Italian => "Prossima proiezione",
+                Locale::English => "Next screening",
+            };
+            lines.push(format!("{label}: {}", next.display(locale)));
+            if let Some(ref url) = next.ticket_url {
+                let label = match locale {
+                    Locale::Italian => "Biglietti",
+                    Locale::English => "Tickets",
+                };
+                lines.push(format!("{label}: {url}"));
+            }
+        }
+        if let Some(ref watch) = self.watch {
+            lines.push(format!("Watchlist: {watch}"));
+        }
+        lines.push(self.film.url.clone());
+        lines.join("\n")
+    }
+}
+
+/// Errors never carry the request URL: Telegram puts the bot token in the path, and
+/// these end up in the run log.
+#[derive(Debug)]
+pub enum NotifyError {
+    /// The endpoint answered with a non-success status.
+    Http(StatusCode),
+    /// Connection, TLS or timeout failure.
+    Network(reqwest::Error),
+    /// A configured base URL that cannot be parsed.
+    Url(String),
+    /// Some of the notices sent one by one failed; the others went through.
+    Several {
+        failed: Vec<NotifyError>,
+        total: usize,
+    },
+}
+
+impl fmt::Display for NotifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotifyError::Http(status) => write!(f, "HTTP {status}"),
+            NotifyError::Network(e) => write!(f, "network error: {e}"),
+            NotifyError::Url(url) => write!(f, "invalid URL {url}"),
+            NotifyError::Several { failed, total } => {
+                write!(f, "{} of {total} notices failed", failed.len())?;
+                for (i, e) in failed.iter().enumerate() {
+                    write!(f, "{} {e}", if i == 0 { ":" } else { ";" })?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for NotifyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NotifyError::Network(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for NotifyError {
+    fn from(e: reqwest::Error) -> Self {
+        NotifyError::Network(e.without_url())
+    }
+}
+
+/// A chat destination for [`Notice`]s.
+#[async_trait::async_trait]
+pub trait Notifier: Send + Sync {
+    /// Name used in the run log, e.g. "telegram".
+    fn name(&self) -> &str;
+
+    /// Push `notices` (never empty) to the destination.
+    async fn notify(&self, client: &Client, notices: &[Notice]) -> Result<(), NotifyError>;
+}
+
+/// Send a request and turn a non-success status into an error.
+async fn send(request: reqwest::RequestBuilder) -> Result<(), NotifyError> {
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(NotifyError::Http(status));
+    }
+    Ok(())
+}
+
+/// Outcome of sending `total` notices one by one, `failed` being the errors.
+fn all_sent(mut failed: Vec<NotifyError>, total: usize) -> Result<(), NotifyError> {
+    match failed.len() {
+        0 => Ok(()),
+        _ if total == 1 => Err(failed.remove(0)),
+        _ => Err(NotifyError::Several { failed, total }),
+    }
+}
+
+/// `base` with `segments` appended, each percent-encoded.
+fn endpoint(base: &str, segments: &[&str]) -> Result<Url, NotifyError> {
+    let mut url = Url::parse(base).map_err(|_| NotifyError::Url(base.to_string()))?;
+    url.path_segments_mut()
+        .map_err(|_| NotifyError::Url(base.to_string()))?
+        .pop_if_empty()
+        .extend(segments);
+    Ok(url)
+}
+
+/// POSTs one JSON document per run: a `text` summary plus every film as an object,
+/// for Slack/Discord-compatible hooks and home-made bots alike.
+pub struct Webhook {
+    pub url: String,
+    pub locale: Locale,
+}
+
+#[derive(Serialize)]
+struct WebhookFilm<'a> {
+    title: &'a str,
+    cinema: &'a str,
+    url: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    poster_url: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_showtime: Option<String>,
+    /// Booking page of the next screening.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ticket_url: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    watch: Option<&'a str>,
+}
+
+#[async_trait::async_trait]
+impl Notifier for Webhook {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    async fn notify(&self, client: &Client, notices: &[Notice]) -> Result<(), NotifyError> {
+        let now = Utc::now();
+        let text: Vec<String> = notices.iter().map(|n| n.text(self.locale, now)).collect();
+        let films: Vec<WebhookFilm> = notices
+            .iter()
+            .map(|n| WebhookFilm {
+                title: &n.film.title,
+                cinema: &n.cinema,
+                url: &n.film.url,
+                poster_url: n.film.poster_url.as_deref(),
+                next_showtime: n.next_showtime(now).map(|s| s.start.to_rfc3339()),
+                ticket_url: n.next_showtime(now).and_then(|s| s.ticket_url.as_deref()),
+                watch: n.watch.as_deref(),
+            })
+            .collect();
+        let body = json!({ "text": text.join("\n\n"), "films": films });
+        send(client.post(&self.url).json(&body)).await
+    }
+}
+
+/// Telegram Bot API: one message per film, with its poster when there is one.
+pub struct Telegram {
+    /// Default: `https://api.telegram.org`.
+    pub base_url: String,
+    pub token: String,
+    pub chat_id: String,
+    pub locale: Locale,
+}
+
+#[async_trait::async_trait]
+impl Notifier for Telegram {
+    fn name(&self) -> &str {
+        "telegram"
+    }
+
+    async fn notify(&self, client: &Client, notices: &[Notice]) -> Result<(), NotifyError> {
+        let now = Utc::now();
+        let bot = format!("bot{}", self.token);
+        let mut failed = Vec::new();
+        for notice in notices {
+            let text = notice.text(self.locale, now);
+            let request = match notice.film.poster_url {
+                Some(ref poster) => client
+                    .post(endpoint(&self.base_url, &[&bot, "sendPhoto"])?)
+                    .json(&json!({ "chat_id": self.chat_id, "photo": poster, "caption": text })),
+                None => client
+                    .post(endpoint(&self.base_url, &[&bot, "sendMessage"])?)
+                    .json(&json!({ "chat_id": self.chat_id, "text": text })),
+            };
+            if let Err(e) = send(request).await {
+                failed.push(e);
+            }
+        }
+        all_sent(failed, notices.len())
+    }
+}
+
+/// Matrix client-server API: one `m.text` message per film in a room.
+pub struct Matrix {
+    /// Homeserver, e.g. `https://matrix.org`.
+    pub base_url: String,
+    /// Room ID, e.g. `!abcdef:matrix.org`.
+    pub room_id: String,
+    pub access_token: String,
+    pub locale: Locale,
+}
+
+#[async_trait::async_trait]
+impl Notifier for Matrix {
+    fn name(&self) -> &str {
+        "matrix"
+    }
+
+    async fn notify(&self, client: &Client, notices: &[Notice]) -> Result<(), NotifyError> {
+        let now = Utc::now();
+        let mut failed = Vec::new();
+        for (i, notice) in notices.iter().enumerate() {
+            // Transaction IDs make retried requests idempotent; unique per message.
+            let txn = format!("cinema-scrape-{}-{i}", now.timestamp_millis());
+            let url = endpoint(
+                &self.base_url,
+                &[
+                    "_matrix",
+                    "client",
+                    "v3",
+                    "rooms",
+                    &self.room_id,
+                    "send",
+                    "m.room.message",
+                    &txn,
+                ],
+            )?;
+            let body = json!({ "msgtype": "m.text", "body": notice.text(self.locale, now) });
+            let request = client.put(url).bearer_auth(&self.access_token).json(&body);
+            if let Err(e) = send(request).await {
+                failed.push(e);
+            }
+        }
+        all_sent(failed, notices.len())
+    }
+}
+
+/// Mastodon (or any compatible server): one status per film.
+pub struct Mastodon {
+    /// Instance, e.g. `https://mastodon.social`.
+    pub base_url: String,
+    pub access_token: String,
+    /// "public", "unlisted", "private" or "direct".
+    pub visibility: String,
+    pub locale: Locale,
+}
+
+#[async_trait::async_trait]
+impl Notifier for Mastodon {
+    fn name(&self) -> &str {
+        "mastodon"
+    }
+
+    async fn notify(&self, client: &Client, notices: &[Notice]) -> Result<(), NotifyError> {
+        let now = Utc::now();
+        let url = endpoint(&self.base_url, &["api", "v1", "statuses"])?;
+        let mut failed = Vec::new();
+        for notice in notices {
+            let body = json!({
+                "status": notice.text(self.locale, now),
+                "visibility": self.visibility,
+                "language": self.locale.code(),
+            });
+            let request = client
+                .post(url.clone())
+                .bearer_auth(&self.access_token)
+                .json(&body);
+            if let Err(e) = send(request).await {
+                failed.push(e);
+            }
+        }
+        all_sent(failed, notices.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Router;
+    use axum::extract::Request;
+    use axum::http::StatusCode as AxumStatus;
+    use chrono::{Duration, TimeZone};
+    use std::sync::{Arc, Mutex};
+
+    /// (method, path, authorization, body) of every request a mock server received.
+    type Received = Arc<Mutex<Vec<(String, String, String, serde_json::Value)>>>;
+
+    /// A local server answering 200 to everything (500 under `/broken`), recording requests.
+    async fn mock_server() -> (String, Received) {
+        let received: Received = Arc::default();
+        let log = received.clone();
+        let app = Router::new().fallback(move |request: Request| {
+            let log = log.clone();
+            async move {
+                let (parts, body) = request.into_parts();
+                let body = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+                let auth = parts
+                    .headers
+                    .get("authorization")
+                    .map(|v| v.to_str().unwrap().to_string())
+                    .unwrap_or_default();
+                let path = parts.uri.path().to_string();
+                let status = if path.starts_with("/broken") {
+                    AxumStatus::INTERNAL_SERVER_ERROR
+                } else {
+                    AxumStatus::OK
+                };
+                log.lock().unwrap().push((
+                    parts.method.to_string(),
+                    path,
+                    auth,
+                    serde_json::from_slice(&body).unwrap(),
+                ));
+                (status, "{}")
+            }
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (base, received)
+    }
+
+    fn notices() -> Vec<Notice> {
+        let tomorrow = Utc::now() + Duration::days(1);
+        let start = chrono_tz::Europe::Rome
+            .from_utc_datetime(&tomorrow.naive_utc())
+            .fixed_offset();
+        let film = |title: &str, poster: Option<&str>| Film {
+            title: title.to_string(),
+            url: format!("https://example.com/{title}"),
+            poster_url: poster.map(String::from),
+            showtimes: Some(vec![Showtime::new(start)]),
+            ..Default::default()
+        };
+        vec![
+            Notice {
+                cinema: "Cinema Edera".to_string(),
+                film: film("Barry Lyndon", Some("https://example.com/barry.jpg")),
+                watch: Some("director \"Kubrick\"".to_string()),
+            },
+            Notice {
+                cinema: "Cinema Rex".to_string(),
+                film: film("Stalker", None),
+                watch: None,
+            },
+        ]
+    }
+
+    #[tokio::test]
+    async fn posts_to_every_kind_of_endpoint() {
+        let (base, log) = mock_server().await;
+        let client = Client::new();
+        let notices = notices();
+        let notifiers: Vec<Box<dyn Notifier>> = vec![
+            Box::new(Webhook {
+                url: format!("{base}/hook"),
+                locale: Locale::Italian,
+            }),
+            Box::new(Telegram {
+                base_url: base.clone(),
+                token: "123:abc".to_string(),
+                chat_id: "-100".to_string(),
+                locale: Locale::Italian,
+            }),
+            Box::new(Matrix {
+                base_url: base.clone(),
+                room_id: "!room:example.org".to_string(),
+                access_token: "secret".to_string(),
+                locale: Locale::English,
+            }),
+            Box::new(Mastodon {
+                base_url: format!("{base}/"),
+                access_token: "toot".to_string(),
+                visibility: "unlisted".to_string(),
+                locale: Locale::Italian,
+            }),
+        ];
+        for notifier in &notifiers {
+            notifier.notify(&client, &notices).await.unwrap();
+        }
+
+        let received = log.lock().unwrap().clone();
+        let paths: Vec<&str> = received.iter().map(|(_, p, _, _)| p.as_str()).collect();
+        assert_eq!(
+            paths[..3],
+            ["/hook", "/bot123:abc/sendPhoto", "/bot123:abc/sendMessage"]
+        );
+        assert!(
+            paths[3].starts_with("/_matrix/client/v3/rooms/!room:example.org/send/m.room.message/")
+        );
+        assert_eq!(paths[5..], ["/api/v1/statuses", "/api/v1/statuses"]);
+
+        let hook = &received[0].3;
+        assert_eq!(hook["films"][0]["cinema"], "Cinema Edera");
+        assert_eq!(
+            hook["films"][0]["poster_url"],
+            "https://example.com/barry.jpg"
+        );
+        assert!(hook["films"][1]["next_showtime"].is_string());
+        let caption = received[1].3["caption"].as_str().unwrap();
+        assert!(caption.starts_with("🎬 Barry Lyndon - Cinema Edera\nProssima proiezione: "));
+        assert!(caption.contains("Watchlist: director \"Kubrick\""));
+        assert_eq!(received[3].0, "PUT");
+        assert_eq!(received[3].2, "Bearer secret");
+        assert!(
+            received[3].3["body"]
+                .as_str()
+                .unwrap()
+                .contains("Next screening: ")
+        );
+        assert_eq!(received[5].3["visibility"], "unlisted");
+
+        let broken = Webhook {
+            url: format!("{base}/broken"),
+            locale: Locale::Italian,
+        };
+        assert!(matches!(
+            broken.notify(&client, &notices).await,
+            Err(NotifyError::Http(_))
+        ));
+
+        // Every notice is tried, and the token stays out of the error.
+        let broken = Telegram {
+            base_url: format!("{base}/broken"),
+            token: "123:secret".to_string(),
+            chat_id: "42".to_string(),
+            locale: Locale::Italian,
+        };
+        let sent = log.lock().unwrap().len();
+        let error = broken.notify(&client, &notices).await.unwrap_err();
+        assert_eq!(log.lock().unwrap().len(), sent + 2);
+        assert!(
+            matches!(error, NotifyError::Several { ref failed, total: 2 } if failed.len() == 2)
+        );
+        assert!(!error.to_string().contains("secret"));
+    }
+}
@@ -1,7 +1,9 @@
-use crate::{CinemaScraper, Film};
-use reqwest::{Client, header};
+use crate::{CinemaScraper, Film, HttpClient, ScrapeError, Showtime, showtime};
+use chrono::NaiveDate;
+use futures::future::join_all;
+use reqwest::header;
 use scraper::{Html, Selector};
-use std::collections::HashSet;
+use std::collections::BTreeSet;
 
 /// True if the line looks like "Domenica 15/02", "**Mercoledì 18/02**", etc.
 fn is_day_line(line: &str) -> bool {
@@ -60,7 +62,7 @@ impl PortoAstraScraper {
 
 #[async_trait::async_trait]
 impl CinemaScraper for PortoAstraScraper {
-    async fn fetch_films(&self, client: &Client) -> Result<Vec<Film>, Box<dyn std::error::Error>> {
+    async fn fetch_films(&self, client: &HttpClient) -> Result<Vec<Film>, ScrapeError> {
         let resp = client
             .get(&self.url)
             .header(
@@ -74,42 +76,16 @@ impl CinemaScraper for PortoAstraScraper {
             .error_for_status()?;
 
         let body = resp.text().await?;
-        // Limit lifetime of Html to avoid crossing await boundaries
-        let urls: HashSet<String> = {
-            let listing = Html::parse_document(&body);
-
-            // Collect unique film URLs from listing page
-            let link_sel = Selector::parse("a[href*=\"/film/\"]")?;
-            let mut urls = HashSet::new();
-
-            for a in listing.select(&link_sel) {
-                if let Some(href) = a.value().attr("href") {
-                    let href = href.trim();
-                    if href.is_empty() {
-                        continue;
-                    }
-                    let full = if href.starts_with("http") {
-                        href.to_string()
-                    } else if href.starts_with('/') {
-                        format!("https://portoastra.it{}", href)
-                    } else {
-                        format!("https://portoastra.it/{}", href)
-                    };
-                    urls.insert(full);
-                }
-            }
-
-            urls
-        };
+        let urls = parse_listing(&body)?;
 
         if urls.is_empty() {
             return Ok(Vec::new());
         }
 
-        let mut films = Vec::new();
+        let today = showtime::today();
 
         // For each film page, extract title, poster, metadata, synopsis.
-        for url in urls {
+        let details = urls.into_iter().map(|url| async move {
             let resp = match client
                 .get(&url)
                 .header(
@@ -122,188 +98,244 @@ impl CinemaScraper for PortoAstraScraper {
                 .await
             {
                 Ok(r) => r,
-                Err(_) => continue,
+                Err(_) => return None,
             };
 
             let resp = match resp.error_for_status() {
                 Ok(r) => r,
-                Err(_) => continue,
+                Err(_) => return None,
             };
 
             let body = match resp.text().await {
                 Ok(b) => b,
-                Err(_) => continue,
+                Err(_) => return None,
             };
 
-            let doc = Html::parse_document(&body);
+            parse_film_page(&url, &body, today)
+        });
+        let films: Vec<Film> = join_all(details).await.into_iter().flatten().collect();
 
-            // Title: try <h1>/<h2>/<h3>, then first strong/bold text
-            let mut title = None;
-            if let Ok(h_sel) = Selector::parse("h1, h2, h3")
-                && let Some(h) = doc.select(&h_sel).next()
-            {
-                let t = h
-                    .text()
-                    .map(|t| t.trim())
-                    .filter(|t| !t.is_empty())
-                    .collect::<Vec<_>>()
-                    .join(" ");
-                if !t.is_empty() {
-                    title = Some(t);
-                }
-            }
-            if title.is_none()
-                && let Ok(b_sel) = Selector::parse("b, strong")
-            {
-                for b in doc.select(&b_sel) {
-                    let t = b
-                        .text()
-                        .map(|t| t.trim())
-                        .filter(|t| !t.is_empty())
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    if !t.is_empty() && !t.contains("REGIA") && !t.contains("ATTORI") {
-                        title = Some(t);
-                        break;
-                    }
-                }
-            }
+        Ok(films)
+    }
 
-            let title = match title {
-                Some(t) => t,
-                None => continue,
+    fn rss_filename(&self) -> String {
+        "docs/feeds/padova.xml".to_string()
+    }
+}
+
+/// Film page URLs linked from the listing page, sorted and deduplicated.
+fn parse_listing(body: &str) -> Result<BTreeSet<String>, ScrapeError> {
+    let listing = Html::parse_document(body);
+    let link_sel = Selector::parse("a[href*=\"/film/\"]")?;
+    let mut urls = BTreeSet::new();
+
+    for a in listing.select(&link_sel) {
+        if let Some(href) = a.value().attr("href") {
+            let href = href.trim();
+            if href.is_empty() {
+                continue;
+            }
+            let full = if href.starts_with("http") {
+                href.to_string()
+            } else if href.starts_with('/') {
+                format!("https://portoastra.it{}", href)
+            } else {
+                format!("https://portoastra.it/{}", href)
             };
+            urls.insert(full);
+        }
+    }
+    Ok(urls)
+}
+
+/// A film page: title, poster, REGIA/ATTORI/Durata lines, the synopsis after them and
+/// the ORARI section. `None` if the page has no recognisable title.
+fn parse_film_page(url: &str, body: &str, today: NaiveDate) -> Option<Film> {
+    let doc = Html::parse_document(body);
 
-            // Collect all text lines for simple parsing
-            let all_text: Vec<String> = doc
-                .root_element()
+    // Title: try <h1>/<h2>/<h3>, then first strong/bold text
+    let mut title = None;
+    if let Ok(h_sel) = Selector::parse("h1, h2, h3")
+        && let Some(h) = doc.select(&h_sel).next()
+    {
+        let t = h
+            .text()
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !t.is_empty() {
+            title = Some(t);
+        }
+    }
+    if title.is_none()
+        && let Ok(b_sel) = Selector::parse("b, strong")
+    {
+        for b in doc.select(&b_sel) {
+            let t = b
                 .text()
                 .map(|t| t.trim())
                 .filter(|t| !t.is_empty())
-                .map(|t| t.to_string())
-                .collect();
-
-            // Poster: prefer real film poster served from appalcinema.it
-            let mut poster_url = None;
-            if let Ok(img_sel) = Selector::parse("img[src]") {
-                for img in doc.select(&img_sel) {
-                    if let Some(src) = img.value().attr("src") {
-                        let s = src.trim();
-                        if s.contains("appalcinema.") {
-                            poster_url = Some(s.to_string());
-                            break;
-                        }
-                    }
-                }
+                .collect::<Vec<_>>()
+                .join(" ");
+            if !t.is_empty() && !t.contains("REGIA") && !t.contains("ATTORI") {
+                title = Some(t);
+                break;
             }
+        }
+    }
+
+    let title = title?;
 
-            let mut regia = None;
-            let mut attori = None;
-            let mut running_time = None;
-            let mut synopsis_parts = Vec::new();
+    // Collect all text lines for simple parsing
+    let all_text: Vec<String> = doc
+        .root_element()
+        .text()
+        .map(|t| t.trim())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_string())
+        .collect();
 
-            let mut after_duration = false;
-            for line in &all_text {
-                if line.starts_with("REGIA:") {
-                    regia = Some(line.trim_start_matches("REGIA:").trim().to_string());
-                } else if line.starts_with("ATTORI:") {
-                    attori = Some(line.trim_start_matches("ATTORI:").trim().to_string());
-                } else if line.starts_with("Durata:") {
-                    let rest = line.trim_start_matches("Durata:").trim();
-                    if let Some(min_str) = rest.split_whitespace().next() {
-                        running_time = min_str.parse::<u32>().ok();
-                    }
-                    after_duration = true;
-                } else if after_duration {
-                    // Stop synopsis collection when we hit obvious non-synopsis markers
-                    if line.starts_with("Sito ufficiale")
-                        || line.starts_with("## ORARI")
-                        || line.contains('/')
-                    {
-                        break;
-                    }
-                    // Skip menu/footer and very short lines
-                    if line.len() > 40
-                        && !line.contains("Home")
-                        && !line.contains("Film della settimana")
-                        && !line.contains("Il cinema")
-                        && !line.contains("Info e costi")
-                    {
-                        synopsis_parts.push(line.clone());
-                    }
+    // Poster: prefer real film poster served from appalcinema.it
+    let mut poster_url = None;
+    if let Ok(img_sel) = Selector::parse("img[src]") {
+        for img in doc.select(&img_sel) {
+            if let Some(src) = img.value().attr("src") {
+                let s = src.trim();
+                if s.contains("appalcinema.") {
+                    poster_url = Some(s.to_string());
+                    break;
                 }
             }
+        }
+    }
 
-            let cast = match (regia, attori) {
-                (Some(r), Some(a)) => Some(format!("Regia: {}. Attori: {}", r, a)),
-                (Some(r), None) => Some(format!("Regia: {}", r)),
-                (None, Some(a)) => Some(format!("Attori: {}", a)),
-                (None, None) => None,
-            };
+    let mut regia = None;
+    let mut attori = None;
+    let mut running_time = None;
+    let mut synopsis_parts = Vec::new();
 
-            let synopsis = if synopsis_parts.is_empty() {
-                None
-            } else {
-                Some(synopsis_parts.join(" "))
-            };
+    let mut after_duration = false;
+    for line in &all_text {
+        if line.starts_with("REGIA:") {
+            regia = Some(line.trim_start_matches("REGIA:").trim().to_string());
+        } else if line.starts_with("ATTORI:") {
+            attori = Some(line.trim_start_matches("ATTORI:").trim().to_string());
+        } else if line.starts_with("Durata:") {
+            let rest = line.trim_start_matches("Durata:").trim();
+            if let Some(min_str) = rest.split_whitespace().next() {
+                running_time = min_str.parse::<u32>().ok();
+            }
+            after_duration = true;
+        } else if after_duration {
+            // Stop synopsis collection when we hit obvious non-synopsis markers
+            if line.starts_with("Sito ufficiale")
+                || line.starts_with("## ORARI")
+                || line.contains('/')
+            {
+                break;
+            }
+            // Skip menu/footer and very short lines
+            if line.len() > 40
+                && !line.contains("Home")
+                && !line.contains("Film della settimana")
+                && !line.contains("Il cinema")
+                && !line.contains("Info e costi")
+            {
+                synopsis_parts.push(line.clone());
+            }
+        }
+    }
 
-            // Parse ORARI section: day lines (e.g. "Domenica 15/02", "**Mercoledì 18/02**") and time lines (single or concatenated like "17.4020.1022.30").
-            // Times are associated with the most recently seen day; when we see a new day we flush the previous day's times.
-            let showtimes = {
-                let orari_start = all_text.iter().position(|l| l.contains("ORARI"));
-                let orari_end = all_text
-                    .iter()
-                    .position(|l| l.contains("ALTRI FILM") || l.contains("Articoli correlati"));
-                let start = orari_start.unwrap_or(0);
-                let end = orari_end.unwrap_or(all_text.len());
-                let orari_slice = &all_text[start..end];
-                let mut showtimes_vec: Vec<String> = Vec::new();
-                let mut time_buf: Vec<String> = Vec::new();
-                let mut last_day: Option<String> = None;
-                for line in orari_slice {
-                    if is_day_line(line) {
-                        let day_clean = line.trim().trim_matches('*').trim().to_string();
-                        if let Some(ref d) = last_day
-                            && !time_buf.is_empty()
-                        {
-                            showtimes_vec.push(format!("{} ore {}", d, time_buf.join(", ")));
-                            time_buf.clear();
-                        }
-                        last_day = Some(day_clean);
-                    } else {
-                        for t in parse_time_tokens(line) {
-                            time_buf.push(t);
-                        }
-                    }
-                }
-                if let Some(d) = last_day
-                    && !time_buf.is_empty()
-                {
-                    showtimes_vec.push(format!("{} ore {}", d, time_buf.join(", ")));
+    let synopsis = if synopsis_parts.is_empty() {
+        None
+    } else {
+        Some(synopsis_parts.join(" "))
+    };
+
+    // Parse ORARI section: day lines (e.g. "Domenica 15/02", "**Mercoledì 18/02**") and time lines (single or concatenated like "17.4020.1022.30").
+    // Times are associated with the most recently seen day; when we see a new day we flush the previous day's times.
+    let showtimes = {
+        let orari_start = all_text.iter().position(|l| l.contains("ORARI"));
+        let orari_end = all_text
+            .iter()
+            .position(|l| l.contains("ALTRI FILM") || l.contains("Articoli correlati"));
+        let start = orari_start.unwrap_or(0);
+        let end = orari_end.unwrap_or(all_text.len());
+        let slice = &all_text[start..end];
+        let mut showtimes_vec: Vec<Showtime> = Vec::new();
+        let mut time_buf: Vec<String> = Vec::new();
+        let mut last_day: Option<String> = None;
+        let mut flush = |day: &str, times: &mut Vec<String>| {
+            if let Some(date) = showtime::parse_date(day, today) {
+                showtimes_vec.extend(
+                    times
+                        .iter()
+                        .filter_map(|t| showtime::parse_time(t))
+                        .filter_map(|t| Showtime::at(date, t, chrono_tz::Europe::Rome)),
+                );
+            }
+            times.clear();
+        };
+        for line in slice {
+            if is_day_line(line) {
+                let day_clean = line.trim().trim_matches('*').trim().to_string();
+                if let Some(ref d) = last_day {
+                    flush(d, &mut time_buf);
                 }
-                if showtimes_vec.is_empty() {
-                    None
-                } else {
-                    Some(showtimes_vec)
+                last_day = Some(day_clean);
+                time_buf.clear();
+            } else {
+                for t in parse_time_tokens(line) {
+                    time_buf.push(t);
                 }
-            };
-
-            films.push(Film {
-                title,
-                url: url.clone(),
-                poster_url,
-                cast,
-                release_date: None,
-                running_time,
-                synopsis,
-                showtimes,
-            });
+            }
+        }
+        if let Some(ref d) = last_day {
+            flush(d, &mut time_buf);
+        }
+        if showtimes_vec.is_empty() {
+            None
+        } else {
+            Some(showtimes_vec)
         }
+    };
 
-        Ok(films)
-    }
+    Some(Film {
+        title,
+        url: url.to_string(),
+        poster_url,
+        cast: attori.filter(|a| !a.is_empty()),
+        director: regia.filter(|r| !r.is_empty()),
+        running_time,
+        synopsis,
+        showtimes,
+        ..Default::default()
+    })
+}
 
-    fn rss_filename(&self) -> String {
-        "docs/feeds/padova.xml".to_string()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn parses_fixture() {
+        let urls = parse_listing(&fixtures::load("porto_astra", "home.html")).unwrap();
+        let films: Vec<Film> = urls
+            .iter()
+            .filter_map(|url| {
+                let page = format!(
+                    "{}.html",
+                    url.trim_end_matches('/').rsplit('/').next().unwrap()
+                );
+                parse_film_page(
+                    url,
+                    &fixtures::load("porto_astra", &page),
+                    fixtures::today(),
+                )
+            })
+            .collect();
+        fixtures::assert_films("porto_astra", &films);
     }
 }
@@ -0,0 +1,92 @@
+//! Projection formats of a screening, read from the cinema's page text or session
+//! attributes: "35mm Presentation", "Format: 70mm", "IMAX 3D", "4K DCP".
+
+use crate::language::words;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Format {
+    #[serde(rename = "16mm")]
+    Mm16,
+    #[serde(rename = "35mm")]
+    Mm35,
+    #[serde(rename = "70mm")]
+    Mm70,
+    #[serde(rename = "dcp")]
+    Dcp,
+    #[serde(rename = "4k_dcp")]
+    Dcp4k,
+    #[serde(rename = "imax")]
+    Imax,
+    #[serde(rename = "3d")]
+    ThreeD,
+}
+
+impl Format {
+    /// Every format named in `text`, in the order they first appear.
+    pub fn detect(text: &str) -> Vec<Format> {
+        let words = words(text);
+        let mut found = Vec::new();
+        for (i, word) in words.iter().enumerate() {
+            let next = words.get(i + 1).map(String::as_str).unwrap_or_default();
+            let format = match (word.as_str(), next) {
+                ("16", "mm") => Format::Mm16,
+                ("35", "mm") => Format::Mm35,
+                ("70", "mm") => Format::Mm70,
+                ("4", "k") => Format::Dcp4k,
+                // "4K DCP" is one format, already taken at "4K".
+                ("dcp", _) if i > 0 && words[i - 1] == "k" => continue,
+                ("dcp", _) => Format::Dcp,
+                ("imax", _) => Format::Imax,
+                ("3", "d") => Format::ThreeD,
+                _ => continue,
+            };
+            if !found.contains(&format) {
+                found.push(format);
+            }
+        }
+        found
+    }
+
+    /// A print on film rather than a digital projection.
+    pub fn is_analog(self) -> bool {
+        matches!(self, Format::Mm16 | Format::Mm35 | Format::Mm70)
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Format::Mm16 => "16mm",
+            Format::Mm35 => "35mm",
+            Format::Mm70 => "70mm",
+            Format::Dcp => "DCP",
+            Format::Dcp4k => "4K DCP",
+            Format::Imax => "IMAX",
+            Format::ThreeD => "3D",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_formats() {
+        assert_eq!(
+            Format::detect("1974 | 2h 10m | 35mm Presentation"),
+            [Format::Mm35]
+        );
+        assert_eq!(Format::detect("Format: 70mm"), [Format::Mm70]);
+        assert_eq!(
+            Format::detect("Avatar (IMAX 3D)"),
+            [Format::Imax, Format::ThreeD]
+        );
+        assert_eq!(Format::detect("4K DCP"), [Format::Dcp4k]);
+        assert_eq!(Format::detect("1981 | 2h 3m | DCP"), [Format::Dcp]);
+        assert_eq!(Format::detect("Toy Story 3"), []);
+        assert!(Format::Mm16.is_analog() && !Format::Imax.is_analog());
+    }
+}
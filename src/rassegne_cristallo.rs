@@ -1,5 +1,7 @@
-use crate::{CinemaScraper, Film};
-use reqwest::{Client, header};
+use crate::{CinemaScraper, Film, HttpClient, ScrapeError, Showtime, showtime, split_genres};
+use chrono::NaiveDate;
+use futures::future::join_all;
+use reqwest::header;
 use scraper::{Html, Selector};
 use std::collections::HashSet;
 
@@ -17,7 +19,7 @@ impl RassegneScraperCristallo {
 
 #[async_trait::async_trait]
 impl CinemaScraper for RassegneScraperCristallo {
-    async fn fetch_films(&self, client: &Client) -> Result<Vec<Film>, Box<dyn std::error::Error>> {
+    async fn fetch_films(&self, client: &HttpClient) -> Result<Vec<Film>, ScrapeError> {
         let resp = client
             .get(&self.url)
             .header(
@@ -32,69 +34,15 @@ impl CinemaScraper for RassegneScraperCristallo {
 
         let body = resp.text().await?;
 
-        // Collect unique film URLs from the Rassegna section.
-        // We scope to the amy-section row used on the Rassegna page
-        // to avoid picking up navigation/menu links.
-        let film_urls: Vec<String> = {
-            let document = Html::parse_document(&body);
-            let section_selector =
-                Selector::parse("div.amy-section.wpb_row.vc_custom_1666775304691")?;
-            let link_selector = Selector::parse("a[href*=\"/movie/\"]")?;
-
-            let mut urls = Vec::new();
-            let mut seen = HashSet::new();
-
-            for section in document.select(&section_selector) {
-                for a in section.select(&link_selector) {
-                    if let Some(href) = a.value().attr("href") {
-                        let href = href.trim();
-                        if href.is_empty() {
-                            continue;
-                        }
-                        let full = if href.starts_with("http") {
-                            href.to_string()
-                        } else {
-                            format!("https://www.cinemacristallo.com{}", href)
-                        };
-                        if seen.insert(full.clone()) {
-                            urls.push(full);
-                        }
-                    }
-                }
-            }
-
-            urls
-        };
+        let film_urls = parse_rassegna(&body)?;
 
         if film_urls.is_empty() {
             return Ok(Vec::new());
         }
 
-        // For each film page, extract:
-        // - side column block (data, genere, durata)
-        // - poster image
-        // - long-form synopsis / description
-        let info_container_selector =
-            Selector::parse("div.row.amy-single-movie div.col-md-4.col-sm-4")?;
-        let poster_selector = Selector::parse("div.row.amy-single-movie img")?;
-        // Showtimes widgets, e.g.:
-        // <div class=\"showtime-item single-cinema\">
-        //   <div class=\"st-item\">
-        //     <div class=\"st-title\">
-        //       <label>martedì 11 Nov.</label>
-        //       ...
-        //     </div>
-        //     <ul><li>17.00 - €4.00</li></ul>
-        //   </div>
-        // </div>
-        let showtime_item_selector = Selector::parse("div.showtime-item.single-cinema")?;
-        let st_title_selector = Selector::parse("div.st-title")?;
-        let date_label_selector = Selector::parse("label")?;
-        let time_li_selector = Selector::parse("ul li")?;
-
-        let mut films = Vec::new();
-
-        for url in film_urls {
+        let today = showtime::today();
+
+        let details = film_urls.into_iter().map(|url| async move {
             let resp = client
                 .get(&url)
                 .header(
@@ -108,174 +56,222 @@ impl CinemaScraper for RassegneScraperCristallo {
                 .error_for_status()?;
 
             let body = resp.text().await?;
-            let doc = Html::parse_document(&body);
-
-            let container = match doc.select(&info_container_selector).next() {
-                Some(c) => c,
-                None => {
-                    // If layout is unexpected, fall back to using <h1> as title only.
-                    let title = extract_title_fallback(&doc).unwrap_or_else(|| url.clone());
-                    films.push(Film {
-                        title,
-                        url,
-                        poster_url: extract_poster(&doc, &poster_selector),
-                        cast: None,
-                        release_date: None,
-                        running_time: None,
-                        synopsis: extract_synopsis(&doc)
-                            .map(|s| format!("Cinema: Cinema Cristallo Oderzo\n\n{}", s))
-                            .or_else(|| Some("Cinema: Cinema Cristallo Oderzo".to_string())),
-                        showtimes: None,
-                    });
+            parse_film_page(url, &body, today)
+        });
+        join_all(details).await.into_iter().collect()
+    }
+
+    fn rss_filename(&self) -> String {
+        "docs/feeds/rassegne.xml".to_string()
+    }
+}
+
+/// Film page URLs of the Rassegna section. We scope to the amy-section row used on
+/// the Rassegna page to avoid picking up navigation/menu links.
+fn parse_rassegna(body: &str) -> Result<Vec<String>, ScrapeError> {
+    let document = Html::parse_document(body);
+    let section_selector = Selector::parse("div.amy-section.wpb_row.vc_custom_1666775304691")?;
+    let link_selector = Selector::parse("a[href*=\"/movie/\"]")?;
+
+    let mut urls = Vec::new();
+    let mut seen = HashSet::new();
+
+    for section in document.select(&section_selector) {
+        for a in section.select(&link_selector) {
+            if let Some(href) = a.value().attr("href") {
+                let href = href.trim();
+                if href.is_empty() {
                     continue;
                 }
-            };
-
-            let text_lines: Vec<String> = container
-                .text()
-                .map(|t| t.trim())
-                .filter(|t| !t.is_empty())
-                .map(|t| t.to_string())
-                .collect();
-
-            let mut title: Option<String> = None;
-            let mut release_date: Option<String> = None;
-            let mut running_time: Option<u32> = None;
-            let mut genre: Option<String> = None;
-
-            for line in &text_lines {
-                let lower = line.to_lowercase();
-
-                // First non-label line is the title fallback if we don't find a better one.
-                if title.is_none()
-                    && !lower.starts_with("data uscita")
-                    && !lower.starts_with("durata")
-                    && !lower.starts_with("genere")
-                {
-                    title = Some(line.clone());
+                let full = if href.starts_with("http") {
+                    href.to_string()
+                } else {
+                    format!("https://www.cinemacristallo.com{}", href)
+                };
+                if seen.insert(full.clone()) {
+                    urls.push(full);
                 }
+            }
+        }
+    }
 
-                if lower.starts_with("data uscita") {
-                    if let Some((_, rest)) = line.split_once(':') {
-                        let value = rest.trim();
-                        if !value.is_empty() {
-                            release_date = Some(value.to_string());
-                        }
-                    }
-                } else if lower.starts_with("durata") {
-                    // Example: "Durata: 01 ore 42 minuti"
-                    if let Some((_, rest)) = line.split_once(':') {
-                        let tokens: Vec<&str> = rest.split_whitespace().collect();
-                        let mut hours: u32 = 0;
-                        let mut minutes: u32 = 0;
-                        for (idx, tok) in tokens.iter().enumerate() {
-                            if let Ok(n) = tok.parse::<u32>() {
-                                if idx + 1 < tokens.len() && tokens[idx + 1].starts_with("ore") {
-                                    hours = n;
-                                } else if idx + 1 < tokens.len()
-                                    && tokens[idx + 1].starts_with("min")
-                                {
-                                    minutes = n;
-                                }
-                            }
-                        }
-                        let total = hours.saturating_mul(60).saturating_add(minutes);
-                        if total > 0 {
-                            running_time = Some(total);
+    Ok(urls)
+}
+
+/// A single-movie page: side column (data uscita, durata, genere), poster, synopsis
+/// and the showtime widgets.
+fn parse_film_page(url: String, body: &str, today: NaiveDate) -> Result<Film, ScrapeError> {
+    // Side column block, poster and synopsis of the single-movie layout:
+    // - side column block (data, genere, durata)
+    // - poster image
+    // - long-form synopsis / description
+    let info_container_selector =
+        &Selector::parse("div.row.amy-single-movie div.col-md-4.col-sm-4")?;
+    let poster_selector = &Selector::parse("div.row.amy-single-movie img")?;
+    // Showtimes widgets, e.g.:
+    // <div class=\"showtime-item single-cinema\">
+    //   <div class=\"st-item\">
+    //     <div class=\"st-title\">
+    //       <label>martedì 11 Nov.</label>
+    //       ...
+    //     </div>
+    //     <ul><li>17.00 - €4.00</li></ul>
+    //   </div>
+    // </div>
+    let showtime_item_selector = &Selector::parse("div.showtime-item.single-cinema")?;
+    let st_title_selector = &Selector::parse("div.st-title")?;
+    let date_label_selector = &Selector::parse("label")?;
+    let time_li_selector = &Selector::parse("ul li")?;
+
+    let doc = Html::parse_document(body);
+
+    let container = match doc.select(info_container_selector).next() {
+        Some(c) => c,
+        None => {
+            // If layout is unexpected, fall back to using <h1> as title only.
+            let title = extract_title_fallback(&doc).unwrap_or_else(|| url.clone());
+            return Ok(Film {
+                title,
+                url,
+                poster_url: extract_poster(&doc, poster_selector),
+                synopsis: extract_synopsis(&doc)
+                    .map(|s| format!("Cinema: Cinema Cristallo Oderzo\n\n{}", s))
+                    .or_else(|| Some("Cinema: Cinema Cristallo Oderzo".to_string())),
+                ..Default::default()
+            });
+        }
+    };
+
+    let text_lines: Vec<String> = container
+        .text()
+        .map(|t| t.trim())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_string())
+        .collect();
+
+    let mut title: Option<String> = None;
+    let mut release_date: Option<String> = None;
+    let mut running_time: Option<u32> = None;
+    let mut genre: Option<String> = None;
+
+    for line in &text_lines {
+        let lower = line.to_lowercase();
+
+        // First non-label line is the title fallback if we don't find a better one.
+        if title.is_none()
+            && !lower.starts_with("data uscita")
+            && !lower.starts_with("durata")
+            && !lower.starts_with("genere")
+        {
+            title = Some(line.clone());
+        }
+
+        if lower.starts_with("data uscita") {
+            if let Some((_, rest)) = line.split_once(':') {
+                let value = rest.trim();
+                if !value.is_empty() {
+                    release_date = Some(value.to_string());
+                }
+            }
+        } else if lower.starts_with("durata") {
+            // Example: "Durata: 01 ore 42 minuti"
+            if let Some((_, rest)) = line.split_once(':') {
+                let tokens: Vec<&str> = rest.split_whitespace().collect();
+                let mut hours: u32 = 0;
+                let mut minutes: u32 = 0;
+                for (idx, tok) in tokens.iter().enumerate() {
+                    if let Ok(n) = tok.parse::<u32>() {
+                        if idx + 1 < tokens.len() && tokens[idx + 1].starts_with("ore") {
+                            hours = n;
+                        } else if idx + 1 < tokens.len() && tokens[idx + 1].starts_with("min") {
+                            minutes = n;
                         }
                     }
-                } else if lower.starts_with("genere")
-                    && let Some((_, rest)) = line.split_once(':')
-                {
-                    let value = rest.trim();
-                    if !value.is_empty() {
-                        genre = Some(value.to_string());
-                    }
                 }
-            }
-
-            // If we did not manage to find a title inside the info block,
-            // fall back to <h1> from the page.
-            let title = title
-                .or_else(|| extract_title_fallback(&doc))
-                .unwrap_or_else(|| url.clone());
-
-            let cast = genre.as_ref().map(|g| format!("Genere: {}", g));
-
-            let poster_url = extract_poster(&doc, &poster_selector);
-            let synopsis = extract_synopsis(&doc)
-                .map(|s| format!("Cinema: Cinema Cristallo Oderzo\n\n{}", s))
-                .or_else(|| Some("Cinema: Cinema Cristallo Oderzo".to_string()));
-
-            // Collect showtimes from the showtime widgets.
-            let mut showtime_vec: Vec<String> = Vec::new();
-            for item in doc.select(&showtime_item_selector) {
-                // Date label like "martedì 11 Nov."
-                let date = item
-                    .select(&st_title_selector)
-                    .next()
-                    .and_then(|title_div| {
-                        title_div.select(&date_label_selector).next().map(|lbl| {
-                            lbl.text()
-                                .map(|t| t.trim())
-                                .filter(|t| !t.is_empty())
-                                .collect::<Vec<_>>()
-                                .join(" ")
-                        })
-                    })
-                    .unwrap_or_default();
-
-                if date.is_empty() {
-                    continue;
+                let total = hours.saturating_mul(60).saturating_add(minutes);
+                if total > 0 {
+                    running_time = Some(total);
                 }
+            }
+        } else if lower.starts_with("genere")
+            && let Some((_, rest)) = line.split_once(':')
+        {
+            let value = rest.trim();
+            if !value.is_empty() {
+                genre = Some(value.to_string());
+            }
+        }
+    }
 
-                for li in item.select(&time_li_selector) {
-                    let text = li
-                        .text()
+    // If we did not manage to find a title inside the info block,
+    // fall back to <h1> from the page.
+    let title = title
+        .or_else(|| extract_title_fallback(&doc))
+        .unwrap_or_else(|| url.clone());
+
+    let poster_url = extract_poster(&doc, poster_selector);
+    let synopsis = extract_synopsis(&doc)
+        .map(|s| format!("Cinema: Cinema Cristallo Oderzo\n\n{}", s))
+        .or_else(|| Some("Cinema: Cinema Cristallo Oderzo".to_string()));
+
+    // Collect showtimes from the showtime widgets.
+    let mut showtime_vec: Vec<Showtime> = Vec::new();
+    for item in doc.select(showtime_item_selector) {
+        // Date label like "martedì 11 Nov."
+        let date = item
+            .select(st_title_selector)
+            .next()
+            .and_then(|title_div| {
+                title_div.select(date_label_selector).next().map(|lbl| {
+                    lbl.text()
                         .map(|t| t.trim())
                         .filter(|t| !t.is_empty())
                         .collect::<Vec<_>>()
-                        .join(" ");
-                    if text.is_empty() {
-                        continue;
-                    }
-                    // Take the first token that looks like a time, e.g. "17.00".
-                    let time_token = text
-                        .split_whitespace()
-                        .find(|tok| tok.chars().any(|c| c.is_ascii_digit()) && tok.contains('.'))
-                        .unwrap_or("")
-                        .to_string();
-                    if time_token.is_empty() {
-                        continue;
-                    }
-                    showtime_vec.push(format!("{} ore {}", date, time_token));
-                }
-            }
+                        .join(" ")
+                })
+            })
+            .and_then(|label| showtime::parse_date(&label, today));
 
-            let showtimes = if showtime_vec.is_empty() {
-                None
-            } else {
-                Some(showtime_vec)
-            };
+        let Some(date) = date else {
+            continue;
+        };
 
-            films.push(Film {
-                title,
-                url,
-                poster_url,
-                cast,
-                release_date,
-                running_time,
-                synopsis,
-                showtimes,
-            });
+        for li in item.select(time_li_selector) {
+            let text = li
+                .text()
+                .map(|t| t.trim())
+                .filter(|t| !t.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+            if text.is_empty() {
+                continue;
+            }
+            // Take the first token that looks like a time, e.g. "17.00 - €4.00".
+            if let Some(time) = showtime::parse_time(&text)
+                && let Some(st) = Showtime::at(date, time, chrono_tz::Europe::Rome)
+            {
+                showtime_vec.push(st);
+            }
         }
-
-        Ok(films)
     }
 
-    fn rss_filename(&self) -> String {
-        "docs/feeds/rassegne.xml".to_string()
-    }
+    let showtimes = if showtime_vec.is_empty() {
+        None
+    } else {
+        Some(showtime_vec)
+    };
+
+    Ok(Film {
+        title,
+        url,
+        poster_url,
+        release_date,
+        genres: genre.as_deref().map(split_genres).unwrap_or_default(),
+        running_time,
+        synopsis,
+        showtimes,
+        ..Default::default()
+    })
 }
 
 /// Fallback title extraction from a generic <h1>.
@@ -346,3 +342,27 @@ fn extract_synopsis(doc: &Html) -> Option<String> {
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn parses_fixture() {
+        let listing = fixtures::load("rassegne_cristallo", "rassegna.html");
+        let films: Vec<Film> = parse_rassegna(&listing)
+            .unwrap()
+            .into_iter()
+            .map(|url| {
+                let page = format!(
+                    "{}.html",
+                    url.trim_end_matches('/').rsplit('/').next().unwrap()
+                );
+                let body = fixtures::load("rassegne_cristallo", &page);
+                parse_film_page(url, &body, fixtures::today()).unwrap()
+            })
+            .collect();
+        fixtures::assert_films("rassegne_cristallo", &films);
+    }
+}
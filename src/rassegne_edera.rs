@@ -1,5 +1,6 @@
-use crate::{CinemaScraper, Film};
-use reqwest::{Client, header};
+use crate::{CinemaScraper, Film, HttpClient, ScrapeError};
+use futures::future::join_all;
+use reqwest::header;
 use scraper::{Html, Selector};
 use std::collections::HashSet;
 
@@ -18,7 +19,7 @@ impl RassegneScraperEdera {
 
 #[async_trait::async_trait]
 impl CinemaScraper for RassegneScraperEdera {
-    async fn fetch_films(&self, client: &Client) -> Result<Vec<Film>, Box<dyn std::error::Error>> {
+    async fn fetch_films(&self, client: &HttpClient) -> Result<Vec<Film>, ScrapeError> {
         let resp = client
             .get(&self.url)
             .header(
@@ -33,158 +34,190 @@ impl CinemaScraper for RassegneScraperEdera {
 
         let body = resp.text().await?;
 
-        // Collect unique rassegna URLs like rassegne/10-e-luce.html
-        // and, when available, their poster image URLs from the main page.
-        let rassegna_links: Vec<(String, Option<String>)> = {
-            let document = Html::parse_document(&body);
-            // Match only the image links for each rassegna entry so we
-            // reliably capture the poster banner, e.g.
-            // <a href="/rassegne/10-e-luce.html" class="post__image-link">
-            //   <img src="/_media/images/thumbs/1600x600_10_e_luce_banner.jpg" ...>
-            // </a>
-            let link_selector = Selector::parse("a.post__image-link[href*=\"rassegne/\"]")?;
-            let img_selector = Selector::parse("img")?;
-            let mut links = Vec::new();
-            let mut seen = HashSet::new();
-
-            for a in document.select(&link_selector) {
-                if let Some(href) = a.value().attr("href") {
-                    let href = href.trim();
-                    if href.is_empty() {
-                        continue;
-                    }
-                    let full_url = if href.starts_with("http") {
-                        href.to_string()
-                    } else {
-                        format!("https://www.cinemaedera.it{}", href)
-                    };
-                    if seen.insert(full_url.clone()) {
-                        // Try to grab a poster image inside the link, if present.
-                        let poster_url = a
-                            .select(&img_selector)
-                            .next()
-                            .and_then(|img| img.value().attr("src"))
-                            .map(|src| {
-                                let src = src.trim();
-                                if src.starts_with("http") {
-                                    src.to_string()
-                                } else {
-                                    format!("https://www.cinemaedera.it{}", src)
-                                }
-                            });
-
-                        links.push((full_url, poster_url));
-                    }
-                }
-            }
-
-            links
-        };
+        let rassegna_links = parse_rassegne(&body)?;
 
         if rassegna_links.is_empty() {
             return Ok(Vec::new());
         }
 
-        let mut films = Vec::new();
-
-        for (url, poster_url) in rassegna_links {
-            let resp = client
-                .get(&url)
-                .header(
-                    header::USER_AGENT,
-                    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) \
+        let details = rassegna_links
+            .into_iter()
+            .map(|(url, poster_url)| async move {
+                let resp = client
+                    .get(&url)
+                    .header(
+                        header::USER_AGENT,
+                        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) \
                      AppleWebKit/537.36 (KHTML, like Gecko) \
                      Chrome/143.0.0.0 Safari/537.36",
-                )
-                .send()
-                .await?
-                .error_for_status()?;
-
-            let body = resp.text().await?;
-            let doc = Html::parse_document(&body);
-
-            // Title from the page heading, e.g. <h2 class="page-heading">10 E LUCE</h2>
-            let title_selector = Selector::parse("h2.page-heading")?;
-            let title = doc
-                .select(&title_selector)
-                .next()
-                .map(|h2| {
-                    h2.text()
-                        .map(|t| t.trim())
-                        .filter(|t| !t.is_empty())
-                        .collect::<Vec<_>>()
-                        .join(" ")
-                })
-                .filter(|s| !s.is_empty())
-                .unwrap_or_else(|| url.clone());
-
-            // Date range line: text starting with "Dal ...".
-            let date_range = {
-                let text_nodes: Vec<String> = doc
-                    .root_element()
-                    .text()
-                    .map(|t| t.trim())
-                    .filter(|t| !t.is_empty())
-                    .map(|t| t.to_string())
-                    .collect();
-                text_nodes.iter().find(|s| s.starts_with("Dal ")).cloned()
-            };
+                    )
+                    .send()
+                    .await?
+                    .error_for_status()?;
 
-            // All <h3> blocks on the page are the per-film descriptions we care about.
-            let h3_selector = Selector::parse("h3")?;
-            let mut entries = Vec::new();
-            for h3 in doc.select(&h3_selector) {
-                let text = h3
-                    .text()
-                    .map(|t| t.trim())
-                    .filter(|t| !t.is_empty())
-                    .collect::<Vec<_>>()
-                    .join(" ");
-                if !text.is_empty() {
-                    entries.push(text);
-                }
-            }
+                let body = resp.text().await?;
+                parse_rassegna_page(url, poster_url, &body)
+            });
+        let mut films = Vec::new();
+        for film in join_all(details).await {
+            films.extend(film?);
+        }
 
-            // If there are no <h3> entries at all (e.g. flyer/ABC page), skip.
-            if entries.is_empty() {
+        Ok(films)
+    }
+
+    fn rss_filename(&self) -> String {
+        "docs/feeds/rassegne.xml".to_string()
+    }
+}
+
+/// Rassegna page URLs like rassegne/10-e-luce.html and, when available, their poster
+/// image URLs from the main page.
+fn parse_rassegne(body: &str) -> Result<Vec<(String, Option<String>)>, ScrapeError> {
+    let document = Html::parse_document(body);
+    // Match only the image links for each rassegna entry so we
+    // reliably capture the poster banner, e.g.
+    // <a href="/rassegne/10-e-luce.html" class="post__image-link">
+    //   <img src="/_media/images/thumbs/1600x600_10_e_luce_banner.jpg" ...>
+    // </a>
+    let link_selector = Selector::parse("a.post__image-link[href*=\"rassegne/\"]")?;
+    let img_selector = Selector::parse("img")?;
+    let mut links = Vec::new();
+    let mut seen = HashSet::new();
+
+    for a in document.select(&link_selector) {
+        if let Some(href) = a.value().attr("href") {
+            let href = href.trim();
+            if href.is_empty() {
                 continue;
             }
-
-            // Build a simple long-form synopsis:
-            // - Cinema name
-            // - Date range line (Dal ... al ...)
-            // - Bullet-like list of all <h3> entries.
-            let synopsis = {
-                let mut parts = Vec::new();
-                parts.push("Cinema: Cinema Edera".to_string());
-                if let Some(ds) = &date_range {
-                    parts.push(ds.clone());
-                }
-                if !entries.is_empty() {
-                    parts.push("I film della rassegna:".to_string());
-                    for e in entries {
-                        parts.push(format!("* {}", e));
-                    }
-                }
-                Some(parts.join("\n\n"))
+            let full_url = if href.starts_with("http") {
+                href.to_string()
+            } else {
+                format!("https://www.cinemaedera.it{}", href)
             };
+            if seen.insert(full_url.clone()) {
+                // Try to grab a poster image inside the link, if present.
+                let poster_url = a
+                    .select(&img_selector)
+                    .next()
+                    .and_then(|img| img.value().attr("src"))
+                    .map(|src| {
+                        let src = src.trim();
+                        if src.starts_with("http") {
+                            src.to_string()
+                        } else {
+                            format!("https://www.cinemaedera.it{}", src)
+                        }
+                    });
+
+                links.push((full_url, poster_url));
+            }
+        }
+    }
 
-            films.push(Film {
-                title,
-                url,
-                poster_url,
-                cast: None,
-                release_date: date_range,
-                running_time: None,
-                synopsis,
-                showtimes: None,
-            });
+    Ok(links)
+}
+
+/// A rassegna page as one entry: heading, "Dal ... al ..." line and the list of
+/// per-film <h3> blocks. `None` if the page lists no films (e.g. a flyer page).
+fn parse_rassegna_page(
+    url: String,
+    poster_url: Option<String>,
+    body: &str,
+) -> Result<Option<Film>, ScrapeError> {
+    let doc = Html::parse_document(body);
+
+    // Title from the page heading, e.g. <h2 class="page-heading">10 E LUCE</h2>
+    let title_selector = Selector::parse("h2.page-heading")?;
+    let title = doc
+        .select(&title_selector)
+        .next()
+        .map(|h2| {
+            h2.text()
+                .map(|t| t.trim())
+                .filter(|t| !t.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| url.clone());
+
+    // Date range line: text starting with "Dal ...".
+    let date_range = {
+        let text_nodes: Vec<String> = doc
+            .root_element()
+            .text()
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+            .map(|t| t.to_string())
+            .collect();
+        text_nodes.iter().find(|s| s.starts_with("Dal ")).cloned()
+    };
+
+    // All <h3> blocks on the page are the per-film descriptions we care about.
+    let h3_selector = Selector::parse("h3")?;
+    let mut entries = Vec::new();
+    for h3 in doc.select(&h3_selector) {
+        let text = h3
+            .text()
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !text.is_empty() {
+            entries.push(text);
         }
+    }
 
-        Ok(films)
+    // If there are no <h3> entries at all (e.g. flyer/ABC page), skip.
+    if entries.is_empty() {
+        return Ok(None);
     }
 
-    fn rss_filename(&self) -> String {
-        "docs/feeds/rassegne.xml".to_string()
+    // Build a simple long-form synopsis:
+    // - Cinema name
+    // - Date range line (Dal ... al ...)
+    // - Bullet-like list of all <h3> entries.
+    let synopsis = {
+        let mut parts = Vec::new();
+        parts.push("Cinema: Cinema Edera".to_string());
+        if let Some(ds) = &date_range {
+            parts.push(ds.clone());
+        }
+        if !entries.is_empty() {
+            parts.push("I film della rassegna:".to_string());
+            for e in entries {
+                parts.push(format!("* {}", e));
+            }
+        }
+        Some(parts.join("\n\n"))
+    };
+
+    Ok(Some(Film {
+        title,
+        url,
+        poster_url,
+        release_date: date_range,
+        synopsis,
+        ..Default::default()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn parses_fixture() {
+        let links = parse_rassegne(&fixtures::load("rassegne_edera", "rassegne.html")).unwrap();
+        let mut films = Vec::new();
+        for (url, poster_url) in links {
+            let page = url.rsplit('/').next().unwrap().to_string();
+            let body = fixtures::load("rassegne_edera", &page);
+            films.extend(parse_rassegna_page(url, poster_url, &body).unwrap());
+        }
+        fixtures::assert_films("rassegne_edera", &films);
     }
 }
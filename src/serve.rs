@@ -0,0 +1,366 @@
+//! `serve` mode: an HTTP server publishing what a run writes to docs/ (feeds in every
+//! format, the programme site) from memory, for self-hosting without a scheduled job.
+//!
+//! Content is refreshed either on a schedule (`--every`) or lazily, by the first
+//! request that finds it older than the TTL. Only one refresh runs at a time; a
+//! failed refresh keeps the previous content. Every file carries an `ETag` and a
+//! `Last-Modified` date, and conditional requests are answered with 304.
+//! `/health` reports how every cinema fared in the last refresh.
+
+use crate::fnv1a;
+use axum::Router;
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock};
+
+/// Everything one refresh publishes: files as (path under docs/, contents), and the
+/// status of every cinema.
+pub struct Published {
+    pub files: Vec<(String, String)>,
+    pub health: Vec<Health>,
+}
+
+/// How one cinema fared in the last refresh.
+#[derive(Debug, Clone, Serialize)]
+pub struct Health {
+    pub feed: String,
+    pub cinema: String,
+    pub required: bool,
+    pub ok: bool,
+    /// Films found, when the scrape succeeded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub films: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// On failure: when the snapshot served instead was taken.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot_from: Option<DateTime<Utc>>,
+}
+
+/// Runs the scrapers and renders everything, as `main` does for a one-off run.
+pub type Refresh = Box<dyn Fn() -> BoxFuture<'static, Result<Published, String>> + Send + Sync>;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Schedule {
+    /// Refresh when a request finds content older than this.
+    Lazy(Duration),
+    /// Refresh in the background this often, starting at launch.
+    Every(Duration),
+}
+
+impl Schedule {
+    fn period(self) -> Duration {
+        match self {
+            Schedule::Lazy(d) | Schedule::Every(d) => d,
+        }
+    }
+}
+
+struct Page {
+    body: String,
+    etag: String,
+    modified: DateTime<Utc>,
+}
+
+#[derive(Default)]
+struct Store {
+    pages: HashMap<String, Page>,
+    health: Vec<Health>,
+    refreshed_at: Option<DateTime<Utc>>,
+    /// Last refresh, successful or not: a failing site is not scraped on every request.
+    attempted_at: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+}
+
+impl Store {
+    /// Replace the content with a refresh's. Files whose contents did not change keep
+    /// their `Last-Modified` date; files no longer published are dropped.
+    fn update(&mut self, published: Published, now: DateTime<Utc>) {
+        let mut pages = HashMap::new();
+        for (path, body) in published.files {
+            let etag = etag(&body);
+            let modified = match self.pages.get(&path) {
+                Some(old) if old.etag == etag => old.modified,
+                _ => now,
+            };
+            pages.insert(
+                path,
+                Page {
+                    body,
+                    etag,
+                    modified,
+                },
+            );
+        }
+        self.pages = pages;
+        self.health = published.health;
+        self.refreshed_at = Some(now);
+        self.attempted_at = Some(now);
+        self.last_error = None;
+    }
+
+    fn is_fresh(&self, ttl: Duration, now: DateTime<Utc>) -> bool {
+        self.attempted_at
+            .is_some_and(|at| (now - at).to_std().is_ok_and(|age| age < ttl))
+    }
+
+    /// Answer a GET for `path` (without the leading slash), honouring
+    /// `If-None-Match` and `If-Modified-Since`.
+    fn respond(&self, path: &str, headers: &HeaderMap, max_age: Duration) -> Response {
+        let path = match path {
+            "" => "index.html".to_string(),
+            p if p.ends_with('/') => format!("{p}index.html"),
+            p => p.to_string(),
+        };
+        let Some(page) = self.pages.get(&path) else {
+            let status = if self.refreshed_at.is_none() {
+                StatusCode::SERVICE_UNAVAILABLE
+            } else {
+                StatusCode::NOT_FOUND
+            };
+            return (status, "Not found\n").into_response();
+        };
+
+        let last_modified = http_date(page.modified);
+        let mut response = if not_modified(headers, &page.etag, page.modified) {
+            StatusCode::NOT_MODIFIED.into_response()
+        } else {
+            let mut response = Response::new(Body::from(page.body.clone()));
+            response.headers_mut().insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(content_type(&path)),
+            );
+            response
+        };
+        let headers = response.headers_mut();
+        let mut set = |name, value: String| {
+            if let Ok(value) = HeaderValue::from_str(&value) {
+                headers.insert(name, value);
+            }
+        };
+        set(header::ETAG, page.etag.clone());
+        set(header::LAST_MODIFIED, last_modified);
+        set(
+            header::CACHE_CONTROL,
+            format!("public, max-age={}", max_age.as_secs()),
+        );
+        response
+    }
+}
+
+/// Whether the client's cached copy is still current. `If-None-Match` wins over
+/// `If-Modified-Since` when both are sent (RFC 9110, 13.1.3).
+fn not_modified(headers: &HeaderMap, etag: &str, modified: DateTime<Utc>) -> bool {
+    let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+    if let Some(tags) = header(header::IF_NONE_MATCH) {
+        return tags
+            .split(',')
+            .map(|t| t.trim().trim_start_matches("W/"))
+            .any(|t| t == "*" || t == etag);
+    }
+    header(header::IF_MODIFIED_SINCE)
+        .and_then(|since| DateTime::parse_from_rfc2822(since).ok())
+        .is_some_and(|since| modified.timestamp() <= since.timestamp())
+}
+
+/// Same body, same tag, across restarts and rebuilds.
+fn etag(body: &str) -> String {
+    format!("\"{:016x}\"", fnv1a(body.bytes()))
+}
+
+/// IMF-fixdate, as HTTP wants it: "Mon, 09 Feb 2026 06:00:00 GMT".
+fn http_date(at: DateTime<Utc>) -> String {
+    at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+fn content_type(path: &str) -> &'static str {
+    match path.rsplit_once('.').map(|(_, ext)| ext) {
+        Some("xml") => "application/rss+xml; charset=utf-8",
+        Some("atom") => "application/atom+xml; charset=utf-8",
+        Some("json") => "application/feed+json; charset=utf-8",
+        Some("ics") => "text/calendar; charset=utf-8",
+        Some("html") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+struct Server {
+    store: RwLock<Store>,
+    /// Held while a refresh runs, so concurrent requests wait for it instead of
+    /// starting their own.
+    refreshing: Mutex<()>,
+    refresh: Refresh,
+    schedule: Schedule,
+}
+
+impl Server {
+    async fn refresh(&self) {
+        let _running = self.refreshing.lock().await;
+        if let Schedule::Lazy(ttl) = self.schedule
+            && self.store.read().await.is_fresh(ttl, Utc::now())
+        {
+            // Another request refreshed while this one waited.
+            return;
+        }
+        match (self.refresh)().await {
+            Ok(published) => self.store.write().await.update(published, Utc::now()),
+            Err(e) => {
+                eprintln!("Refresh failed: {e}");
+                let mut store = self.store.write().await;
+                store.attempted_at = Some(Utc::now());
+                store.last_error = Some(e);
+            }
+        }
+    }
+}
+
+async fn serve_file(State(server): State<Arc<Server>>, request: Request) -> Response {
+    if let Schedule::Lazy(ttl) = server.schedule
+        && !server.store.read().await.is_fresh(ttl, Utc::now())
+    {
+        server.refresh().await;
+    }
+    let path = request.uri().path().trim_start_matches('/');
+    server
+        .store
+        .read()
+        .await
+        .respond(path, request.headers(), server.schedule.period())
+}
+
+async fn health(State(server): State<Arc<Server>>) -> Response {
+    #[derive(Serialize)]
+    struct Report<'a> {
+        ok: bool,
+        refreshed_at: Option<DateTime<Utc>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        last_error: Option<&'a str>,
+        cinemas: &'a [Health],
+    }
+    let store = server.store.read().await;
+    let ok = store.refreshed_at.is_some()
+        && store.last_error.is_none()
+        && store.health.iter().all(|h| h.ok || !h.required);
+    let report = Report {
+        ok,
+        refreshed_at: store.refreshed_at,
+        last_error: store.last_error.as_deref(),
+        cinemas: &store.health,
+    };
+    let status = if ok {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let body = serde_json::to_string_pretty(&report).unwrap_or_default();
+    (
+        status,
+        [
+            (header::CONTENT_TYPE, "application/json"),
+            (header::CACHE_CONTROL, "no-cache"),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// Serve on `addr` until the process is stopped.
+pub async fn serve(addr: SocketAddr, schedule: Schedule, refresh: Refresh) -> std::io::Result<()> {
+    let server = Arc::new(Server {
+        store: RwLock::new(Store::default()),
+        refreshing: Mutex::new(()),
+        refresh,
+        schedule,
+    });
+    if let Schedule::Every(period) = schedule {
+        let server = server.clone();
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(period);
+            loop {
+                ticks.tick().await;
+                server.refresh().await;
+            }
+        });
+    }
+
+    let app = Router::new()
+        .route("/health", get(health))
+        .fallback(get(serve_file))
+        .with_state(server);
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    println!("Serving on http://{}", listener.local_addr()?);
+    axum::serve(listener, app).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn conditional_requests_and_headers() {
+        let monday = Utc.with_ymd_and_hms(2026, 2, 9, 6, 0, 0).unwrap();
+        let published = |rss: &str| Published {
+            files: vec![
+                ("feeds/edera.xml".to_string(), rss.to_string()),
+                ("index.html".to_string(), "<html></html>".to_string()),
+            ],
+            health: Vec::new(),
+        };
+        let mut store = Store::default();
+        store.update(published("<rss>1</rss>"), monday);
+        let max_age = Duration::from_secs(3600);
+
+        let response = store.respond("feeds/edera.xml", &HeaderMap::new(), max_age);
+        assert_eq!(response.status(), StatusCode::OK);
+        let headers = response.headers();
+        assert_eq!(headers[header::CACHE_CONTROL], "public, max-age=3600");
+        assert_eq!(
+            headers[header::LAST_MODIFIED],
+            "Mon, 09 Feb 2026 06:00:00 GMT"
+        );
+        assert!(
+            headers[header::CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .starts_with("application/rss+xml")
+        );
+        let etag = headers[header::ETAG].clone();
+
+        let mut conditional = HeaderMap::new();
+        conditional.insert(header::IF_NONE_MATCH, etag.clone());
+        let response = store.respond("feeds/edera.xml", &conditional, max_age);
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        // Unchanged files keep their date across refreshes; changed ones do not.
+        let tuesday = monday + chrono::Duration::days(1);
+        store.update(published("<rss>2</rss>"), tuesday);
+        let response = store.respond("feeds/edera.xml", &conditional, max_age);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_ne!(response.headers()[header::ETAG], etag);
+        let mut since = HeaderMap::new();
+        since.insert(
+            header::IF_MODIFIED_SINCE,
+            HeaderValue::from_static("Mon, 09 Feb 2026 06:00:00 GMT"),
+        );
+        assert_eq!(
+            store.respond("", &since, max_age).status(),
+            StatusCode::NOT_MODIFIED
+        );
+        assert_eq!(
+            store.respond("feeds/missing.xml", &since, max_age).status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+}
@@ -0,0 +1,505 @@
+//! Structured screening times shared by every scraper.
+//! Scrapers parse the cinema's own date/time text into a [`Showtime`]; the
+//! human-readable Italian/English strings used in the feeds are derived from it.
+
+use crate::clock;
+use crate::language::Languages;
+use crate::projection::Format;
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveTime, TimeZone, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Language used when rendering showtimes (and the feed's channel language).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Locale {
+    #[serde(rename = "it")]
+    Italian,
+    #[serde(rename = "en")]
+    English,
+}
+
+impl Locale {
+    /// Language code as used by RSS `<language>`.
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::Italian => "it",
+            Locale::English => "en",
+        }
+    }
+}
+
+/// One screening of a film.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Showtime {
+    /// Start of the screening, in the cinema's local time zone.
+    pub start: DateTime<FixedOffset>,
+    /// End of the screening, when the cinema publishes it.
+    pub end: Option<DateTime<FixedOffset>>,
+    /// Hall, screen or venue, e.g. "Sala 3" or "Berlinale Palast".
+    pub hall: Option<String>,
+    /// Version / language tag as shown by the cinema, e.g. "V.O.S.".
+    pub version: Option<String>,
+    /// Audio and subtitles, from `version` or else the film's title and language.
+    #[serde(flatten)]
+    pub languages: Languages,
+    /// Projection formats, e.g. 35mm or IMAX 3D.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub formats: Vec<Format>,
+    /// Booking page for this specific screening.
+    pub ticket_url: Option<String>,
+}
+
+impl Showtime {
+    pub fn new(start: DateTime<FixedOffset>) -> Self {
+        Self {
+            start,
+            end: None,
+            hall: None,
+            version: None,
+            languages: Languages::default(),
+            formats: Vec::new(),
+            ticket_url: None,
+        }
+    }
+
+    /// Screening at local `date` + `time` in the cinema's time zone.
+    /// Returns None for local times that do not exist (DST gap).
+    pub fn at(date: NaiveDate, time: NaiveTime, tz: Tz) -> Option<Self> {
+        tz.from_local_datetime(&date.and_time(time))
+            .earliest()
+            .map(|dt| Self::new(dt.fixed_offset()))
+    }
+
+    pub fn with_hall(mut self, hall: Option<String>) -> Self {
+        self.hall = hall.filter(|h| !h.trim().is_empty());
+        self
+    }
+
+    /// Set the cinema's version tag and the languages and formats it names.
+    pub fn with_version(mut self, version: Option<String>) -> Self {
+        self.version = version
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+        if let Some(version) = self.version.clone() {
+            self.languages = Languages::parse(&version).or(&self.languages);
+            self = self.with_formats(&Format::detect(&version));
+        }
+        self
+    }
+
+    /// Add the projection formats `formats` names that the screening lacks.
+    pub fn with_formats(mut self, formats: &[Format]) -> Self {
+        for format in formats {
+            if !self.formats.contains(format) {
+                self.formats.push(*format);
+            }
+        }
+        self
+    }
+
+    /// "Lunedì 9 Febbraio ore 17:15" / "Monday, February 9, 5:15 PM", plus end time,
+    /// hall, formats and version when known.
+    pub fn display(&self, locale: Locale) -> String {
+        let start = self.start;
+        let day = format_day(start.date_naive(), locale);
+        let mut out = match locale {
+            Locale::Italian => format!("{} ore {}", day, start.format("%H:%M")),
+            Locale::English => format!("{}, {}", day, start.format("%-I:%M %p")),
+        };
+        if let Some(end) = self.end {
+            match locale {
+                Locale::Italian => out.push_str(&format!(" - {}", end.format("%H:%M"))),
+                Locale::English => out.push_str(&format!(" - {}", end.format("%-I:%M %p"))),
+            }
+        }
+        if let Some(ref hall) = self.hall {
+            out.push_str(&format!(", {}", hall));
+        }
+        for format in &self.formats {
+            out.push_str(&format!(", {}", format));
+        }
+        if let Some(version) = self.version_label(locale) {
+            out.push_str(&format!(" ({})", version));
+        }
+        out
+    }
+
+    /// "V.O. in inglese, sottotitoli in italiano" when the languages are known, else
+    /// the cinema's own tag.
+    pub fn version_label(&self, locale: Locale) -> Option<String> {
+        self.languages
+            .label(locale)
+            .or_else(|| self.version.clone())
+    }
+}
+
+impl fmt::Display for Showtime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.display(Locale::Italian))
+    }
+}
+
+/// "Lunedì 9 Febbraio" / "Monday, February 9"
+pub fn format_day(date: NaiveDate, locale: Locale) -> String {
+    match locale {
+        Locale::Italian => format!(
+            "{} {} {}",
+            IT_WEEKDAYS[date.weekday().num_days_from_monday() as usize],
+            date.day(),
+            IT_MONTHS[date.month0() as usize]
+        ),
+        Locale::English => format!(
+            "{}, {} {}",
+            date.format("%A"),
+            date.format("%B"),
+            date.day()
+        ),
+    }
+}
+
+const IT_WEEKDAYS: [&str; 7] = [
+    "Lunedì",
+    "Martedì",
+    "Mercoledì",
+    "Giovedì",
+    "Venerdì",
+    "Sabato",
+    "Domenica",
+];
+
+const IT_MONTHS: [&str; 12] = [
+    "Gennaio",
+    "Febbraio",
+    "Marzo",
+    "Aprile",
+    "Maggio",
+    "Giugno",
+    "Luglio",
+    "Agosto",
+    "Settembre",
+    "Ottobre",
+    "Novembre",
+    "Dicembre",
+];
+
+const EN_MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+/// Today's date in Italy, where most cinemas are; the reference for year inference.
+/// Not the machine's zone: CI runners are on UTC, a day behind until 01:00/02:00.
+pub fn today() -> NaiveDate {
+    today_in(chrono_tz::Europe::Rome)
+}
+
+/// Today's date in the cinema's time zone `tz`.
+pub fn today_in(tz: Tz) -> NaiveDate {
+    clock::now().with_timezone(&tz).date_naive()
+}
+
+/// Month number (1-12) for an Italian or English month name or abbreviation
+/// ("febbraio", "Feb", "Set.", "September").
+fn month_from_word(word: &str) -> Option<u32> {
+    let w = word.trim_end_matches('.').to_lowercase();
+    if w.len() < 3 {
+        return None;
+    }
+    let it = IT_MONTHS.iter().map(|m| m.to_lowercase());
+    for (i, name) in it.enumerate() {
+        if name.starts_with(&w) {
+            return Some(i as u32 + 1);
+        }
+    }
+    for (i, name) in EN_MONTHS.iter().enumerate() {
+        if name.starts_with(&w) || (w == "sept" && i == 8) {
+            return Some(i as u32 + 1);
+        }
+    }
+    None
+}
+
+/// Weekday for an Italian or English day name or abbreviation ("Sab", "martedì", "Fri").
+fn weekday_from_word(word: &str) -> Option<Weekday> {
+    let w = word.trim_end_matches('.').to_lowercase();
+    if w.len() < 3 {
+        return None;
+    }
+    const NAMES: [(&str, &str, Weekday); 7] = [
+        ("lunedì", "monday", Weekday::Mon),
+        ("martedì", "tuesday", Weekday::Tue),
+        ("mercoledì", "wednesday", Weekday::Wed),
+        ("giovedì", "thursday", Weekday::Thu),
+        ("venerdì", "friday", Weekday::Fri),
+        ("sabato", "saturday", Weekday::Sat),
+        ("domenica", "sunday", Weekday::Sun),
+    ];
+    NAMES
+        .iter()
+        .find(|(it, en, _)| it.starts_with(&w) || en.starts_with(&w))
+        .map(|(_, _, wd)| *wd)
+}
+
+/// Day of month from "9", "09", "6th", "22nd".
+fn day_from_word(word: &str) -> Option<u32> {
+    let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == '.');
+    if digits.is_empty() || digits.len() > 2 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let suffix = &word[digits.len()..].trim_end_matches('.').to_lowercase();
+    if !suffix.is_empty() && !["st", "nd", "rd", "th"].contains(&suffix.as_str()) {
+        return None;
+    }
+    digits.parse().ok().filter(|d| (1..=31).contains(d))
+}
+
+/// Year from a four-digit word in a plausible range.
+fn year_from_word(word: &str) -> Option<i32> {
+    if word.len() == 4 && word.chars().all(|c| c.is_ascii_digit()) {
+        word.parse().ok().filter(|y| (1990..=2100).contains(y))
+    } else {
+        None
+    }
+}
+
+/// Resolve a day/month without year to the first matching date on or after
+/// `reference` minus 90 days (programmes list current and upcoming screenings).
+fn infer_year(day: u32, month: u32, reference: NaiveDate) -> Option<NaiveDate> {
+    let earliest = reference - Duration::days(90);
+    (reference.year() - 1..=reference.year() + 1)
+        .filter_map(|y| NaiveDate::from_ymd_opt(y, month, day))
+        .find(|d| *d >= earliest)
+}
+
+/// Numeric dates: "21/08", "22/08/2026", "2026-08-22", "13.02.", "13.02.2026".
+fn parse_numeric_date(text: &str, reference: NaiveDate) -> Option<NaiveDate> {
+    for raw in text.split_whitespace() {
+        let token = raw.trim_matches(|c: char| c == ',' || c == '(' || c == ')' || c == '*');
+        let sep = if token.contains('/') {
+            '/'
+        } else if token.contains('-') {
+            '-'
+        } else if token.contains('.') {
+            '.'
+        } else {
+            continue;
+        };
+        let parts: Vec<&str> = token.split(sep).collect();
+        if parts.iter().take(2).any(|p| p.is_empty())
+            || !parts
+                .iter()
+                .all(|p| p.is_empty() || p.chars().all(|c| c.is_ascii_digit()))
+        {
+            continue;
+        }
+        // "17.30" is a time, not a date: dotted dates need a trailing dot or a year.
+        if sep == '.' && parts.len() < 3 {
+            continue;
+        }
+        let nums: Vec<u32> = parts.iter().filter_map(|p| p.parse().ok()).collect();
+        let date = match (sep, nums.as_slice()) {
+            ('-', [y, m, d]) if parts[0].len() == 4 => NaiveDate::from_ymd_opt(*y as i32, *m, *d),
+            ('-', _) => None,
+            (_, [d, m]) => infer_year(*d, *m, reference),
+            (_, [d, m, y]) => {
+                let y = if *y < 100 { 2000 + *y } else { *y };
+                NaiveDate::from_ymd_opt(y as i32, *m, *d)
+            }
+            _ => None,
+        };
+        if date.is_some() {
+            return date;
+        }
+    }
+    None
+}
+
+/// Parse the date part of a cinema's showtime text, e.g. "Lunedì 9 Febbraio",
+/// "martedì 11 Nov.", "Sabato 22/08/2026", "August 22nd Saturday", "Fri Feb 13"
+/// or "22 Sab". Missing years (and months) are inferred from `reference`,
+/// normally [`today`].
+pub fn parse_date(text: &str, reference: NaiveDate) -> Option<NaiveDate> {
+    if let Some(date) = parse_numeric_date(text, reference) {
+        return Some(date);
+    }
+
+    let words: Vec<&str> = text
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '-' || c == '·')
+        .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric() && c != '.'))
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    let year = words.iter().find_map(|w| year_from_word(w));
+    let weekday = words.iter().find_map(|w| weekday_from_word(w));
+
+    // Month names; "mar" is also Italian for Tuesday, so only trust it when nothing else matches.
+    let months: Vec<(usize, u32)> = words
+        .iter()
+        .enumerate()
+        .filter(|(_, w)| weekday_from_word(w).is_none() || w.len() > 3)
+        .filter_map(|(i, w)| month_from_word(w).map(|m| (i, m)))
+        .collect();
+    let ambiguous: Vec<(usize, u32)> = words
+        .iter()
+        .enumerate()
+        .filter(|(_, w)| w.trim_end_matches('.').eq_ignore_ascii_case("mar"))
+        .map(|(i, _)| (i, 3))
+        .collect();
+
+    // A lone "mar" next to a day number is usually martedì in a weekly listing ("10 Mar").
+    if months.is_empty()
+        && ambiguous.len() == 1
+        && let Some(date) = day_and_weekday(&words, weekday, reference)
+        && date <= reference + Duration::days(14)
+    {
+        return Some(date);
+    }
+
+    for (idx, month) in months.iter().chain(ambiguous.iter()) {
+        let day = idx
+            .checked_sub(1)
+            .and_then(|i| words.get(i))
+            .and_then(|w| day_from_word(w))
+            .or_else(|| words.get(idx + 1).and_then(|w| day_from_word(w)));
+        if let Some(day) = day {
+            return match year {
+                Some(y) => NaiveDate::from_ymd_opt(y, *month, day),
+                None => infer_year(day, *month, reference),
+            };
+        }
+    }
+
+    // Day number + weekday only ("22 Sab").
+    day_and_weekday(&words, weekday, reference)
+}
+
+/// The first date from a week before `reference` whose day number and weekday both match.
+fn day_and_weekday(
+    words: &[&str],
+    weekday: Option<Weekday>,
+    reference: NaiveDate,
+) -> Option<NaiveDate> {
+    let day = words.iter().find_map(|w| day_from_word(w))?;
+    let weekday = weekday?;
+    let start = reference - Duration::days(7);
+    (0..120)
+        .map(|offset| start + Duration::days(offset))
+        .find(|d| d.day() == day && d.weekday() == weekday)
+}
+
+/// Parse the first time of day in `text`: "17:15", "17.30", "ore 21.00", "7:30 pm", "10:00 am".
+pub fn parse_time(text: &str) -> Option<NaiveTime> {
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if !bytes[i].is_ascii_digit() || (i > 0 && bytes[i - 1].is_ascii_digit()) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        let hour_len = i - start;
+        if !(1..=2).contains(&hour_len)
+            || i + 2 >= bytes.len()
+            || (bytes[i] != b':' && bytes[i] != b'.')
+            || !bytes[i + 1].is_ascii_digit()
+            || !bytes[i + 2].is_ascii_digit()
+        {
+            continue;
+        }
+        let mut hour: u32 = text[start..i].parse().ok()?;
+        let minute: u32 = text[i + 1..i + 3].parse().ok()?;
+        // Skip dates like "13.02.2026" or "13.02."
+        if bytes
+            .get(i + 3)
+            .is_some_and(|b| b.is_ascii_digit() || *b == b'.')
+        {
+            i += 3;
+            continue;
+        }
+        let rest = text[i + 3..].trim_start().to_lowercase();
+        if rest.starts_with("pm") || rest.starts_with("p.m.") {
+            if hour < 12 {
+                hour += 12;
+            }
+        } else if (rest.starts_with("am") || rest.starts_with("a.m.")) && hour == 12 {
+            hour = 0;
+        }
+        if let Some(t) = NaiveTime::from_hms_opt(hour, minute, 0) {
+            return Some(t);
+        }
+        i += 3;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(y: i32, m: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, day).unwrap()
+    }
+
+    #[test]
+    fn parses_cinema_date_formats() {
+        let today = d(2026, 8, 20);
+        assert_eq!(parse_date("Sabato 22 Agosto", today), Some(d(2026, 8, 22)));
+        assert_eq!(parse_date("Venerdì  21/08", today), Some(d(2026, 8, 21)));
+        assert_eq!(parse_date("Sabato 22/08/2026", today), Some(d(2026, 8, 22)));
+        assert_eq!(parse_date("martedì 22 Set.", today), Some(d(2026, 9, 22)));
+        assert_eq!(
+            parse_date("August 22nd Saturday", today),
+            Some(d(2026, 8, 22))
+        );
+        assert_eq!(parse_date("Fri September 25", today), Some(d(2026, 9, 25)));
+        assert_eq!(parse_date("13.02.", d(2026, 2, 1)), Some(d(2026, 2, 13)));
+        assert_eq!(parse_date("22 Sab", today), Some(d(2026, 8, 22)));
+        assert_eq!(parse_date("Mar 10 Mar", today), Some(d(2027, 3, 10)));
+        assert_eq!(parse_date("10 Mar", d(2026, 2, 9)), Some(d(2026, 2, 10)));
+        assert_eq!(parse_date("22 Mar", d(2026, 2, 9)), Some(d(2026, 3, 22)));
+        assert_eq!(
+            parse_date("Lunedì 5 Gennaio", d(2026, 12, 28)),
+            Some(d(2027, 1, 5))
+        );
+        assert_eq!(parse_date("ore 17:15", today), None);
+    }
+
+    #[test]
+    fn parses_times() {
+        assert_eq!(parse_time("ore 17:15"), NaiveTime::from_hms_opt(17, 15, 0));
+        assert_eq!(
+            parse_time("21.30Ingresso"),
+            NaiveTime::from_hms_opt(21, 30, 0)
+        );
+        assert_eq!(parse_time("7:30 pm"), NaiveTime::from_hms_opt(19, 30, 0));
+        assert_eq!(parse_time("12:05 am"), NaiveTime::from_hms_opt(0, 5, 0));
+        assert_eq!(parse_time("13.02.2026"), None);
+    }
+
+    #[test]
+    fn displays_in_both_locales() {
+        let st = Showtime::at(
+            d(2026, 2, 9),
+            NaiveTime::from_hms_opt(17, 15, 0).unwrap(),
+            chrono_tz::Europe::Rome,
+        )
+        .unwrap();
+        assert_eq!(st.display(Locale::Italian), "Lunedì 9 Febbraio ore 17:15");
+        assert_eq!(st.display(Locale::English), "Monday, February 9, 5:15 PM");
+        assert_eq!(st.start.to_rfc3339(), "2026-02-09T17:15:00+01:00");
+    }
+}
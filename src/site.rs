@@ -0,0 +1,474 @@
+//! Static HTML programme site, written into `docs/` next to the feeds.
+//! Pages: `index.html` (all feeds), `programme/<feed>.html` (by day, then cinema) and
+//! `films/<slug>.html` (every cinema playing a film). Plain HTML + one stylesheet.
+
+use crate::{FeedFormat, FeedSpec, Film, Locale, Showtime, dedup, fnv1a, showtime, slug};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// A feed as shown on the site: its file name plus what it publishes.
+pub struct SiteFeed<'a> {
+    pub name: &'a str,
+    pub spec: FeedSpec<'a>,
+}
+
+struct Labels {
+    feeds: &'static str,
+    no_showtimes: &'static str,
+    minutes: &'static str,
+    directed_by: &'static str,
+    where_to_watch: &'static str,
+    updated: &'static str,
+    back: &'static str,
+}
+
+fn labels(locale: Locale) -> Labels {
+    match locale {
+        Locale::Italian => Labels {
+            feeds: "Programmazioni",
+            no_showtimes: "In programmazione (orari non disponibili)",
+            minutes: "min",
+            directed_by: "Regia di",
+            where_to_watch: "Dove vederlo",
+            updated: "Aggiornato",
+            back: "Tutte le programmazioni",
+        },
+        Locale::English => Labels {
+            feeds: "Programmes",
+            no_showtimes: "Now showing (no times published)",
+            minutes: "min",
+            directed_by: "Directed by",
+            where_to_watch: "Where to watch",
+            updated: "Updated",
+            back: "All programmes",
+        },
+    }
+}
+
+/// Render the whole site, its footers stamped `updated`. Returns `(path relative to
+/// docs/, contents)` pairs.
+pub fn render_site(feeds: &[SiteFeed], updated: DateTime<Utc>) -> Vec<(String, String)> {
+    let stamp = updated.format("%Y-%m-%d %H:%M UTC").to_string();
+    let mut files = vec![
+        ("style.css".to_string(), STYLE.to_string()),
+        ("index.html".to_string(), render_index(feeds, &stamp)),
+    ];
+    let pages = FilmPages::new(feeds);
+    for feed in feeds {
+        files.push((
+            format!("programme/{}.html", feed.name),
+            render_feed_page(feed, &pages, &stamp),
+        ));
+    }
+    for (key, showings) in &pages.pages {
+        files.push((
+            format!("films/{key}.html"),
+            render_film_page(showings, &stamp),
+        ));
+    }
+    files
+}
+
+/// One page per film, shared by every cinema and feed showing it (matched as in the
+/// by-film feeds), each under its own name.
+struct FilmPages<'a> {
+    pages: Vec<(String, Vec<Showing<'a>>)>,
+}
+
+/// A film as listed by one cinema of one feed.
+type Showing<'a> = (&'a SiteFeed<'a>, &'a str, &'a Film);
+
+impl<'a> FilmPages<'a> {
+    fn new(feeds: &'a [SiteFeed<'a>]) -> Self {
+        let mut groups: Vec<Vec<Showing>> = Vec::new();
+        for feed in feeds {
+            for (cinema, films) in feed.spec.sources {
+                for film in *films {
+                    match groups
+                        .iter_mut()
+                        .find(|g| dedup::same_film(g[0].2, film, dedup::RUNTIME_TOLERANCE))
+                    {
+                        Some(group) => group.push((feed, cinema, film)),
+                        None => groups.push(vec![(feed, cinema, film)]),
+                    }
+                }
+            }
+        }
+
+        let mut pages: Vec<(String, Vec<_>)> = Vec::new();
+        for showings in groups {
+            // "Odissea (V.O.)" -> "odissea"; a hash tells apart namesakes and titles
+            // with no Latin letters: "film-1a2b3c4d".
+            let first = showings[0].2;
+            let mut key = slug(&dedup::normalize_title(&first.title));
+            if key.is_empty() || pages.iter().any(|(k, _)| *k == key) {
+                let hash = fnv1a(first.title.bytes().chain([0]).chain(first.url.bytes()));
+                if key.is_empty() {
+                    key = "film".to_string();
+                }
+                key = format!("{key}-{:08x}", hash >> 32);
+            }
+            pages.push((key, showings));
+        }
+        Self { pages }
+    }
+
+    /// Page name of `film`, one of the entries the pages were built from.
+    fn key(&self, film: &Film) -> &str {
+        self.pages
+            .iter()
+            .find(|(_, showings)| showings.iter().any(|(_, _, f)| std::ptr::eq(*f, film)))
+            .map_or("", |(key, _)| key)
+    }
+}
+
+fn render_index(feeds: &[SiteFeed], stamp: &str) -> String {
+    let l = labels(Locale::Italian);
+    let mut body = format!("<h1>{}</h1>\n<ul class=\"feeds\">\n", l.feeds);
+    for feed in feeds {
+        let links: Vec<String> = FeedFormat::ALL
+            .iter()
+            .map(|f| {
+                let ext = f.extension();
+                format!("<a href=\"feeds/{}.{ext}\">{ext}</a>", feed.name)
+            })
+            .collect();
+        let _ = writeln!(
+            body,
+            "<li><a href=\"programme/{}.html\">{}</a> <span class=\"formats\">{}</span><br>{}</li>",
+            feed.name,
+            esc(feed.spec.title),
+            links.join(" · "),
+            esc(feed.spec.description)
+        );
+    }
+    body.push_str("</ul>\n");
+    page("it", "Cinema", "", &body, stamp, Locale::Italian)
+}
+
+/// One day of a feed: cinema index -> films with that day's screenings.
+type DayProgramme<'a> = BTreeMap<usize, Vec<(&'a Film, Vec<&'a Showtime>)>>;
+
+fn render_feed_page(feed: &SiteFeed, pages: &FilmPages, stamp: &str) -> String {
+    let locale = feed.spec.locale;
+    let l = labels(locale);
+    let mut days: BTreeMap<NaiveDate, DayProgramme> = BTreeMap::new();
+    let mut undated: Vec<(&str, &Film)> = Vec::new();
+    for (i, (cinema, films)) in feed.spec.sources.iter().enumerate() {
+        for film in *films {
+            let showtimes = film.showtimes.as_deref().unwrap_or_default();
+            if showtimes.is_empty() {
+                undated.push((cinema, film));
+                continue;
+            }
+            let mut per_day: BTreeMap<NaiveDate, Vec<&Showtime>> = BTreeMap::new();
+            for st in showtimes {
+                per_day.entry(st.start.date_naive()).or_default().push(st);
+            }
+            for (day, sts) in per_day {
+                days.entry(day)
+                    .or_default()
+                    .entry(i)
+                    .or_default()
+                    .push((film, sts));
+            }
+        }
+    }
+
+    let mut body = format!(
+        "<p><a href=\"../index.html\">← {}</a></p>\n<h1>{}</h1>\n<p>{}</p>\n",
+        l.back,
+        esc(feed.spec.title),
+        esc(feed.spec.description)
+    );
+    for (day, cinemas) in &days {
+        let _ = writeln!(body, "<h2>{}</h2>", showtime::format_day(*day, locale));
+        for (i, films) in cinemas {
+            let _ = writeln!(body, "<h3>{}</h3>", esc(feed.spec.sources[*i].0));
+            let mut films = films.clone();
+            films.sort_by_key(|(_, sts)| sts.first().map(|s| s.start));
+            for (film, sts) in films {
+                let times: Vec<String> = sts
+                    .iter()
+                    .map(|s| booking_link(s, esc(&short_time(s, locale))))
+                    .collect();
+                body.push_str(&film_card(film, pages.key(film), &times.join(", "), &l));
+            }
+        }
+    }
+    if !undated.is_empty() {
+        let _ = writeln!(body, "<h2>{}</h2>", l.no_showtimes);
+        for (cinema, film) in undated {
+            body.push_str(&film_card(film, pages.key(film), &esc(cinema), &l));
+        }
+    }
+    page(locale.code(), feed.spec.title, "../", &body, stamp, locale)
+}
+
+fn render_film_page(showings: &[Showing], stamp: &str) -> String {
+    let (first_feed, _, first) = showings[0];
+    let locale = first_feed.spec.locale;
+    let l = labels(locale);
+    let poster = showings
+        .iter()
+        .find_map(|(_, _, f)| f.poster_url.as_deref());
+    let synopsis = showings.iter().find_map(|(_, _, f)| f.synopsis.as_deref());
+    let running_time = showings.iter().find_map(|(_, _, f)| f.running_time);
+    let director = showings.iter().find_map(|(_, _, f)| f.director.as_deref());
+    let country = showings.iter().find_map(|(_, _, f)| f.country.as_deref());
+    let year = showings.iter().find_map(|(_, _, f)| f.year);
+    let genres = showings
+        .iter()
+        .map(|(_, _, f)| &f.genres)
+        .find(|g| !g.is_empty());
+
+    let mut body = format!(
+        "<p><a href=\"../index.html\">← {}</a></p>\n<h1>{}</h1>\n",
+        l.back,
+        esc(&first.title)
+    );
+    if let Some(src) = poster {
+        let _ = writeln!(
+            body,
+            "<img class=\"poster-large\" src=\"{}\" alt=\"\">",
+            esc(src)
+        );
+    }
+    let mut meta = Vec::new();
+    if let Some(director) = director {
+        meta.push(format!("{} {}", l.directed_by, esc(director)));
+    }
+    match (country, year) {
+        (Some(country), Some(year)) => meta.push(format!("{}, {year}", esc(country))),
+        (Some(country), None) => meta.push(esc(country)),
+        (None, Some(year)) => meta.push(year.to_string()),
+        (None, None) => {}
+    }
+    if let Some(genres) = genres {
+        meta.push(esc(&genres.join(", ")));
+    }
+    if let Some(minutes) = running_time {
+        meta.push(format!("{minutes} {}", l.minutes));
+    }
+    if !meta.is_empty() {
+        let _ = writeln!(body, "<p class=\"meta\">{}</p>", meta.join(" · "));
+    }
+    if let Some(text) = synopsis {
+        let _ = writeln!(body, "<p>{}</p>", esc(text));
+    }
+    let _ = writeln!(body, "<h2>{}</h2>\n<ul>", l.where_to_watch);
+    for (feed, cinema, film) in showings {
+        let times: Vec<String> = film
+            .showtimes
+            .iter()
+            .flatten()
+            .map(|s| booking_link(s, esc(&s.display(locale))))
+            .collect();
+        let _ = writeln!(
+            body,
+            "<li><a href=\"{}\">{}</a> (<a href=\"../programme/{}.html\">{}</a>){}</li>",
+            esc(&film.url),
+            esc(cinema),
+            feed.name,
+            esc(feed.spec.title),
+            if times.is_empty() {
+                String::new()
+            } else {
+                format!("<br>{}", times.join("<br>"))
+            }
+        );
+    }
+    body.push_str("</ul>\n");
+    page(locale.code(), &first.title, "../", &body, stamp, locale)
+}
+
+/// `times` is HTML: the screenings, each linked to its booking page.
+fn film_card(film: &Film, page: &str, times: &str, l: &Labels) -> String {
+    let mut out = String::from("<article class=\"film\">\n");
+    if let Some(ref poster) = film.poster_url {
+        let _ = writeln!(
+            out,
+            "<img src=\"{}\" alt=\"\" loading=\"lazy\">",
+            esc(poster)
+        );
+    }
+    let _ = writeln!(
+        out,
+        "<div>\n<h4><a href=\"../films/{page}.html\">{}</a></h4>",
+        esc(&film.title)
+    );
+    let mut meta = Vec::new();
+    if let Some(minutes) = film.running_time {
+        meta.push(format!("{minutes} {}", l.minutes));
+    }
+    if let Some(ref director) = film.director {
+        meta.push(format!("{} {}", l.directed_by, esc(director)));
+    }
+    if let Some(ref cast) = film.cast {
+        meta.push(esc(cast));
+    }
+    if !meta.is_empty() {
+        let _ = writeln!(out, "<p class=\"meta\">{}</p>", meta.join(" · "));
+    }
+    let _ = writeln!(out, "<p class=\"times\">{times}</p>\n</div>\n</article>");
+    out
+}
+
+/// `html` as a link to the screening's own booking page, when the cinema has one.
+pub(crate) fn booking_link(st: &Showtime, html: String) -> String {
+    match st.ticket_url {
+        Some(ref url) => format!("<a href=\"{}\">{html}</a>", esc(url)),
+        None => html,
+    }
+}
+
+/// "17:15 (Sala 2)" / "5:15 PM (Sala 2)"
+pub(crate) fn short_time(st: &Showtime, locale: Locale) -> String {
+    let mut out = match locale {
+        Locale::Italian => st.start.format("%H:%M").to_string(),
+        Locale::English => st.start.format("%-I:%M %p").to_string(),
+    };
+    if let Some(ref hall) = st.hall {
+        out.push_str(&format!(" ({hall})"));
+    }
+    for format in &st.formats {
+        out.push_str(&format!(" {format}"));
+    }
+    if let Some(version) = st.version_label(locale) {
+        out.push_str(&format!(" [{version}]"));
+    }
+    out
+}
+
+fn page(lang: &str, title: &str, root: &str, body: &str, stamp: &str, locale: Locale) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"{lang}\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<link rel=\"stylesheet\" href=\"{root}style.css\">\n</head>\n\
+         <body>\n<main>\n{body}</main>\n<footer>{} {stamp}</footer>\n</body>\n</html>\n",
+        esc(title),
+        labels(locale).updated
+    )
+}
+
+pub(crate) fn esc(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+const STYLE: &str = "\
+body { font-family: system-ui, sans-serif; margin: 0; background: #fafafa; color: #222; }
+main { max-width: 900px; margin: 0 auto; padding: 1rem; }
+a { color: #a11; }
+h2 { border-bottom: 2px solid #a11; padding-bottom: .2rem; margin-top: 2rem; }
+h3 { color: #555; }
+.film { display: flex; gap: 1rem; margin: .8rem 0; background: #fff; padding: .6rem; border-radius: 6px; }
+.film img { width: 80px; height: 120px; object-fit: cover; flex-shrink: 0; }
+.film h4 { margin: 0 0 .3rem; }
+.meta { color: #666; font-size: .9rem; margin: .2rem 0; }
+.times { font-weight: 600; margin: .2rem 0; }
+.poster-large { max-width: 240px; float: right; margin: 0 0 1rem 1rem; }
+.feeds li { margin: .8rem 0; }
+.formats { font-size: .8rem; }
+footer { text-align: center; color: #888; font-size: .8rem; padding: 2rem; }
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Grouping;
+    use chrono::TimeZone;
+
+    #[test]
+    fn groups_by_day_then_cinema_and_links_film_pages() {
+        let start = chrono_tz::Europe::Rome
+            .with_ymd_and_hms(2026, 10, 17, 20, 30, 0)
+            .unwrap()
+            .fixed_offset();
+        let film = Film {
+            title: "Stalker <1979>".to_string(),
+            url: "https://example.com/stalker".to_string(),
+            running_time: Some(161),
+            showtimes: Some(vec![Showtime {
+                ticket_url: Some("https://example.com/book?show=1&seat=a".to_string()),
+                ..Showtime::new(start)
+            }]),
+            ..Default::default()
+        };
+        let films = [film];
+        let sources: [(&str, &[Film]); 2] = [("Cinema A", &films), ("Cinema B", &films)];
+        let feeds = [SiteFeed {
+            name: "trieste",
+            spec: FeedSpec {
+                title: "Trieste",
+                link: "https://example.com/",
+                description: "Test",
+                locale: Locale::Italian,
+                sources: &sources,
+                grouping: Grouping::Cinema,
+            },
+        }];
+        let files = render_site(&feeds, Utc::now());
+        let get = |path: &str| &files.iter().find(|(p, _)| p == path).unwrap().1;
+
+        let programme = get("programme/trieste.html");
+        assert!(programme.contains("<h2>Sabato 17 Ottobre</h2>"));
+        assert!(programme.contains("<h3>Cinema B</h3>"));
+        assert!(programme.contains("Stalker &lt;1979&gt;"));
+        assert!(
+            programme.contains("<a href=\"https://example.com/book?show=1&amp;seat=a\">20:30</a>")
+        );
+        let film_page = get("films/stalker-1979.html");
+        assert_eq!(film_page.matches("<li>").count(), 2);
+    }
+
+    #[test]
+    fn every_film_gets_its_own_page() {
+        let film = |title: &str, year| Film {
+            title: title.to_string(),
+            url: format!("https://example.com/{title}"),
+            year,
+            ..Default::default()
+        };
+        let films = [
+            film("Сталкер", None),
+            film("東京物語", None),
+            film("Solaris", Some(1972)),
+            film("Solaris", Some(2002)),
+        ];
+        let sources: [(&str, &[Film]); 1] = [("Cinema A", &films)];
+        let feeds = [SiteFeed {
+            name: "test",
+            spec: FeedSpec {
+                title: "Test",
+                link: "https://example.com/",
+                description: "Test",
+                locale: Locale::Italian,
+                sources: &sources,
+                grouping: Grouping::Cinema,
+            },
+        }];
+        let files = render_site(&feeds, Utc::now());
+        let mut pages: Vec<&str> = files
+            .iter()
+            .map(|(path, _)| path.as_str())
+            .filter(|path| path.starts_with("films/"))
+            .collect();
+        assert_eq!(pages.len(), 4);
+        assert!(pages.contains(&"films/solaris.html"));
+        assert!(pages.iter().all(|p| *p != "films/film.html"));
+        pages.sort();
+        pages.dedup();
+        assert_eq!(pages.len(), 4);
+    }
+}
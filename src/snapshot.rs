@@ -0,0 +1,187 @@
+//! Last-good snapshot of every cinema, stored as `<state dir>/<feed>/<cinema>.json`.
+//! When a fresh scrape fails or returns suspiciously few films, the snapshot is
+//! published instead, with its films marked stale. A fresh scrape is also compared
+//! with the snapshot to find what changed since the previous run.
+
+use chrono::{DateTime, Utc};
+use cinema_scrape::{Film, clock, dedup, slug};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    pub fetched_at: DateTime<Utc>,
+    pub films: Vec<Film>,
+}
+
+impl Snapshot {
+    /// The snapshot's films, each marked as stale since the snapshot was taken.
+    pub fn into_stale_films(self) -> Vec<Film> {
+        let since = self.fetched_at;
+        self.films
+            .into_iter()
+            .map(|film| Film {
+                stale_since: Some(since),
+                ..film
+            })
+            .collect()
+    }
+}
+
+/// What changed at one cinema since its previous good run.
+#[derive(Debug, Default, PartialEq)]
+pub struct Changes {
+    /// Titles of films that were not in the previous run.
+    pub added: Vec<String>,
+    /// Titles of films of the previous run that are gone.
+    pub removed: Vec<String>,
+    /// Screenings added to films that were already showing.
+    pub new_showtimes: usize,
+}
+
+/// Compare a fresh scrape with the previous snapshot and set every film's
+/// `first_seen`: kept from the snapshot for films already there, `now` for new ones.
+/// Films are the same when they share a URL or a normalized title. Without a
+/// snapshot (first run) nothing counts as new.
+pub fn track(previous: Option<&Snapshot>, films: &mut [Film], now: DateTime<Utc>) -> Changes {
+    let Some(previous) = previous else {
+        for film in films.iter_mut() {
+            film.first_seen.get_or_insert(now);
+        }
+        return Changes::default();
+    };
+    let same = |a: &Film, b: &Film| {
+        a.url == b.url || dedup::normalize_title(&a.title) == dedup::normalize_title(&b.title)
+    };
+
+    let mut changes = Changes::default();
+    for film in films.iter_mut() {
+        match previous.films.iter().find(|old| same(old, film)) {
+            Some(old) => {
+                // Snapshots from before first_seen existed date from their own run.
+                film.first_seen = Some(old.first_seen.unwrap_or(previous.fetched_at));
+                let old_starts: Vec<_> = old.showtimes.iter().flatten().map(|s| s.start).collect();
+                changes.new_showtimes += film
+                    .showtimes
+                    .iter()
+                    .flatten()
+                    .filter(|s| !old_starts.contains(&s.start))
+                    .count();
+            }
+            None => {
+                film.first_seen = Some(now);
+                changes.added.push(film.title.clone());
+            }
+        }
+    }
+    changes.removed = previous
+        .films
+        .iter()
+        .filter(|old| !films.iter().any(|film| same(old, film)))
+        .map(|old| old.title.clone())
+        .collect();
+    changes
+}
+
+pub struct SnapshotStore {
+    dir: PathBuf,
+}
+
+impl SnapshotStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, feed: &str, cinema: &str) -> PathBuf {
+        self.dir.join(feed).join(format!("{}.json", slug(cinema)))
+    }
+
+    /// Previous good result, if any. A corrupt file is reported and ignored.
+    pub fn load(&self, feed: &str, cinema: &str) -> Option<Snapshot> {
+        let path = self.path(feed, cinema);
+        let text = fs::read_to_string(&path).ok()?;
+        match serde_json::from_str(&text) {
+            Ok(snapshot) => Some(snapshot),
+            Err(e) => {
+                eprintln!("Ignoring unreadable snapshot {}: {e}", path.display());
+                None
+            }
+        }
+    }
+
+    pub fn save(&self, feed: &str, cinema: &str, films: &[Film]) -> io::Result<()> {
+        let path = self.path(feed, cinema);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let snapshot = Snapshot {
+            fetched_at: clock::now(),
+            films: films.to_vec(),
+        };
+        fs::write(path, serde_json::to_string_pretty(&snapshot)?)
+    }
+}
+
+/// True when `found` films is below `min_ratio` of the previous good count.
+pub fn too_few(found: usize, previous: usize, min_ratio: f64) -> bool {
+    previous > 0 && (found as f64) < (previous as f64) * min_ratio
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugs_and_thresholds() {
+        assert_eq!(
+            slug("Cinema Ariston Trieste (La Cappella Underground)"),
+            "cinema-ariston-trieste-la-cappella-underground"
+        );
+        assert_eq!(slug("The Space Cinema - Silea"), "the-space-cinema-silea");
+        assert!(too_few(2, 14, 0.5));
+        assert!(!too_few(7, 14, 0.5));
+        assert!(!too_few(0, 0, 0.5));
+    }
+
+    #[test]
+    fn tracks_new_films_and_showtimes() {
+        use chrono::{Duration, TimeZone};
+        use cinema_scrape::Showtime;
+
+        let day = |d| Utc.with_ymd_and_hms(2026, 2, d, 6, 0, 0).unwrap();
+        let film = |title: &str, url: &str, hours: &[u32]| Film {
+            title: title.to_string(),
+            url: url.to_string(),
+            showtimes: Some(
+                hours
+                    .iter()
+                    .map(|&h| Showtime::new(day(10).fixed_offset() + Duration::hours(h.into())))
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        let mut first = vec![
+            film("Stalker", "/stalker", &[14]),
+            film("Solaris", "/solaris", &[18]),
+        ];
+        assert_eq!(track(None, &mut first, day(8)), Changes::default());
+        assert_eq!(first[0].first_seen, Some(day(8)));
+
+        let previous = Snapshot {
+            fetched_at: day(8),
+            films: first,
+        };
+        let mut films = vec![
+            film("STALKER", "/stalker-vo", &[14, 20]),
+            film("Lo specchio", "/specchio", &[16]),
+        ];
+        let changes = track(Some(&previous), &mut films, day(9));
+        assert_eq!(changes.added, ["Lo specchio"]);
+        assert_eq!(changes.removed, ["Solaris"]);
+        assert_eq!(changes.new_showtimes, 1);
+        assert_eq!(films[0].first_seen, Some(day(8)));
+        assert_eq!(films[1].first_seen, Some(day(9)));
+    }
+}
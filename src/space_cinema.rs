@@ -1,126 +1,107 @@
-use crate::{CinemaScraper, Film};
-use reqwest::{Client, header};
+use crate::{
+    CinemaScraper, Film, Format, HttpClient, Languages, ScrapeError, Showtime, merge_days,
+};
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveDateTime, TimeZone};
+use futures::future::join_all;
+use reqwest::header;
 use serde::Deserialize;
 
-/// Scraper for The Space Cinema (uses JSON API)
-pub struct SpaceCinemaScraper {
-    cinema_id: u32,
-    showing_date: String,
-}
+const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) \
+     AppleWebKit/537.36 (KHTML, like Gecko) Chrome/143.0.0.0 Safari/537.36";
 
-impl SpaceCinemaScraper {
-    pub fn new(cinema_id: u32, showing_date: String) -> Self {
-        Self {
-            cinema_id,
-            showing_date,
-        }
-    }
-}
+const API: &str = "https://www.thespacecinema.it/api/microservice/showings/cinemas";
 
-#[async_trait::async_trait]
-impl CinemaScraper for SpaceCinemaScraper {
-    async fn warm_up(&self, client: &Client) -> Result<(), Box<dyn std::error::Error>> {
-        // Warm-up request to get fresh cookies/tokens
-        client
-            .get("https://www.thespacecinema.it/")
-            .header(
-                header::USER_AGENT,
-                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) \
-                 AppleWebKit/537.36 (KHTML, like Gecko) \
-                 Chrome/143.0.0.0 Safari/537.36",
-            )
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+/// Parse the API's local ISO datetime ("2026-02-09T22:45:00") as Italian local time.
+fn parse_local_iso(s: &str) -> Option<DateTime<FixedOffset>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt);
     }
+    let naive = NaiveDateTime::parse_from_str(s.get(..19)?, "%Y-%m-%dT%H:%M:%S").ok()?;
+    chrono_tz::Europe::Rome
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|dt| dt.fixed_offset())
+}
 
-    async fn fetch_films(&self, client: &Client) -> Result<Vec<Film>, Box<dyn std::error::Error>> {
-        let api_url = format!(
-            "https://www.thespacecinema.it/api/microservice/showings/cinemas/{}/films",
-            self.cinema_id
-        );
+/// Warm-up request to get fresh cookies/tokens
+async fn warm_up(client: &HttpClient) -> Result<(), ScrapeError> {
+    client
+        .get("https://www.thespacecinema.it/")
+        .header(header::USER_AGENT, USER_AGENT)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
 
-        #[derive(Debug, Deserialize)]
-        struct ApiResponse {
-            result: Vec<ApiFilm>,
-        }
+/// A Space Cinema venue as listed by the showings API.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Venue {
+    pub id: u32,
+    pub name: String,
+    pub city: String,
+}
 
-        #[derive(Debug, Deserialize)]
-        #[allow(non_snake_case)]
-        struct ApiSession {
-            startTime: String,
-            endTime: String,
-        }
+impl Venue {
+    /// Whether `query` names this venue or its city, ignoring case, e.g. "silea",
+    /// "Roma" or "parco de medici".
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        !query.is_empty()
+            && (self.name.to_lowercase().contains(&query)
+                || self.city.to_lowercase().contains(&query))
+    }
+}
 
-        #[derive(Debug, Deserialize)]
-        struct ShowingGroup {
-            sessions: Option<Vec<ApiSession>>,
-        }
+/// Every venue of The Space Cinema in Italy.
+pub async fn fetch_venues(client: &HttpClient) -> Result<Vec<Venue>, ScrapeError> {
+    if let Err(e) = warm_up(client).await {
+        eprintln!("Warm-up failed for The Space Cinema: {e}");
+    }
+    let body = client
+        .get(API)
+        .header(header::USER_AGENT, USER_AGENT)
+        .header(header::ACCEPT, "application/json,text/javascript,*/*;q=0.1")
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    parse_venues(&body)
+}
 
-        #[derive(Debug, Deserialize)]
-        #[allow(non_snake_case)]
-        struct ApiFilm {
-            filmTitle: String,
-            filmUrl: String,
-            posterImageSrc: String,
-            cast: String,
-            releaseDate: String,
-            runningTime: i32,
-            synopsisShort: String,
-            showingGroups: Option<Vec<ShowingGroup>>,
-        }
+/// Scraper for The Space Cinema (uses JSON API)
+/// The API answers for one `showingDate` at a time, so each day of the range is
+/// queried separately and the sessions are merged per film.
+pub struct SpaceCinemaScraper {
+    cinema_id: u32,
+    first_date: NaiveDate,
+    days: u32,
+}
 
-        /// Extract "HH:MM" from ISO datetime like "2026-02-09T22:45:00"
-        fn time_part(s: &str) -> String {
-            s.split('T')
-                .nth(1)
-                .and_then(|t| t.get(..5))
-                .unwrap_or(s)
-                .to_string()
+impl SpaceCinemaScraper {
+    pub fn new(cinema_id: u32, first_date: NaiveDate, days: u32) -> Self {
+        Self {
+            cinema_id,
+            first_date,
+            days: days.max(1),
         }
+    }
 
-        /// Format ISO date "2026-02-09" as "09 Febbraio 2026"
-        fn format_date_italian(s: &str) -> String {
-            const MONTHS: [&str; 12] = [
-                "Gennaio",
-                "Febbraio",
-                "Marzo",
-                "Aprile",
-                "Maggio",
-                "Giugno",
-                "Luglio",
-                "Agosto",
-                "Settembre",
-                "Ottobre",
-                "Novembre",
-                "Dicembre",
-            ];
-            let date_str = s.get(..10).unwrap_or("");
-            let parts: Vec<&str> = date_str.split('-').collect();
-            if parts.len() != 3 {
-                return s.to_string();
-            }
-            let year = parts[0];
-            let month: usize = parts[1].parse().unwrap_or(0);
-            let day = parts[2];
-            if month == 0 || month > 12 {
-                return s.to_string();
-            }
-            format!("{} {} {}", day, MONTHS[month - 1], year)
-        }
+    async fn fetch_day(
+        &self,
+        client: &HttpClient,
+        date: NaiveDate,
+    ) -> Result<Vec<Film>, ScrapeError> {
+        let api_url = format!("{API}/{}/films", self.cinema_id);
+        let showing_date = date.format("%Y-%m-%dT00:00:00").to_string();
 
         let resp = client
             .get(&api_url)
-            .header(
-                header::USER_AGENT,
-                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) \
-                 AppleWebKit/537.36 (KHTML, like Gecko) \
-                 Chrome/143.0.0.0 Safari/537.36",
-            )
+            .header(header::USER_AGENT, USER_AGENT)
             .header(header::ACCEPT, "application/json,text/javascript,*/*;q=0.1")
             .query(&[
-                ("showingDate", self.showing_date.as_str()),
+                ("showingDate", showing_date.as_str()),
                 ("minEmbargoLevel", "3"),
                 ("includesSession", "true"),
                 ("includeSessionAttributes", "true"),
@@ -130,49 +111,227 @@ impl CinemaScraper for SpaceCinemaScraper {
             .error_for_status()?;
 
         let body = resp.text().await?;
-        let parsed: ApiResponse = serde_json::from_str(&body)?;
-
-        let films: Vec<Film> = parsed
-            .result
-            .into_iter()
-            .map(|f| {
-                let showtimes = f
-                    .showingGroups
-                    .map(|groups| {
-                        groups
-                            .into_iter()
-                            .filter_map(|g| g.sessions)
-                            .flatten()
-                            .map(|s| {
-                                let date = format_date_italian(&s.startTime);
-                                format!(
-                                    "{} ore {} - {}",
-                                    date,
-                                    time_part(&s.startTime),
-                                    time_part(&s.endTime)
-                                )
-                            })
-                            .collect::<Vec<_>>()
-                    })
-                    .filter(|v: &Vec<String>| !v.is_empty());
-
-                Film {
-                    title: f.filmTitle,
-                    url: f.filmUrl,
-                    poster_url: Some(f.posterImageSrc),
-                    cast: Some(f.cast),
-                    release_date: Some(f.releaseDate),
-                    running_time: Some(f.runningTime as u32),
-                    synopsis: Some(f.synopsisShort),
-                    showtimes,
-                }
-            })
-            .collect();
+        parse_films(&body)
+    }
+}
 
-        Ok(films)
+#[async_trait::async_trait]
+impl CinemaScraper for SpaceCinemaScraper {
+    async fn warm_up(&self, client: &HttpClient) -> Result<(), ScrapeError> {
+        warm_up(client).await
+    }
+
+    async fn fetch_films(&self, client: &HttpClient) -> Result<Vec<Film>, ScrapeError> {
+        let days = join_all(
+            (0..self.days)
+                .map(|d| self.fetch_day(client, self.first_date + Duration::days(d.into()))),
+        )
+        .await;
+
+        // The first day must work; a later day that fails only shortens the range.
+        let mut films = Vec::new();
+        for (i, day) in days.into_iter().enumerate() {
+            match day {
+                Ok(day_films) => films.extend(day_films),
+                Err(e) if i == 0 => return Err(e),
+                Err(e) => eprintln!(
+                    "Space Cinema {}: skipping {}: {e}",
+                    self.cinema_id,
+                    self.first_date + Duration::days(i as i64)
+                ),
+            }
+        }
+        Ok(merge_days(films))
     }
 
     fn rss_filename(&self) -> String {
         format!("docs/feeds/space_cinema_{}.xml", self.cinema_id)
     }
 }
+
+#[derive(Debug, Deserialize)]
+struct ApiResponse {
+    result: Vec<ApiFilm>,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(non_snake_case)]
+struct ApiSession {
+    startTime: String,
+    endTime: String,
+    /// Requested with `includeSessionAttributes`, e.g. "Versione Originale", "IMAX".
+    #[serde(default)]
+    attributes: Vec<ApiAttribute>,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(non_snake_case)]
+struct ApiAttribute {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    shortName: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShowingGroup {
+    sessions: Option<Vec<ApiSession>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(non_snake_case)]
+struct ApiFilm {
+    filmTitle: String,
+    filmUrl: String,
+    posterImageSrc: String,
+    cast: String,
+    releaseDate: String,
+    runningTime: i32,
+    synopsisShort: String,
+    showingGroups: Option<Vec<ShowingGroup>>,
+}
+
+/// Films and sessions from the showings API response.
+fn parse_films(body: &str) -> Result<Vec<Film>, ScrapeError> {
+    let parsed: ApiResponse = serde_json::from_str(body)?;
+
+    let films: Vec<Film> = parsed
+        .result
+        .into_iter()
+        .map(|f| {
+            let showtimes = f
+                .showingGroups
+                .map(|groups| {
+                    groups
+                        .into_iter()
+                        .filter_map(|g| g.sessions)
+                        .flatten()
+                        .filter_map(|s| {
+                            let mut showtime = Showtime::new(parse_local_iso(&s.startTime)?);
+                            showtime.end = parse_local_iso(&s.endTime);
+                            let names: Vec<&str> = s
+                                .attributes
+                                .iter()
+                                .map(|a| {
+                                    if a.name.is_empty() {
+                                        &a.shortName
+                                    } else {
+                                        &a.name
+                                    }
+                                })
+                                .map(|a| a.as_str())
+                                .collect();
+                            // "IMAX", "3D", next to "Dolby Atmos" and the languages.
+                            let formats: Vec<Format> =
+                                names.iter().flat_map(|a| Format::detect(a)).collect();
+                            let version: Vec<&str> = names
+                                .into_iter()
+                                .filter(|a| !Languages::parse(a).is_empty())
+                                .collect();
+                            Some(
+                                showtime
+                                    .with_version(Some(version.join(", ")))
+                                    .with_formats(&formats),
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .filter(|v: &Vec<Showtime>| !v.is_empty());
+
+            Film {
+                title: f.filmTitle,
+                url: f.filmUrl,
+                poster_url: Some(f.posterImageSrc),
+                cast: Some(f.cast),
+                release_date: Some(f.releaseDate),
+                running_time: Some(f.runningTime as u32),
+                synopsis: Some(f.synopsisShort),
+                showtimes,
+                ..Default::default()
+            }
+        })
+        .collect();
+
+    Ok(films)
+}
+
+#[derive(Debug, Deserialize)]
+struct VenuesResponse {
+    result: Vec<ApiCinema>,
+}
+
+/// The API sends ids as strings ("1009") on some endpoints and numbers on others.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ApiId {
+    Number(u32),
+    Text(String),
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(non_snake_case)]
+struct ApiCinema {
+    cinemaId: ApiId,
+    cinemaName: String,
+    #[serde(default)]
+    city: Option<String>,
+}
+
+/// Venues from the cinemas endpoint, sorted by city then name. Entries whose id is
+/// not numeric cannot be scraped and are skipped.
+fn parse_venues(body: &str) -> Result<Vec<Venue>, ScrapeError> {
+    let parsed: VenuesResponse = serde_json::from_str(body)?;
+    let mut venues: Vec<Venue> = parsed
+        .result
+        .into_iter()
+        .filter_map(|c| {
+            let id = match c.cinemaId {
+                ApiId::Number(id) => id,
+                ApiId::Text(id) => id.trim().parse().ok()?,
+            };
+            Some(Venue {
+                id,
+                name: c.cinemaName.trim().to_string(),
+                city: c.city.unwrap_or_default().trim().to_string(),
+            })
+        })
+        .collect();
+    venues.sort_by(|a, b| (&a.city, &a.name).cmp(&(&b.city, &b.name)));
+    Ok(venues)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn parses_fixture() {
+        let films = parse_films(&fixtures::load("space_cinema", "films.json")).unwrap();
+        fixtures::assert_films("space_cinema", &films);
+
+        // The API repeats a film on every day it is shown; the week keeps it once.
+        let week = merge_days([films.clone(), films.clone()].concat());
+        assert_eq!(week.len(), films.len());
+        for (merged, film) in week.iter().zip(&films) {
+            assert_eq!(merged.showtimes, film.showtimes);
+        }
+    }
+
+    #[test]
+    fn parses_venues() {
+        let venues = parse_venues(&fixtures::load("space_cinema", "cinemas.json")).unwrap();
+        let silea: Vec<&Venue> = venues.iter().filter(|v| v.matches("SILEA")).collect();
+        assert_eq!(silea.len(), 1);
+        assert_eq!(silea[0].id, 1009);
+        assert_eq!(silea[0].city, "Treviso");
+        // A city picks every venue in it.
+        let roma: Vec<u32> = venues
+            .iter()
+            .filter(|v| v.matches("roma"))
+            .map(|v| v.id)
+            .collect();
+        assert_eq!(roma, vec![1022, 1014]);
+        assert!(!venues.iter().any(|v| v.matches("  ")));
+    }
+}
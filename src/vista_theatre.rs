@@ -1,8 +1,9 @@
 //! Scraper for Vista Theater Hollywood.
 //! Schedule: https://www.vistatheaterhollywood.com/ (#now-playing)
 
-use crate::{CinemaScraper, Film};
-use reqwest::{Client, header};
+use crate::{CinemaScraper, Film, Format, HttpClient, ScrapeError, Showtime, showtime};
+use chrono::NaiveDate;
+use reqwest::header;
 use scraper::{ElementRef, Html, Selector};
 
 const HOME_URL: &str = "https://www.vistatheaterhollywood.com/";
@@ -21,7 +22,7 @@ impl VistaTheatreScraper {
 
 #[async_trait::async_trait]
 impl CinemaScraper for VistaTheatreScraper {
-    async fn fetch_films(&self, client: &Client) -> Result<Vec<Film>, Box<dyn std::error::Error>> {
+    async fn fetch_films(&self, client: &HttpClient) -> Result<Vec<Film>, ScrapeError> {
         let resp = client
             .get(&self.url)
             .header(header::USER_AGENT, USER_AGENT)
@@ -30,7 +31,10 @@ impl CinemaScraper for VistaTheatreScraper {
             .await?
             .error_for_status()?;
         let body = resp.text().await?;
-        Ok(parse_homepage(&body))
+        Ok(parse_homepage(
+            &body,
+            showtime::today_in(chrono_tz::America::Los_Angeles),
+        ))
     }
 
     fn rss_filename(&self) -> String {
@@ -38,7 +42,7 @@ impl CinemaScraper for VistaTheatreScraper {
     }
 }
 
-fn parse_homepage(html: &str) -> Vec<Film> {
+fn parse_homepage(html: &str, today: NaiveDate) -> Vec<Film> {
     let doc = Html::parse_document(html);
     let row_sel = match Selector::parse("#now-playing .shows__grid > .shows__grid--row") {
         Ok(s) => s,
@@ -58,12 +62,9 @@ fn parse_homepage(html: &str) -> Vec<Film> {
         let showtimes = row
             .select(&times_sel)
             .next()
-            .map(parse_showtimes)
+            .map(|cell| parse_showtimes(cell, today))
             .unwrap_or_default();
-        let ticket_url = row
-            .select(&times_sel)
-            .next()
-            .and_then(first_ticket_url);
+        let ticket_url = row.select(&times_sel).next().and_then(first_ticket_url);
         let Some(content_cell) = row.select(&content_sel).next() else {
             continue;
         };
@@ -107,7 +108,7 @@ fn parse_homepage(html: &str) -> Vec<Film> {
     films
 }
 
-fn parse_showtimes(times_cell: ElementRef<'_>) -> Vec<String> {
+fn parse_showtimes(times_cell: ElementRef<'_>, today: NaiveDate) -> Vec<Showtime> {
     let time_link_sel = match Selector::parse("a.card__button") {
         Ok(s) => s,
         Err(_) => return Vec::new(),
@@ -133,31 +134,31 @@ fn parse_showtimes(times_cell: ElementRef<'_>) -> Vec<String> {
         } else if tag == "p" && !class.contains("month") && !class.contains("text__size-2") {
             weekday = child_text(&child);
         } else if tag == "div" && class.contains("times") {
+            let date = showtime::parse_date(&format!("{} {} {}", month, day_num, weekday), today);
             for a in child.select(&time_link_sel) {
-                let time = child_text(&a);
-                if time.is_empty() {
-                    continue;
+                if let Some(date) = date
+                    && let Some(time) = showtime::parse_time(&child_text(&a))
+                    && let Some(st) = Showtime::at(date, time, chrono_tz::America::Los_Angeles)
+                {
+                    // Each time button is the Veezi purchase page for that screening.
+                    let ticket_url = a.value().attr("href").map(|s| s.trim().to_string());
+                    showtimes.push(Showtime { ticket_url, ..st });
                 }
-                let date_part = format!("{} {} {}", month, day_num, weekday)
-                    .trim()
-                    .to_string();
-                showtimes.push(format!("{} ore {}", date_part, time));
             }
         }
     }
     showtimes
 }
 
+/// Link for the film itself: the purchase page of its first screening.
 fn first_ticket_url(times_cell: ElementRef<'_>) -> Option<String> {
-    Selector::parse("a.card__button")
-        .ok()
-        .and_then(|sel| {
-            times_cell
-                .select(&sel)
-                .next()
-                .and_then(|a| a.value().attr("href"))
-                .map(|s| s.to_string())
-        })
+    Selector::parse("a.card__button").ok().and_then(|sel| {
+        times_cell
+            .select(&sel)
+            .next()
+            .and_then(|a| a.value().attr("href"))
+            .map(|s| s.to_string())
+    })
 }
 
 fn poster_for_panel(content_cell: ElementRef<'_>, panel_index: usize) -> Option<String> {
@@ -182,7 +183,7 @@ fn poster_for_panel(content_cell: ElementRef<'_>, panel_index: usize) -> Option<
 fn parse_film_block(
     block: ElementRef<'_>,
     poster_url: Option<String>,
-    showtimes: &[String],
+    showtimes: &[Showtime],
     panel_index: usize,
     content_cell: ElementRef<'_>,
     ticket_url: Option<&str>,
@@ -212,6 +213,7 @@ fn parse_film_block(
 
     let mut year = None;
     let mut running_time = None;
+    let mut formats = Vec::new();
     let mut director = None;
     let mut writers = None;
     let mut stars = None;
@@ -254,15 +256,14 @@ fn parse_film_block(
                 let (y, mins) = parse_meta_line(&text);
                 year = y;
                 running_time = mins;
+                // "1974 | 2h 10m | 35mm Presentation"
+                formats = Format::detect(&text);
             }
         }
         i += 1;
     }
 
     let mut cast_parts = Vec::new();
-    if let Some(ref d) = director.filter(|s| !s.is_empty()) {
-        cast_parts.push(format!("Director: {}", d));
-    }
     if let Some(ref w) = writers.filter(|s| !s.is_empty()) {
         cast_parts.push(format!("Writers: {}", w));
     }
@@ -290,27 +291,29 @@ fn parse_film_block(
         url,
         poster_url,
         cast,
-        release_date: year,
+        director,
+        year,
         running_time,
         synopsis,
         showtimes: if showtimes.is_empty() {
             None
         } else {
-            Some(showtimes.to_vec())
+            Some(
+                showtimes
+                    .iter()
+                    .map(|st| st.clone().with_formats(&formats))
+                    .collect(),
+            )
         },
+        ..Default::default()
     })
 }
 
-fn parse_meta_line(line: &str) -> (Option<String>, Option<u32>) {
+fn parse_meta_line(line: &str) -> (Option<u32>, Option<u32>) {
     let parts: Vec<&str> = line.split('|').map(|s| s.trim()).collect();
-    let year = parts.first().and_then(|p| {
-        p.chars()
-            .take(4)
-            .collect::<String>()
-            .parse::<u32>()
-            .ok()
-            .map(|y| y.to_string())
-    });
+    let year = parts
+        .first()
+        .and_then(|p| p.chars().take(4).collect::<String>().parse::<u32>().ok());
     let running_time = parts.get(1).and_then(|p| parse_duration(p));
     (year, running_time)
 }
@@ -342,6 +345,7 @@ fn child_text(el: &ElementRef<'_>) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fixtures;
 
     #[test]
     fn parse_duration_works() {
@@ -376,14 +380,23 @@ mod tests {
           </div>
         </div>
         </div></section></body></html>"#;
-        let films = parse_homepage(html);
+        let films = parse_homepage(html, fixtures::today());
         assert_eq!(films.len(), 1);
         let f = &films[0];
         assert_eq!(f.title, "L.A. Story");
-        assert_eq!(f.release_date.as_deref(), Some("1991"));
+        assert_eq!(f.year, Some(1991));
         assert_eq!(f.running_time, Some(95));
-        assert!(f.cast.as_ref().unwrap().contains("Mick Jackson"));
+        assert_eq!(f.director.as_deref(), Some("Mick Jackson"));
+        assert!(f.cast.as_ref().unwrap().contains("Victoria Tennant"));
         assert!(f.synopsis.as_ref().unwrap().contains("freeway billboard"));
-        assert!(f.showtimes.as_ref().unwrap()[0].contains("10:00 am"));
+        let st = &f.showtimes.as_ref().unwrap()[0];
+        assert_eq!(st.start.format("%m-%d %H:%M").to_string(), "06-06 10:00");
+        assert_eq!(st.formats, [Format::Mm35]);
+    }
+
+    #[test]
+    fn parses_fixture() {
+        let body = fixtures::load("vista_theatre", "home.html");
+        fixtures::assert_films("vista_theatre", &parse_homepage(&body, fixtures::today()));
     }
 }
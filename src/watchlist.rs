@@ -0,0 +1,187 @@
+//! Personal watchlist: films, directors, actors or keywords to look out for at every
+//! cinema. Entries come from the `[[watch]]` tables of the config; every film matching
+//! one is published in the watchlist feed.
+//!
+//! Matching ignores case and accents and works on whole words, so `director = "Kubrick"`
+//! finds "Stanley Kubrick" and `cast = "Hüller"` finds "Sandra Huller". Cinema names
+//! are matched on any part, so `cinema = "New Bev"` is the New Beverly Cinema.
+
+use crate::Film;
+use crate::dedup::{self, normalize_title};
+use crate::filter::contains_words;
+use serde::Deserialize;
+use std::fmt;
+
+/// One watchlist entry as written in the config. Every field given must match, so
+/// `director = "Wong Kar-wai"` with `cinema = "New Bev"` is "Wong Kar-wai at New Bev".
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WatchRule {
+    /// Looked for in the title.
+    pub title: Option<String>,
+    /// Looked for in [`Film::director`], or the "Regia:"/"Director:" part of the cast.
+    pub director: Option<String>,
+    /// Looked for in the cast.
+    pub cast: Option<String>,
+    /// Looked for in the title, director, cast and synopsis.
+    pub keyword: Option<String>,
+    /// Only at cinemas whose name contains this.
+    pub cinema: Option<String>,
+}
+
+impl fmt::Display for WatchRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts = [
+            ("title", &self.title),
+            ("director", &self.director),
+            ("cast", &self.cast),
+            ("keyword", &self.keyword),
+            ("at", &self.cinema),
+        ];
+        let parts: Vec<String> = parts
+            .iter()
+            .filter_map(|(label, value)| value.as_ref().map(|v| format!("{label} \"{v}\"")))
+            .collect();
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// Normalized [`WatchRule`]s.
+pub struct Watchlist {
+    rules: Vec<(WatchRule, Normalized)>,
+}
+
+struct Normalized {
+    title: Option<String>,
+    director: Option<String>,
+    cast: Option<String>,
+    keyword: Option<String>,
+    cinema: Option<String>,
+}
+
+impl Watchlist {
+    /// Fails on an entry with nothing to look for but a cinema, which would match
+    /// its whole programme.
+    pub fn new(rules: &[WatchRule]) -> Result<Self, String> {
+        let norm = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(normalize_title)
+                .filter(|v| !v.is_empty())
+        };
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let normalized = Normalized {
+                    title: norm(&rule.title),
+                    director: norm(&rule.director),
+                    cast: norm(&rule.cast),
+                    keyword: norm(&rule.keyword),
+                    cinema: norm(&rule.cinema),
+                };
+                let looks_for_something = normalized.title.is_some()
+                    || normalized.director.is_some()
+                    || normalized.cast.is_some()
+                    || normalized.keyword.is_some();
+                if looks_for_something {
+                    Ok((rule.clone(), normalized))
+                } else {
+                    Err(format!(
+                        "watchlist entry {{{rule}}} needs a title, director, cast or keyword"
+                    ))
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rules })
+    }
+
+    /// The first entry `film`, shown at `cinema`, matches.
+    pub fn check(&self, cinema: &str, film: &Film) -> Option<&WatchRule> {
+        let cinema = normalize_title(cinema);
+        let title = normalize_title(&film.title);
+        let director = dedup::director(film).unwrap_or_default();
+        let cast = normalize_title(film.cast.as_deref().unwrap_or_default());
+        let synopsis = normalize_title(film.synopsis.as_deref().unwrap_or_default());
+        let matches = |wanted: &Option<String>, fields: &[&str]| {
+            wanted
+                .as_deref()
+                .is_none_or(|w| fields.iter().any(|field| contains_words(field, w)))
+        };
+
+        self.rules
+            .iter()
+            .find(|(_, n)| {
+                n.cinema.as_deref().is_none_or(|c| cinema.contains(c))
+                    && matches(&n.title, &[&title])
+                    && matches(&n.director, &[&director])
+                    && matches(&n.cast, &[&cast])
+                    && matches(&n.keyword, &[&title, &director, &cast, &synopsis])
+            })
+            .map(|(rule, _)| rule)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn film(title: &str, director: Option<&str>, cast: Option<&str>) -> Film {
+        Film {
+            title: title.to_string(),
+            url: String::new(),
+            cast: cast.map(String::from),
+            director: director.map(String::from),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn matches_directors_cast_and_cinemas() {
+        let rules = vec![
+            WatchRule {
+                director: Some("Kubrick".to_string()),
+                ..WatchRule::default()
+            },
+            WatchRule {
+                director: Some("Wong Kar-wai".to_string()),
+                cinema: Some("New Bev".to_string()),
+                ..WatchRule::default()
+            },
+            WatchRule {
+                cast: Some("Sandra Hüller".to_string()),
+                ..WatchRule::default()
+            },
+        ];
+        let watchlist = Watchlist::new(&rules).unwrap();
+
+        let barry = film(
+            "Barry Lyndon",
+            None,
+            Some("Regia: Stanley KUBRICK | Ryan O'Neal"),
+        );
+        assert_eq!(
+            watchlist
+                .check("Cinema Edera", &barry)
+                .map(|r| r.to_string()),
+            Some("director \"Kubrick\"".to_string())
+        );
+        let mood = film("In the Mood for Love", Some("Wong Kar-wai"), None);
+        assert!(watchlist.check("New Beverly Cinema", &mood).is_some());
+        assert!(watchlist.check("Vista Theater", &mood).is_none());
+        let huller = film(
+            "Rose",
+            Some("Markus Schleinzer"),
+            Some("Sandra Huller, Caro Braun"),
+        );
+        assert!(watchlist.check("Berlinale", &huller).is_some());
+        // Whole words only: "Kubrickiana" is not Kubrick.
+        let doc = film("Kubrickiana", Some("Someone Else"), None);
+        assert!(watchlist.check("Cinema Edera", &doc).is_none());
+
+        let cinema_only = [WatchRule {
+            cinema: Some("New Bev".to_string()),
+            ..WatchRule::default()
+        }];
+        assert!(Watchlist::new(&cinema_only).is_err());
+    }
+}